@@ -0,0 +1,43 @@
+//! Records how the crate was built, for the metadata of the runner's results.
+
+use std::env;
+use std::process::Command;
+
+/// Runs `program` and returns its trimmed output if it succeeds.
+fn output(program: &str, args: &[&str]) -> Option<String> {
+    Command::new(program)
+        .args(args)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|stdout| stdout.trim().to_owned())
+}
+
+fn main() {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_owned());
+    println!(
+        "cargo:rustc-env=POLYBENCH_RUSTC_VERSION={}",
+        output(&rustc, &["--version"]).unwrap_or_else(|| "unknown".to_owned())
+    );
+
+    let rustflags = env::var("CARGO_ENCODED_RUSTFLAGS").unwrap_or_default();
+    println!(
+        "cargo:rustc-env=POLYBENCH_RUSTFLAGS={}",
+        rustflags.split('\x1f').collect::<Vec<_>>().join(" ")
+    );
+
+    let revision = match output("git", &["rev-parse", "HEAD"]) {
+        Some(revision)
+            if output("git", &["status", "--porcelain"]).is_some_and(|s| !s.is_empty()) =>
+        {
+            revision + "-dirty"
+        }
+        Some(revision) => revision,
+        None => "unknown".to_owned(),
+    };
+    println!("cargo:rustc-env=POLYBENCH_GIT_REVISION={}", revision);
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/index");
+    println!("cargo:rerun-if-env-changed=CARGO_ENCODED_RUSTFLAGS");
+}
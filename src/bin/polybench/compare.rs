@@ -0,0 +1,180 @@
+use crate::results::Measurement;
+use polybench_rs::stats::{Outliers, Speedup, Summary};
+
+/// How two results files are compared.
+pub struct Thresholds {
+    /// The confidence level of the speedup intervals, e.g. 0.95.
+    pub confidence: f64,
+    /// The slowdown, as a fraction of the baseline time, above which a significant change counts
+    /// as a regression.
+    pub max_regression: f64,
+}
+
+/// Returns whether two measurements are of the same kernel and size, run the same way.
+fn same(a: &Measurement, b: &Measurement) -> bool {
+    a.kernel == b.kernel
+        && a.dataset == b.dataset
+        && a.dims == b.dims
+        && a.layout == b.layout
+        && a.pages == b.pages
+        && a.timer == b.timer
+        && a.padding == b.padding
+}
+
+fn describe(m: &Measurement) -> String {
+    format!(
+        "{} {:?} ({} layout, {} pages, {} timer, padding {})",
+        m.kernel, m.dims, m.layout, m.pages, m.timer, m.padding
+    )
+}
+
+/// Returns a note if `m` appears more than once in `all`, the measurements of `file`.
+fn duplicates(m: &Measurement, file: &str, all: &[Measurement]) -> Option<String> {
+    let count = all.iter().filter(|other| same(m, other)).count();
+    (count > 1).then(|| {
+        format!(
+            "{} appears {} times in the {}, only the first is compared",
+            describe(m),
+            count,
+            file
+        )
+    })
+}
+
+/// Pairs every measurement of the baseline with the one of the candidate it is compared with,
+/// and returns the pairs and notes on the measurements found in only one of the files or more
+/// than once in either, of which only the first is compared.
+fn pair<'a>(
+    baseline: &'a [Measurement],
+    candidate: &'a [Measurement],
+) -> (Vec<(&'a Measurement, &'a Measurement)>, Vec<String>) {
+    let mut pairs = Vec::new();
+    let mut notes = Vec::new();
+    for (i, old) in baseline.iter().enumerate() {
+        if baseline[..i].iter().any(|other| same(old, other)) {
+            continue;
+        }
+        notes.extend(duplicates(old, "baseline", baseline));
+        match candidate.iter().find(|new| same(old, new)) {
+            Some(new) => {
+                notes.extend(duplicates(new, "candidate", candidate));
+                pairs.push((old, new));
+            }
+            None => notes.push(format!("{} is missing from the candidate", describe(old))),
+        }
+    }
+    for (i, new) in candidate.iter().enumerate() {
+        let first = !candidate[..i].iter().any(|other| same(new, other));
+        if first && !baseline.iter().any(|old| same(old, new)) {
+            notes.extend(duplicates(new, "candidate", candidate));
+            notes.push(format!("{} is missing from the baseline", describe(new)));
+        }
+    }
+    (pairs, notes)
+}
+
+/// Prints the speedup of every kernel and size found in both files, run with the same layout,
+/// pages, timer and padding, and returns `true` if any of them regressed by more than
+/// `thresholds.max_regression`.
+pub fn compare(
+    baseline: &[Measurement],
+    candidate: &[Measurement],
+    thresholds: &Thresholds,
+) -> bool {
+    let median = |m: &Measurement| Summary::new(m.samples.clone(), Outliers::Keep).median;
+
+    let (pairs, notes) = pair(baseline, candidate);
+    for note in notes {
+        eprintln!("note: {}", note);
+    }
+    let mut regressed = false;
+    for (old, new) in pairs {
+        let speedup = Speedup::new(&old.samples, &new.samples, thresholds.confidence);
+        let verdict = if !speedup.is_significant() {
+            "within noise"
+        } else if speedup.estimate > 1.0 {
+            "faster"
+        } else if 1.0 / speedup.estimate - 1.0 > thresholds.max_regression {
+            regressed = true;
+            "REGRESSION"
+        } else {
+            "slower"
+        };
+        println!(
+            "{:<14} | {:<10} | {:<30} | {:<24} | {:.7} s -> {:.7} s | {:.3}x [{:.3}, {:.3}] | {}",
+            old.kernel,
+            old.dataset,
+            format!("{:?}", old.dims),
+            format!("{} {} {} {}", old.layout, old.pages, old.timer, old.padding),
+            median(old).as_secs_f64(),
+            median(new).as_secs_f64(),
+            speedup.estimate,
+            speedup.low,
+            speedup.high,
+            verdict
+        );
+    }
+    regressed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn measurement(kernel: &str, layout: &str, scale: u32) -> Measurement {
+        Measurement {
+            kernel: kernel.to_owned(),
+            dataset: "MINI".to_owned(),
+            dims: vec![20, 25, 30],
+            layout: layout.to_owned(),
+            pages: "default".to_owned(),
+            timer: "instant".to_owned(),
+            padding: "0".to_owned(),
+            samples: (10..20)
+                .map(|ms| Duration::from_millis(ms) * scale)
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn pairs_match_the_setup() {
+        let baseline = [
+            measurement("gemm", "row-major", 1),
+            measurement("gemm", "tiled-64", 1),
+            measurement("gemm", "row-major", 2),
+        ];
+        let candidate = [
+            measurement("gemm", "tiled-64", 2),
+            measurement("atax", "row-major", 1),
+        ];
+        let (pairs, notes) = pair(&baseline, &candidate);
+        assert_eq!(1, pairs.len());
+        assert!(std::ptr::eq(&baseline[1], pairs[0].0));
+        assert!(std::ptr::eq(&candidate[0], pairs[0].1));
+        assert_eq!(3, notes.len(), "{:?}", notes);
+        assert!(notes[0].starts_with("gemm [20, 25, 30] (row-major layout"));
+        assert!(notes[0].ends_with("appears 2 times in the baseline, only the first is compared"));
+        assert!(notes[1].ends_with("is missing from the candidate"));
+        assert!(notes[2].starts_with("atax") && notes[2].ends_with("missing from the baseline"));
+    }
+
+    #[test]
+    fn regressions_fail_the_comparison() {
+        let thresholds = Thresholds {
+            confidence: 0.95,
+            max_regression: 0.05,
+        };
+        let baseline = [measurement("gemm", "row-major", 1)];
+        let slower = [measurement("gemm", "row-major", 2)];
+        assert!(compare(&baseline, &slower, &thresholds));
+        assert!(!compare(&slower, &baseline, &thresholds));
+        assert!(!compare(&baseline, &baseline, &thresholds));
+        // A regression of another layout is not compared.
+        assert!(!compare(
+            &baseline,
+            &[measurement("gemm", "tiled-64", 2)],
+            &thresholds
+        ));
+    }
+}
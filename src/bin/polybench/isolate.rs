@@ -0,0 +1,235 @@
+//! Runs every kernel and size in a forked child, so that it starts from the runner's pristine
+//! heap, page tables and transparent huge pages, and so that a crash only fails that job.
+
+use crate::Sampled;
+use polybench_rs::stats::{Sampling, Stop, Summary};
+use std::fmt::{self, Write as _};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::os::unix::io::FromRawFd;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::Duration;
+
+/// Why an isolated job did not deliver results.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Failure {
+    /// The child was killed by a signal, e.g. `SIGSEGV`, `SIGABRT` or the `SIGKILL` of the OOM
+    /// killer.
+    Signal(i32),
+    /// The child exited with a non-zero status, e.g. 101 after a panic.
+    Exit(i32),
+    /// The child ran longer than the timeout and was killed.
+    Timeout(Duration),
+    /// The child could not be started or its results could not be read.
+    Error(String),
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::Signal(libc::SIGKILL) => {
+                f.write_str("crashed: killed by SIGKILL, possibly out of memory")
+            }
+            Failure::Signal(signal) => {
+                let name = unsafe { std::ffi::CStr::from_ptr(libc::strsignal(*signal)) };
+                write!(
+                    f,
+                    "crashed: killed by signal {} ({})",
+                    signal,
+                    name.to_string_lossy()
+                )
+            }
+            Failure::Exit(status) => write!(f, "crashed: exited with status {}", status),
+            Failure::Timeout(timeout) => {
+                write!(f, "timeout: ran longer than {} s", timeout.as_secs_f64())
+            }
+            Failure::Error(error) => write!(f, "error: {}", error),
+        }
+    }
+}
+
+/// Serializes the results as lines of a keyword followed by values.
+fn encode(sampled: &Sampled) -> String {
+    let mut out = String::new();
+    let nanos: Vec<_> = sampled
+        .summary
+        .samples
+        .iter()
+        .map(|s| s.as_nanos().to_string())
+        .collect();
+    writeln!(out, "samples {}", nanos.join(" ")).unwrap();
+    writeln!(out, "stop {}", sampled.summary.stop).unwrap();
+    writeln!(out, "pages {}", sampled.pages).unwrap();
+    for (name, counts) in &sampled.counts {
+        let counts: Vec<_> = counts.iter().map(u64::to_string).collect();
+        writeln!(out, "counter {} {}", name, counts.join(" ")).unwrap();
+    }
+    out
+}
+
+fn decode(text: &str, sampling: &Sampling) -> Option<Sampled> {
+    let mut samples = None;
+    let mut stop = None;
+    let mut pages = None;
+    let mut counts = Vec::new();
+    for line in text.lines() {
+        let mut words = line.split(' ');
+        match words.next()? {
+            "samples" => {
+                samples = words
+                    .filter(|w| !w.is_empty())
+                    .map(|w| w.parse().ok().map(Duration::from_nanos))
+                    .collect::<Option<Vec<_>>>();
+            }
+            "stop" => {
+                stop = Some(match words.next()? {
+                    "count" => Stop::Count,
+                    "converged" => Stop::Converged,
+                    "max-samples" => Stop::MaxSamples,
+                    "max-time" => Stop::MaxTime,
+                    _ => return None,
+                })
+            }
+            "pages" => pages = Some(crate::parse_pages(words.next()?).ok()?),
+            "counter" => {
+                let name = words.next()?.to_owned();
+                let values = words
+                    .filter(|w| !w.is_empty())
+                    .map(|w| w.parse().ok())
+                    .collect::<Option<_>>()?;
+                counts.push((name, values));
+            }
+            _ => return None,
+        }
+    }
+    let mut summary = Summary::new(samples?, sampling.outliers);
+    summary.stop = stop?;
+    Some(Sampled {
+        summary,
+        counts,
+        pages: pages?,
+    })
+}
+
+/// Runs `job` in a forked child and returns what it measured.
+///
+/// The runner is single-threaded, so the child may do anything the parent could. Everything
+/// the parent wants to keep must be flushed before, because the child leaves with `_exit`.
+///
+/// The child is killed by `SIGALRM` once it has run for `timeout`.
+pub fn run<F>(sampling: &Sampling, timeout: Option<Duration>, job: F) -> Result<Sampled, Failure>
+where
+    F: FnOnce() -> Sampled,
+{
+    let error =
+        |what: &str| Failure::Error(format!("{}: {}", what, std::io::Error::last_os_error()));
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+        return Err(error("cannot create a pipe"));
+    }
+    let (mut reader, mut writer) =
+        unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) };
+
+    match unsafe { libc::fork() } {
+        -1 => Err(error("cannot fork")),
+        0 => {
+            drop(reader);
+            if let Some(timeout) = timeout {
+                let timer = libc::itimerval {
+                    it_interval: libc::timeval {
+                        tv_sec: 0,
+                        tv_usec: 0,
+                    },
+                    it_value: libc::timeval {
+                        tv_sec: timeout.as_secs() as libc::time_t,
+                        tv_usec: timeout.subsec_micros() as libc::suseconds_t,
+                    },
+                };
+                unsafe { libc::setitimer(libc::ITIMER_REAL, &timer, std::ptr::null_mut()) };
+            }
+            let status = match panic::catch_unwind(AssertUnwindSafe(job)) {
+                Ok(sampled) => match writer.write_all(encode(&sampled).as_bytes()) {
+                    Ok(()) => 0,
+                    Err(_) => 1,
+                },
+                // The panic message has been printed already.
+                Err(_) => 101,
+            };
+            unsafe { libc::_exit(status) }
+        }
+        child => {
+            drop(writer);
+            let mut text = String::new();
+            let read = reader.read_to_string(&mut text);
+            let mut status = 0;
+            if unsafe { libc::waitpid(child, &mut status, 0) } != child {
+                return Err(error("cannot wait for the child"));
+            }
+            if libc::WIFSIGNALED(status) {
+                return Err(match (libc::WTERMSIG(status), timeout) {
+                    (libc::SIGALRM, Some(timeout)) => Failure::Timeout(timeout),
+                    (signal, _) => Failure::Signal(signal),
+                });
+            }
+            match libc::WEXITSTATUS(status) {
+                0 => {}
+                status => return Err(Failure::Exit(status)),
+            }
+            read.ok()
+                .and_then(|_| decode(&text, sampling))
+                .ok_or_else(|| Failure::Error("the child sent malformed results".to_owned()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polybench_rs::pages::Pages;
+    use polybench_rs::stats::Outliers;
+
+    #[test]
+    fn encode_decode() {
+        let sampling = Sampling {
+            outliers: Outliers::TrimMinMax,
+            ..Sampling::SINGLE
+        };
+        let samples = [3, 1, 2].iter().map(|&n| Duration::from_nanos(n)).collect();
+        let mut summary = Summary::new(samples, sampling.outliers);
+        summary.stop = Stop::MaxTime;
+        let sampled = Sampled {
+            summary,
+            counts: vec![("cycles".to_owned(), vec![7, 8, 9])],
+            pages: Pages::Transparent,
+        };
+        let decoded = decode(&encode(&sampled), &sampling).unwrap();
+        assert_eq!(sampled.summary, decoded.summary);
+        assert_eq!(sampled.counts, decoded.counts);
+        assert_eq!(sampled.pages, decoded.pages);
+        assert!(decode("samples 1 x\nstop count\n", &sampling).is_none());
+    }
+
+    #[test]
+    fn failures() {
+        let sampling = Sampling::SINGLE;
+        let sampled = run(&sampling, None, || Sampled {
+            summary: Summary::new(vec![Duration::from_nanos(42)], Outliers::Keep),
+            counts: Vec::new(),
+            pages: Pages::Default,
+        });
+        assert_eq!(
+            vec![Duration::from_nanos(42)],
+            sampled.unwrap().summary.samples
+        );
+
+        let aborted = run(&sampling, None, || std::process::abort());
+        assert_eq!(Failure::Signal(libc::SIGABRT), aborted.err().unwrap());
+        let exited = run(&sampling, None, || std::process::exit(3));
+        assert_eq!(Failure::Exit(3), exited.err().unwrap());
+        let timeout = Duration::from_millis(50);
+        let timed_out = run(&sampling, Some(timeout), || loop {
+            std::thread::sleep(Duration::from_secs(1));
+        });
+        assert_eq!(Failure::Timeout(timeout), timed_out.err().unwrap());
+    }
+}
@@ -0,0 +1,238 @@
+use polybench_rs::datamining::*;
+pub use polybench_rs::dataset::Dataset;
+use polybench_rs::layout::{ColumnMajor, Morton, RowMajor, Tiled};
+use polybench_rs::linear_algebra::blas::*;
+use polybench_rs::linear_algebra::kernels::*;
+use polybench_rs::linear_algebra::solvers::*;
+use polybench_rs::medley::*;
+use polybench_rs::stencils::*;
+use polybench_rs::timer::Timer;
+use std::convert::TryInto;
+use std::fmt;
+use std::time::Duration;
+
+/// How the arrays with two or three dimensions of a kernel are stored, see
+/// [`polybench_rs::layout`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layout {
+    /// The row-major arrays of `bench` and `bench_dyn`, which index rows without looking them up.
+    Default,
+    RowMajor,
+    ColumnMajor,
+    Tiled8,
+    Tiled16,
+    Tiled32,
+    Tiled64,
+    Morton,
+}
+
+impl Layout {
+    pub const ALL: [Layout; 8] = [
+        Layout::Default,
+        Layout::RowMajor,
+        Layout::ColumnMajor,
+        Layout::Tiled8,
+        Layout::Tiled16,
+        Layout::Tiled32,
+        Layout::Tiled64,
+        Layout::Morton,
+    ];
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Layout::Default => "default",
+            Layout::RowMajor => "row-major",
+            Layout::ColumnMajor => "column-major",
+            Layout::Tiled8 => "tiled-8",
+            Layout::Tiled16 => "tiled-16",
+            Layout::Tiled32 => "tiled-32",
+            Layout::Tiled64 => "tiled-64",
+            Layout::Morton => "morton",
+        })
+    }
+}
+
+/// Runs a kernel with dimensions given at runtime and its arrays in a layout.
+pub type RunLayout = fn(&[usize], Layout, &dyn Timer) -> Duration;
+
+/// A single benchmark that can be dispatched by name.
+pub struct Kernel {
+    pub name: &'static str,
+    /// Path of the module the kernel lives in, e.g. `linear_algebra/blas`.
+    pub category: &'static str,
+    /// Returns the dimensions of a dataset in the order of the const generics of `bench`.
+    pub dims: fn(Dataset) -> &'static [usize],
+    pub run: fn(Dataset, &dyn Timer) -> Duration,
+    /// Runs the kernel with dimensions given at runtime; their number must equal [`Kernel::arity`].
+    pub run_dyn: fn(&[usize], &dyn Timer) -> Duration,
+    /// Runs the kernel like [`Kernel::run_dyn`] with its arrays in the given layout, or `None` if
+    /// it has no arrays with two or three dimensions.
+    pub run_layout: Option<RunLayout>,
+    /// Returns the number of operations one run performs with the given dimensions.
+    pub ops: fn(&[usize]) -> u64,
+    /// What [`Kernel::ops`] counts: `FLOP` for floating-point kernels, `op` for integer ones.
+    pub unit: &'static str,
+    /// Returns the name of the kernel's `DataType`, e.g. `f64`.
+    pub data_type: fn() -> &'static str,
+    /// Returns the bytes of the arrays one run allocates with the given dimensions.
+    pub footprint: fn(&[usize]) -> u64,
+}
+
+impl Kernel {
+    /// Returns the number of dimensions the kernel takes.
+    pub fn arity(&self) -> usize {
+        (self.dims)(Dataset::Mini).len()
+    }
+
+    /// Returns the layout the kernel's arrays are stored in when `layout` is requested.
+    pub fn layout(&self, layout: Layout) -> Layout {
+        match self.run_layout {
+            Some(_) => layout,
+            None => Layout::Default,
+        }
+    }
+
+    /// Returns `true` if `target` names this kernel, one of its categories or `all`.
+    pub fn matches(&self, target: &str) -> bool {
+        target == "all"
+            || self.name == target
+            || self.category == target
+            || self.category.split('/').any(|c| c == target)
+    }
+}
+
+macro_rules! kernel {
+    ($name:literal, $category:literal, $module:ident) => {
+        kernel!($name, $category, $module, flops, "FLOP")
+    };
+    ($name:literal, $category:literal, $module:ident, $ops:ident, $unit:literal) => {
+        kernel!(@ $name, $category, $module, $ops, $unit, Some(|dims, layout, timer| {
+            let dims = dims.try_into().unwrap();
+            match layout {
+                Layout::Default => $module::bench_dyn(dims, timer),
+                Layout::RowMajor => $module::bench_layout::<RowMajor>(dims, timer),
+                Layout::ColumnMajor => $module::bench_layout::<ColumnMajor>(dims, timer),
+                Layout::Tiled8 => $module::bench_layout::<Tiled<8>>(dims, timer),
+                Layout::Tiled16 => $module::bench_layout::<Tiled<16>>(dims, timer),
+                Layout::Tiled32 => $module::bench_layout::<Tiled<32>>(dims, timer),
+                Layout::Tiled64 => $module::bench_layout::<Tiled<64>>(dims, timer),
+                Layout::Morton => $module::bench_layout::<Morton>(dims, timer),
+            }
+        }))
+    };
+    // For kernels with one-dimensional arrays only, which have no `bench_layout`.
+    ($name:literal, $category:literal, $module:ident, one_dimensional) => {
+        kernel!(@ $name, $category, $module, flops, "FLOP", None)
+    };
+    (@ $name:literal, $category:literal, $module:ident, $ops:ident, $unit:literal, $run_layout:expr) => {
+        Kernel {
+            name: $name,
+            category: $category,
+            dims: $module::dims,
+            run: $module::bench_dataset,
+            run_dyn: |dims, timer| $module::bench_dyn(dims.try_into().unwrap(), timer),
+            run_layout: $run_layout,
+            ops: |dims| $module::$ops(dims.try_into().unwrap()),
+            unit: $unit,
+            data_type: std::any::type_name::<$module::DataType>,
+            footprint: |dims| $module::footprint(dims.try_into().unwrap()),
+        }
+    };
+}
+
+pub static KERNELS: &[Kernel] = &[
+    kernel!("correlation", "datamining", correlation),
+    kernel!("covariance", "datamining", covariance),
+    kernel!("gemm", "linear_algebra/blas", gemm),
+    kernel!("gemver", "linear_algebra/blas", gemver),
+    kernel!("gesummv", "linear_algebra/blas", gesummv),
+    kernel!("symm", "linear_algebra/blas", symm),
+    kernel!("syr2k", "linear_algebra/blas", syr2k),
+    kernel!("syrk", "linear_algebra/blas", syrk),
+    kernel!("trmm", "linear_algebra/blas", trmm),
+    kernel!("2mm", "linear_algebra/kernels", _2mm),
+    kernel!("3mm", "linear_algebra/kernels", _3mm),
+    kernel!("atax", "linear_algebra/kernels", atax),
+    kernel!("bicg", "linear_algebra/kernels", bicg),
+    kernel!("doitgen", "linear_algebra/kernels", doitgen),
+    kernel!("mvt", "linear_algebra/kernels", mvt),
+    kernel!("cholesky", "linear_algebra/solvers", cholesky),
+    kernel!("durbin", "linear_algebra/solvers", durbin, one_dimensional),
+    kernel!("gramschmidt", "linear_algebra/solvers", gramschmidt),
+    kernel!("lu", "linear_algebra/solvers", lu),
+    kernel!("ludcmp", "linear_algebra/solvers", ludcmp),
+    kernel!("trisolv", "linear_algebra/solvers", trisolv),
+    kernel!("deriche", "medley", deriche),
+    kernel!("floyd_warshall", "medley", floyd_warshall, ops, "op"),
+    kernel!("nussinov", "medley", nussinov, ops, "op"),
+    kernel!("adi", "stencils", adi),
+    kernel!("fdtd_2d", "stencils", fdtd_2d),
+    kernel!("heat_3d", "stencils", heat_3d),
+    kernel!("jacobi_1d", "stencils", jacobi_1d, one_dimensional),
+    kernel!("jacobi_2d", "stencils", jacobi_2d),
+    kernel!("seidel_2d", "stencils", seidel_2d),
+];
+
+/// Looks up a kernel by its exact name.
+pub fn find(name: &str) -> Option<&'static Kernel> {
+    KERNELS.iter().find(|k| k.name == name)
+}
+
+#[test]
+fn names_are_unique() {
+    for (i, a) in KERNELS.iter().enumerate() {
+        for b in &KERNELS[i + 1..] {
+            assert_ne!(a.name, b.name);
+        }
+    }
+}
+
+#[test]
+fn ops_take_the_dataset_dims() {
+    for kernel in KERNELS {
+        assert!(
+            (kernel.ops)((kernel.dims)(Dataset::Large)) > 0,
+            "{}",
+            kernel.name
+        );
+    }
+}
+
+#[test]
+fn ops_accept_the_smallest_dims() {
+    // The command line accepts any dimension from 1, below what the stencils update.
+    for kernel in KERNELS {
+        (kernel.ops)(&vec![1; kernel.arity()]);
+    }
+}
+
+#[test]
+fn footprints() {
+    let gemm = find("gemm").unwrap();
+    assert_eq!(
+        (20 * 25 + 20 * 30 + 30 * 25) * 8,
+        (gemm.footprint)(&[20, 25, 30])
+    );
+    for kernel in KERNELS {
+        assert!((kernel.footprint)((kernel.dims)(Dataset::Mini)) > 0);
+    }
+}
+
+#[test]
+fn layouts_run_every_kernel() {
+    let timer = polybench_rs::timer::FnTimer::nanos(|| 0);
+    for kernel in KERNELS {
+        let dims = (kernel.dims)(Dataset::Mini);
+        for &layout in &Layout::ALL {
+            if let Some(run_layout) = kernel.run_layout {
+                run_layout(dims, layout, &timer);
+            }
+        }
+    }
+    assert_eq!(
+        Layout::Default,
+        find("durbin").unwrap().layout(Layout::Morton)
+    );
+}
@@ -0,0 +1,836 @@
+mod compare;
+#[cfg(target_os = "linux")]
+mod isolate;
+mod kernels;
+mod preflight;
+mod results;
+#[cfg(target_os = "linux")]
+mod sched;
+
+use compare::Thresholds;
+use kernels::{Dataset, Kernel, Layout, KERNELS};
+#[cfg(target_os = "linux")]
+use polybench_rs::counters::{Counted, Counters};
+#[cfg(feature = "print-result")]
+use polybench_rs::dump;
+use polybench_rs::ndarray;
+use polybench_rs::pages::{self, Pages};
+use polybench_rs::stats::{Adaptive, Outliers, Sampling, Stop, Summary};
+use polybench_rs::timer::{self, Calibrated, Timer};
+use polybench_rs::util::{self, CacheFlush, Prefault};
+use preflight::Environment;
+use results::{Format, Host, Record, Run, Writer};
+#[cfg(target_os = "linux")]
+use sched::Scheduling;
+use std::env;
+use std::fs;
+use std::process;
+use std::time::Duration;
+
+const USAGE: &str = "\
+Usage:
+    polybench list
+    polybench run <kernel|category|all>... [--dataset <name|all>]... [--dims <d1,d2,...>]...
+    polybench run --dims-file <file>
+    polybench compare <baseline> <candidate> [--confidence <level>] [--max-regression <percent>]
+
+Options of `run`:
+    --samples <n>       measure every kernel n times (default 1)
+    --warmup <n>        execute every kernel n times before measuring (default 0)
+    --outliers <keep|minmax|tukey>
+                        discard no samples, the fastest and the slowest sample, or the samples
+                        outside Tukey's fences (default keep)
+    --polybench         5 samples with the fastest and the slowest discarded, as in PolyBench/C;
+                        --samples, --warmup and --outliers override it
+    --target <percent>  keep sampling until the 95% confidence interval of the median is narrower
+                        than the given percentage of the median, with --samples as the minimum
+    --max-samples <n>   stop --target after n samples (default 1000)
+    --max-time <s>      stop --target once the samples add up to s seconds (default 10)
+    --timer <instant|monotonic|monotonic-raw|thread-cputime|rdtsc>
+                        the clock the kernels are measured with (default instant); `rdtsc` is
+                        calibrated against `monotonic-raw` for 100 ms
+    --cache-flush <none|llc|sweep:<KiB>|clflush>
+                        leave the caches warm, sweep a buffer of the size of the last-level cache
+                        or of the given size, or flush the kernel's arrays with clflush(opt)
+                        before every sample (default llc)
+    --prefault <none|zero|willneed|populate>
+                        leave the kernel's arrays untouched, write them with zeros, advise them
+                        with MADV_WILLNEED or populate them with MADV_POPULATE_WRITE before every
+                        sample, so that page faults are not measured (default zero)
+    --pages <default|hugetlb-2m|hugetlb-1g|thp|4k>
+                        back the kernel's arrays with the pages of the global allocator, explicit
+                        2 MiB or 1 GiB pages of hugetlbfs, which fall back to transparent huge
+                        pages if none are free, transparent huge pages, or 4 KiB pages with
+                        transparent huge pages disabled (Linux only, default default)
+    --align <32|64|4096>
+                        align the kernel's arrays to the given number of bytes (default 32)
+    --padding <n>       store n unused elements after every row of the kernel's arrays with two
+                        or three dimensions, like PolyBench/C's POLYBENCH_PADDING_FACTOR, to break
+                        up cache-set conflicts (default 0); this and --align run the datasets with
+                        the runtime-sized arrays of `bench_dyn`
+    --layout <default|row-major|column-major|tiled-8|tiled-16|tiled-32|tiled-64|morton>
+                        store the kernel's arrays with two or three dimensions in row-major order,
+                        in row-major order with the rows looked up like in the other layouts,
+                        column-major, in row-major tiles of the given size or along the Z-order
+                        curve; all but `default` run the runtime-sized arrays of `bench_layout`
+                        and exclude --padding (default default)
+    --counters          count cycles, instructions, L1d and LLC load misses and branch misses of
+                        every sample with perf_event_open, or task-clock, page faults and context
+                        switches where hardware events are unavailable, and report them together
+                        with getrusage deltas (Linux only)
+    --cpu <n>           pin the measuring thread to CPU n (Linux only)
+    --fifo              run with SCHED_FIFO at the highest priority, like PolyBench/C's
+                        POLYBENCH_LINUX_FIFO_SCHEDULER, if permitted (Linux only)
+    --isolate           measure every kernel and size in a forked child process, so that they do
+                        not share a heap and page tables and a crash only fails one entry (Linux
+                        only)
+    --timeout <s>       kill a kernel that runs longer than s seconds for all its samples and
+                        record it as a timeout; implies --isolate (Linux only)
+    --memory-budget <MiB>
+                        skip kernels whose arrays need more memory than this (default: the
+                        MemAvailable of /proc/meminfo)
+    --strict            refuse to run if the pre-flight check of the environment warns
+    --jsonl <file>      also write the results as JSON Lines, one object per kernel and size
+    --csv <file>        also write the results as CSV, one row per kernel and size
+
+Options of `compare`:
+    --confidence <level>
+                        the confidence level of the speedup intervals (default 0.95)
+    --max-regression <percent>
+                        the slowdown above which a significant change fails the comparison
+                        (default 5)
+
+`compare` reads two results files written with `--jsonl` or `--csv` and matches their kernels by
+name, size, layout, pages, timer and padding. It exits with status 1 if any kernel is
+significantly slower in the candidate by more than the maximum regression.
+
+Categories are the module paths of the kernels, e.g. `datamining`, `linear_algebra`, `blas`.
+The datasets are the PolyBench/C 4.2 presets mini, small, medium, large and extralarge; `large`
+is the default as in PolyBench/C.
+
+`--dims` runs the kernels with dimensions chosen at runtime, given in the same order as the
+dataset dimensions printed by `list`. Every line of a dims file names a kernel followed by its
+dimensions, e.g. `gemm 1000,1100,1200`; `#` starts a comment.";
+
+/// The problem size of a single run.
+#[derive(Clone, Debug)]
+enum Size {
+    Dataset(Dataset),
+    Dims(Vec<usize>),
+}
+
+struct Job {
+    kernel: &'static Kernel,
+    size: Size,
+}
+
+/// What a job measured: its samples, the counts of every counter in every sample and the pages
+/// its arrays were backed with.
+struct Sampled {
+    summary: Summary,
+    counts: Vec<(String, Vec<u64>)>,
+    pages: Pages,
+}
+
+enum Command {
+    List,
+    Run {
+        jobs: Vec<Job>,
+        sampling: Sampling,
+        timer_name: String,
+        timer: Box<dyn Timer>,
+        outputs: Vec<(String, Format)>,
+        cache_flush: CacheFlush,
+        prefault: Prefault,
+        pages: Pages,
+        alignment: usize,
+        padding: usize,
+        layout: Layout,
+        counters: bool,
+        cpu: Option<usize>,
+        fifo: bool,
+        strict: bool,
+        isolate: bool,
+        timeout: Option<Duration>,
+        memory_budget: Option<u64>,
+    },
+    Compare {
+        baseline: String,
+        candidate: String,
+        thresholds: Thresholds,
+    },
+}
+
+fn parse_dims(arg: &str) -> Result<Vec<usize>, String> {
+    arg.split(',')
+        .map(|d| match d.trim().parse() {
+            Ok(0) | Err(_) => Err(format!("invalid dimension `{}` in `{}`", d, arg)),
+            Ok(d) => Ok(d),
+        })
+        .collect()
+}
+
+fn parse_count(arg: &str) -> Result<usize, String> {
+    arg.parse().map_err(|_| format!("invalid count `{}`", arg))
+}
+
+/// Parses a number of MiB into bytes.
+fn parse_mebibytes(arg: &str) -> Result<u64, String> {
+    arg.parse::<u64>()
+        .ok()
+        .and_then(|mib| mib.checked_mul(1 << 20))
+        .ok_or_else(|| format!("invalid or too large size in MiB `{}`", arg))
+}
+
+fn parse_alignment(arg: &str) -> Result<usize, String> {
+    match arg.parse() {
+        Ok(bytes @ (32 | 64 | 4096)) => Ok(bytes),
+        _ => Err(format!(
+            "invalid alignment `{}`, expected 32, 64 or 4096",
+            arg
+        )),
+    }
+}
+
+fn parse_layout(arg: &str) -> Result<Layout, String> {
+    Layout::ALL
+        .iter()
+        .copied()
+        .find(|layout| layout.to_string() == arg)
+        .ok_or_else(|| format!("unknown layout `{}`", arg))
+}
+
+fn parse_fraction(arg: &str) -> Result<f64, String> {
+    match arg.parse() {
+        Ok(x) if (0.0..1.0).contains(&x) => Ok(x),
+        _ => Err(format!("invalid level `{}`", arg)),
+    }
+}
+
+fn parse_seconds(arg: &str) -> Result<Duration, String> {
+    arg.parse()
+        .ok()
+        .filter(|s: &f64| s.is_finite() && *s >= 0.0)
+        .map(Duration::from_secs_f64)
+        .ok_or_else(|| format!("invalid time `{}`", arg))
+}
+
+/// Parses a non-negative percentage as a fraction.
+fn parse_percentage(arg: &str) -> Result<f64, String> {
+    match arg.parse::<f64>() {
+        Ok(p) if p >= 0.0 => Ok(p / 100.0),
+        _ => Err(format!("invalid percentage `{}`", arg)),
+    }
+}
+
+fn parse_outliers(arg: &str) -> Result<Outliers, String> {
+    match arg {
+        "keep" => Ok(Outliers::Keep),
+        "minmax" => Ok(Outliers::TrimMinMax),
+        "tukey" => Ok(Outliers::Tukey(1.5)),
+        _ => Err(format!("unknown outlier policy `{}`", arg)),
+    }
+}
+
+fn parse_timer(arg: &str) -> Result<Box<dyn Timer>, String> {
+    #[cfg(target_os = "linux")]
+    use timer::{Clock, ClockGettime};
+
+    match arg {
+        "instant" => Ok(Box::new(timer::Instant::new())),
+        #[cfg(target_os = "linux")]
+        "monotonic" => Ok(Box::new(ClockGettime::new(Clock::Monotonic))),
+        #[cfg(target_os = "linux")]
+        "monotonic-raw" => Ok(Box::new(ClockGettime::new(Clock::MonotonicRaw))),
+        #[cfg(target_os = "linux")]
+        "thread-cputime" => Ok(Box::new(ClockGettime::new(Clock::ThreadCputime))),
+        #[cfg(all(target_os = "linux", any(target_arch = "x86", target_arch = "x86_64")))]
+        "rdtsc" => timer::Rdtsc::calibrate(
+            &ClockGettime::new(Clock::MonotonicRaw),
+            Duration::from_millis(100),
+        )
+        .map(|rdtsc| Box::new(rdtsc) as Box<dyn Timer>)
+        .ok_or_else(|| "`monotonic-raw` does not advance, so `rdtsc` cannot be calibrated".into()),
+        _ => Err(format!("unknown or unsupported timer `{}`", arg)),
+    }
+}
+
+fn parse_cache_flush(arg: &str) -> Result<CacheFlush, String> {
+    match arg {
+        "none" => Ok(CacheFlush::None),
+        "llc" => Ok(CacheFlush::sweep_llc()),
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        "clflush" => Ok(CacheFlush::Clflush),
+        _ => match arg.strip_prefix("sweep:").map(str::parse::<usize>) {
+            Some(Ok(kib)) if kib > 0 => Ok(CacheFlush::Sweep(kib * 1024)),
+            _ => Err(format!(
+                "unknown or unsupported cache-flush policy `{}`",
+                arg
+            )),
+        },
+    }
+}
+
+fn parse_prefault(arg: &str) -> Result<Prefault, String> {
+    match arg {
+        "none" => Ok(Prefault::None),
+        "zero" => Ok(Prefault::Zero),
+        #[cfg(target_os = "linux")]
+        "willneed" => Ok(Prefault::WillNeed),
+        #[cfg(target_os = "linux")]
+        "populate" => Ok(Prefault::Populate),
+        _ => Err(format!("unknown or unsupported pre-fault policy `{}`", arg)),
+    }
+}
+
+fn parse_pages(arg: &str) -> Result<Pages, String> {
+    match arg {
+        "default" => Ok(Pages::Default),
+        #[cfg(target_os = "linux")]
+        "hugetlb-2m" => Ok(Pages::Huge2M),
+        #[cfg(target_os = "linux")]
+        "hugetlb-1g" => Ok(Pages::Huge1G),
+        #[cfg(target_os = "linux")]
+        "thp" => Ok(Pages::Transparent),
+        #[cfg(target_os = "linux")]
+        "4k" => Ok(Pages::Small),
+        _ => Err(format!("unknown or unsupported pages `{}`", arg)),
+    }
+}
+
+/// Turns the counts of every sample into the counts of every counter in sample order.
+fn per_counter(samples: &[Vec<(&'static str, u64)>]) -> Vec<(String, Vec<u64>)> {
+    let names = samples.first().map_or(&[][..], Vec::as_slice);
+    names
+        .iter()
+        .enumerate()
+        .map(|(i, &(name, _))| {
+            let counts = samples.iter().map(|counts| counts[i].1).collect();
+            (name.to_owned(), counts)
+        })
+        .collect()
+}
+
+fn median(values: &[u64]) -> u64 {
+    let mut values = values.to_vec();
+    values.sort_unstable();
+    match values.len() {
+        0 => 0,
+        n if n % 2 == 1 => values[n / 2],
+        n => (values[n / 2 - 1] + values[n / 2]) / 2,
+    }
+}
+
+fn check_arity(kernel: &Kernel, dims: &[usize]) -> Result<(), String> {
+    if dims.len() != kernel.arity() {
+        return Err(format!(
+            "`{}` takes {} dimensions but {:?} were given",
+            kernel.name,
+            kernel.arity(),
+            dims
+        ));
+    }
+    Ok(())
+}
+
+fn read_dims_file(path: &str) -> Result<Vec<Job>, String> {
+    let contents =
+        fs::read_to_string(path).map_err(|e| format!("cannot read `{}`: {}", path, e))?;
+    let mut jobs = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (name, dims) = line
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| format!("{}:{}: expected `<kernel> <dims>`", path, number + 1))?;
+        let kernel = kernels::find(name)
+            .ok_or_else(|| format!("{}:{}: unknown kernel `{}`", path, number + 1, name))?;
+        let dims = parse_dims(&dims.split_whitespace().collect::<Vec<_>>().join(","))
+            .map_err(|e| format!("{}:{}: {}", path, number + 1, e))?;
+        check_arity(kernel, &dims).map_err(|e| format!("{}:{}: {}", path, number + 1, e))?;
+        jobs.push(Job {
+            kernel,
+            size: Size::Dims(dims),
+        });
+    }
+    Ok(jobs)
+}
+
+fn parse_args(args: &[String]) -> Result<Command, String> {
+    let (command, rest) = args.split_first().ok_or("missing command")?;
+    match command.as_str() {
+        "list" => Ok(Command::List),
+        "run" => {
+            let mut targets = Vec::new();
+            let mut sizes = Vec::new();
+            let mut jobs = Vec::new();
+            let mut polybench = false;
+            let mut samples = None;
+            let mut warmup = None;
+            let mut outliers = None;
+            let mut timer = None;
+            let mut outputs = Vec::new();
+            let mut cache_flush = None;
+            let mut prefault = Prefault::Zero;
+            let mut pages = Pages::Default;
+            let mut alignment = 32;
+            let mut padding = 0;
+            let mut layout = Layout::Default;
+            let mut counters = false;
+            let mut cpu = None;
+            let mut fifo = false;
+            let mut strict = false;
+            let mut isolate = false;
+            let mut timeout = None;
+            let mut memory_budget = None;
+            let mut target = None;
+            let mut max_samples = 1000;
+            let mut max_time = Duration::from_secs(10);
+            let mut rest = rest.iter();
+            while let Some(arg) = rest.next() {
+                let mut value = || rest.next().ok_or(format!("`{}` expects a value", arg));
+                match arg.as_str() {
+                    "--dataset" => {
+                        let name = value()?;
+                        if name == "all" {
+                            sizes.extend(Dataset::ALL.iter().copied().map(Size::Dataset));
+                        } else {
+                            let dataset = Dataset::from_name(name)
+                                .ok_or_else(|| format!("unknown dataset `{}`", name))?;
+                            sizes.push(Size::Dataset(dataset));
+                        }
+                    }
+                    "--dims" => sizes.push(Size::Dims(parse_dims(value()?)?)),
+                    "--dims-file" => jobs.extend(read_dims_file(value()?)?),
+                    "--samples" => samples = Some(parse_count(value()?)?.max(1)),
+                    "--warmup" => warmup = Some(parse_count(value()?)?),
+                    "--outliers" => outliers = Some(parse_outliers(value()?)?),
+                    "--polybench" => polybench = true,
+                    "--target" => target = Some(parse_percentage(value()?)?),
+                    "--max-samples" => max_samples = parse_count(value()?)?.max(1),
+                    "--max-time" => max_time = parse_seconds(value()?)?,
+                    "--timer" => {
+                        let name = value()?;
+                        timer = Some((name.clone(), parse_timer(name)?));
+                    }
+                    "--cache-flush" => cache_flush = Some(parse_cache_flush(value()?)?),
+                    "--prefault" => prefault = parse_prefault(value()?)?,
+                    "--pages" => pages = parse_pages(value()?)?,
+                    "--align" => alignment = parse_alignment(value()?)?,
+                    "--padding" => padding = parse_count(value()?)?,
+                    "--layout" => layout = parse_layout(value()?)?,
+                    #[cfg(target_os = "linux")]
+                    "--counters" => counters = true,
+                    #[cfg(target_os = "linux")]
+                    "--cpu" => cpu = Some(parse_count(value()?)?),
+                    #[cfg(target_os = "linux")]
+                    "--fifo" => fifo = true,
+                    "--strict" => strict = true,
+                    #[cfg(target_os = "linux")]
+                    "--isolate" => isolate = true,
+                    #[cfg(target_os = "linux")]
+                    "--timeout" => {
+                        timeout = Some(parse_seconds(value()?)?);
+                        isolate = true;
+                    }
+                    "--memory-budget" => memory_budget = Some(parse_mebibytes(value()?)?),
+                    "--jsonl" => outputs.push((value()?.clone(), Format::JsonLines)),
+                    "--csv" => outputs.push((value()?.clone(), Format::Csv)),
+                    _ if arg.starts_with("--") => return Err(format!("unknown option `{}`", arg)),
+                    _ => {
+                        if !KERNELS.iter().any(|k| k.matches(arg)) {
+                            return Err(format!("unknown kernel or category `{}`", arg));
+                        }
+                        targets.push(arg.clone());
+                    }
+                }
+            }
+            if targets.is_empty() && jobs.is_empty() {
+                return Err("`run` expects at least one kernel, category or `all`".into());
+            }
+            if padding != 0 && layout != Layout::Default {
+                return Err("`--padding` applies only to the default layout".into());
+            }
+            if sizes.is_empty() {
+                sizes.push(Size::Dataset(Dataset::Large));
+            }
+            for kernel in KERNELS
+                .iter()
+                .filter(|k| targets.iter().any(|t| k.matches(t)))
+            {
+                for size in &sizes {
+                    if let Size::Dims(dims) = size {
+                        check_arity(kernel, dims)?;
+                    }
+                    jobs.push(Job {
+                        kernel,
+                        size: size.clone(),
+                    });
+                }
+            }
+            let (timer_name, timer) = match timer {
+                Some(timer) => timer,
+                None => ("instant".to_owned(), parse_timer("instant")?),
+            };
+            // Explicit options override the preset wherever they appear on the command line.
+            let mut sampling = if polybench {
+                Sampling::POLYBENCH
+            } else {
+                Sampling::SINGLE
+            };
+            sampling.samples = samples.unwrap_or(sampling.samples);
+            sampling.warmup = warmup.unwrap_or(sampling.warmup);
+            sampling.outliers = outliers.unwrap_or(sampling.outliers);
+            sampling.adaptive = target.map(|relative_width| Adaptive {
+                confidence: 0.95,
+                relative_width,
+                max_samples,
+                max_time,
+            });
+            Ok(Command::Run {
+                jobs,
+                sampling,
+                timer_name,
+                timer,
+                outputs,
+                cache_flush: cache_flush.unwrap_or_else(CacheFlush::sweep_llc),
+                prefault,
+                pages,
+                alignment,
+                padding,
+                layout,
+                counters,
+                cpu,
+                fifo,
+                strict,
+                isolate,
+                timeout,
+                memory_budget,
+            })
+        }
+        "compare" => {
+            let mut files = Vec::new();
+            let mut thresholds = Thresholds {
+                confidence: 0.95,
+                max_regression: 0.05,
+            };
+            let mut rest = rest.iter();
+            while let Some(arg) = rest.next() {
+                let mut value = || rest.next().ok_or(format!("`{}` expects a value", arg));
+                match arg.as_str() {
+                    "--confidence" => thresholds.confidence = parse_fraction(value()?)?,
+                    "--max-regression" => thresholds.max_regression = parse_percentage(value()?)?,
+                    _ if arg.starts_with("--") => return Err(format!("unknown option `{}`", arg)),
+                    _ => files.push(arg.clone()),
+                }
+            }
+            match files.as_slice() {
+                [baseline, candidate] => Ok(Command::Compare {
+                    baseline: baseline.clone(),
+                    candidate: candidate.clone(),
+                    thresholds,
+                }),
+                _ => Err("`compare` expects a baseline and a candidate file".into()),
+            }
+        }
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            process::exit(0);
+        }
+        _ => Err(format!("unknown command `{}`", command)),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let command = parse_args(&args).unwrap_or_else(|e| {
+        eprintln!("error: {}\n\n{}", e, USAGE);
+        process::exit(2);
+    });
+
+    match command {
+        Command::List => {
+            for kernel in KERNELS {
+                let dims = format!("{:?}", (kernel.dims)(Dataset::Large));
+                println!("{:<14} {:<22} {}", kernel.name, kernel.category, dims);
+            }
+        }
+        Command::Run {
+            jobs,
+            sampling,
+            timer_name,
+            timer,
+            outputs,
+            cache_flush,
+            prefault,
+            pages,
+            alignment,
+            padding,
+            layout,
+            counters,
+            cpu,
+            fifo,
+            strict,
+            isolate,
+            timeout,
+            memory_budget,
+        } => {
+            let environment = Environment::detect();
+            let warnings = environment.warnings();
+            for warning in &warnings {
+                eprintln!("warning: {}", warning);
+            }
+            if strict && !warnings.is_empty() {
+                eprintln!("error: refusing to run with warnings because of `--strict`");
+                process::exit(1);
+            }
+            util::set_cache_flush(cache_flush);
+            util::set_prefault(prefault);
+            pages::set_pages(pages);
+            ndarray::set_alignment(alignment);
+            ndarray::set_padding(padding);
+            // Restores the previous scheduling when the run is over.
+            #[cfg(target_os = "linux")]
+            let _scheduling = Scheduling::apply(cpu, fifo).unwrap_or_else(|e| {
+                eprintln!("error: {}", e);
+                process::exit(1);
+            });
+            #[cfg(target_os = "linux")]
+            let (affinity, scheduler) = (Scheduling::affinity(), Scheduling::policy());
+            #[cfg(not(target_os = "linux"))]
+            let (affinity, scheduler) = {
+                let _ = (cpu, fifo);
+                ("unknown".to_owned(), "unknown".to_owned())
+            };
+            // Calibrated on the CPU and with the policy the kernels run with.
+            let timer = Calibrated::new(&*timer);
+            let run = Run {
+                host: Host::detect(),
+                timer_frequency: timer.frequency(),
+                timer: timer_name,
+                calibration: timer.calibration,
+                cache_flush: cache_flush.to_string(),
+                prefault: prefault.to_string(),
+                alignment,
+                padding,
+                affinity,
+                scheduler,
+                timeout,
+                memory_budget,
+                environment,
+                warnings,
+            };
+            let mut writers: Vec<Writer> = outputs
+                .iter()
+                .map(|(path, format)| {
+                    Writer::create(path, *format).unwrap_or_else(|e| {
+                        eprintln!("error: cannot create `{}`: {}", path, e);
+                        process::exit(1);
+                    })
+                })
+                .collect();
+            #[cfg(target_os = "linux")]
+            let available_memory = util::available_memory;
+            #[cfg(not(target_os = "linux"))]
+            let available_memory = || {
+                let _ = (isolate, timeout);
+                None
+            };
+            // Every job opens its own counters because they count the thread that opened them,
+            // which is not the child's with `--isolate`.
+            #[cfg(target_os = "linux")]
+            if counters && Counters::new().events().next().is_none() {
+                eprintln!("warning: perf_event_open failed, only getrusage deltas are counted");
+            }
+
+            for Job { kernel, size } in jobs {
+                let (dataset, dims) = match &size {
+                    Size::Dataset(dataset) => (dataset.name(), (kernel.dims)(*dataset).to_vec()),
+                    Size::Dims(dims) => ("-", dims.clone()),
+                };
+                let layout = kernel.layout(layout);
+                // Only the runtime-sized arrays can be aligned, padded and laid out at runtime.
+                let measure = |timer: &dyn Timer| {
+                    let duration = match (&size, kernel.run_layout) {
+                        (_, Some(run_layout)) if layout != Layout::Default => {
+                            run_layout(&dims, layout, timer)
+                        }
+                        (Size::Dataset(dataset), _) if alignment == 32 && padding == 0 => {
+                            (kernel.run)(*dataset, timer)
+                        }
+                        _ => (kernel.run_dyn)(&dims, timer),
+                    };
+                    // Every run starts from the same inputs, so the first dump is the only one.
+                    #[cfg(feature = "print-result")]
+                    dump::set_dump(false);
+                    duration
+                };
+                let measure_job = || {
+                    #[cfg(feature = "print-result")]
+                    dump::set_dump(true);
+                    #[cfg(target_os = "linux")]
+                    let counters = counters.then(Counters::new);
+                    #[cfg(not(target_os = "linux"))]
+                    let _ = counters;
+                    let mut counts = Vec::new();
+                    let summary = sampling.run(|| {
+                        #[cfg(target_os = "linux")]
+                        if let Some(counters) = &counters {
+                            let timer = Counted {
+                                timer: &timer,
+                                counters,
+                            };
+                            let duration = measure(&timer);
+                            counts.push(counters.last());
+                            return duration;
+                        }
+                        measure(&timer)
+                    });
+                    // The counts of the warmup runs come first.
+                    counts.drain(..counts.len().saturating_sub(summary.samples.len()));
+                    #[cfg(target_os = "linux")]
+                    let pages = match pages::take_fallback() {
+                        true => Pages::Transparent,
+                        false => pages,
+                    };
+                    Sampled {
+                        summary,
+                        counts: per_counter(&counts),
+                        pages,
+                    }
+                };
+                let footprint = (kernel.footprint)(&dims);
+                let sampled = match memory_budget.or_else(available_memory) {
+                    Some(budget) if footprint > budget => Err(format!(
+                        "skipped: needs {:.1} MiB but only {:.1} MiB are available",
+                        footprint as f64 / (1 << 20) as f64,
+                        budget as f64 / (1 << 20) as f64
+                    )),
+                    #[cfg(target_os = "linux")]
+                    _ if isolate => isolate::run(&sampling, timeout, measure_job)
+                        .map_err(|failure| failure.to_string()),
+                    _ => Ok(measure_job()),
+                };
+                let (summary, counts, backed, status) = match sampled {
+                    Ok(Sampled {
+                        summary,
+                        counts,
+                        pages,
+                    }) => (summary, counts, pages, "ok".to_owned()),
+                    Err(failure) => (
+                        Summary::new(Vec::new(), sampling.outliers),
+                        Vec::new(),
+                        pages,
+                        failure,
+                    ),
+                };
+
+                let record = Record {
+                    kernel,
+                    dataset,
+                    dims: &dims,
+                    status: &status,
+                    summary: &summary,
+                    counters: &counts,
+                    pages: &backed.to_string(),
+                    layout: &layout.to_string(),
+                };
+                for writer in &mut writers {
+                    if let Err(e) = writer.write(&run, &record) {
+                        eprintln!("error: cannot write results: {}", e);
+                        process::exit(1);
+                    }
+                }
+
+                let rate = format!(
+                    "{:.3} G{}/s",
+                    (kernel.ops)(&dims) as f64 / summary.median.as_secs_f64() / 1e9,
+                    kernel.unit
+                );
+                let dims = format!("{:?}", dims);
+                if status != "ok" {
+                    println!(
+                        "{:<14} | {:<10} | {:<30} | {}",
+                        kernel.name, dataset, dims, status
+                    );
+                    continue;
+                }
+                let samples = match summary.stop {
+                    Stop::Count => format!("{}/{} samples", summary.kept(), summary.samples.len()),
+                    stop => format!(
+                        "{}/{} samples, {}",
+                        summary.kept(),
+                        summary.samples.len(),
+                        stop
+                    ),
+                };
+                if summary.samples.len() == 1 && summary.stop == Stop::Count {
+                    println!(
+                        "{:<14} | {:<10} | {:<30} | {:.7} s | {}",
+                        kernel.name,
+                        dataset,
+                        dims,
+                        summary.median.as_secs_f64(),
+                        rate
+                    );
+                } else {
+                    println!(
+                        "{:<14} | {:<10} | {:<30} | median {:.7} s | min {:.7} s | mean {:.7} s \
+                         | stddev {:.7} s | {} | {}",
+                        kernel.name,
+                        dataset,
+                        dims,
+                        summary.median.as_secs_f64(),
+                        summary.min.as_secs_f64(),
+                        summary.mean.as_secs_f64(),
+                        summary.stddev.as_secs_f64(),
+                        samples,
+                        rate
+                    );
+                }
+                if backed != pages {
+                    eprintln!(
+                        "warning: {} {}: hugetlbfs has no free pages for `{}`, fell back to \
+                         transparent huge pages",
+                        kernel.name, dims, pages
+                    );
+                }
+                if !timer.calibration.is_reliable(summary.min) {
+                    eprintln!(
+                        "warning: {} {}: samples as short as {:.7} s are below {} times the timer \
+                         resolution of {} ns, use a larger dataset or --dims",
+                        kernel.name,
+                        dims,
+                        summary.min.as_secs_f64(),
+                        timer::Calibration::RELIABLE_RESOLUTIONS,
+                        timer.calibration.resolution.as_nanos()
+                    );
+                }
+                if !counts.is_empty() {
+                    let medians: Vec<_> = counts
+                        .iter()
+                        .map(|(name, values)| format!("{} {}", name, median(values)))
+                        .collect();
+                    println!("{:<14} | median counts: {}", "", medians.join(" | "));
+                }
+            }
+        }
+        Command::Compare {
+            baseline,
+            candidate,
+            thresholds,
+        } => {
+            let read = |path: &str| {
+                results::read(path).unwrap_or_else(|e| {
+                    eprintln!("error: {}", e);
+                    process::exit(2);
+                })
+            };
+            if compare::compare(&read(&baseline), &read(&candidate), &thresholds) {
+                process::exit(1);
+            }
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! Checks whether the machine is in a state that gives stable measurements.
+
+use std::fs;
+
+/// The 1-minute load average above which other work is assumed to compete with the kernels.
+const MAX_LOAD: f64 = 1.0;
+
+/// The state of the CPUs and of the system, with `None` for what cannot be read, e.g. in VMs
+/// without cpufreq.
+pub struct Environment {
+    /// The distinct cpufreq governors of the CPUs, e.g. `performance` or `powersave,schedutil`.
+    pub governor: Option<String>,
+    /// The scaling frequency range of the first CPU in kHz.
+    pub min_freq_khz: Option<u64>,
+    pub max_freq_khz: Option<u64>,
+    /// Whether turbo boost may raise the frequency above the nominal one.
+    pub turbo: Option<bool>,
+    /// Whether simultaneous multithreading is active.
+    pub smt: Option<bool>,
+    pub load_average: Option<f64>,
+}
+
+fn read(path: &str) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|contents| contents.trim().to_owned())
+}
+
+impl Environment {
+    pub fn detect() -> Environment {
+        let mut governors: Vec<String> = fs::read_dir("/sys/devices/system/cpu")
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.ok()?.path().join("cpufreq/scaling_governor");
+                read(path.to_str()?)
+            })
+            .collect();
+        governors.sort();
+        governors.dedup();
+
+        let cpufreq = |name| {
+            read(&format!("/sys/devices/system/cpu/cpu0/cpufreq/{}", name))?
+                .parse()
+                .ok()
+        };
+        // intel_pstate reports whether turbo is disabled, acpi-cpufreq whether boost is enabled.
+        let turbo = match read("/sys/devices/system/cpu/intel_pstate/no_turbo") {
+            Some(no_turbo) => Some(no_turbo == "0"),
+            None => read("/sys/devices/system/cpu/cpufreq/boost").map(|boost| boost == "1"),
+        };
+
+        Environment {
+            governor: (!governors.is_empty()).then(|| governors.join(",")),
+            min_freq_khz: cpufreq("scaling_min_freq"),
+            max_freq_khz: cpufreq("scaling_max_freq"),
+            turbo,
+            smt: read("/sys/devices/system/cpu/smt/active").map(|active| active == "1"),
+            load_average: read("/proc/loadavg")
+                .and_then(|loadavg| loadavg.split_whitespace().next()?.parse().ok()),
+        }
+    }
+
+    /// Returns what makes the measurements less stable than they could be.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if let Some(governor) = &self.governor {
+            if governor != "performance" {
+                warnings.push(format!(
+                    "the cpufreq governor is `{}` instead of `performance`",
+                    governor
+                ));
+            }
+        }
+        if let (Some(min), Some(max)) = (self.min_freq_khz, self.max_freq_khz) {
+            if min != max {
+                warnings.push(format!(
+                    "the CPU frequency scales between {} and {} MHz",
+                    min / 1000,
+                    max / 1000
+                ));
+            }
+        }
+        if self.turbo == Some(true) {
+            warnings.push("turbo boost is enabled".to_owned());
+        }
+        if self.smt == Some(true) {
+            warnings.push("SMT is active, so a sibling thread may share the core".to_owned());
+        }
+        if let Some(load) = self.load_average.filter(|&load| load > MAX_LOAD) {
+            warnings.push(format!(
+                "the 1-minute load average is {:.2}, above {:.1}",
+                load, MAX_LOAD
+            ));
+        }
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warnings() {
+        let mut environment = Environment {
+            governor: Some("performance".to_owned()),
+            min_freq_khz: Some(3_000_000),
+            max_freq_khz: Some(3_000_000),
+            turbo: Some(false),
+            smt: None,
+            load_average: Some(0.1),
+        };
+        assert!(environment.warnings().is_empty());
+
+        environment.governor = Some("powersave".to_owned());
+        environment.min_freq_khz = Some(800_000);
+        environment.turbo = Some(true);
+        environment.load_average = Some(3.5);
+        let warnings = environment.warnings();
+        assert_eq!(4, warnings.len());
+        assert!(warnings[1].contains("between 800 and 3000 MHz"));
+    }
+}
@@ -0,0 +1,609 @@
+use crate::kernels::Kernel;
+use crate::preflight::Environment;
+use polybench_rs::stats::Summary;
+use polybench_rs::timer::Calibration;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::iter::Peekable;
+use std::str::Chars;
+use std::time::Duration;
+
+/// The machine and the build the results were measured with.
+pub struct Host {
+    pub cpu_model: String,
+    pub cores: usize,
+    pub rustc: &'static str,
+    pub rustflags: &'static str,
+    pub git_revision: &'static str,
+}
+
+impl Host {
+    pub fn detect() -> Host {
+        let cpu_model = fs::read_to_string("/proc/cpuinfo")
+            .ok()
+            .and_then(|cpuinfo| {
+                cpuinfo
+                    .lines()
+                    .find(|line| line.starts_with("model name"))
+                    .and_then(|line| line.split_once(':'))
+                    .map(|(_, model)| model.trim().to_owned())
+            })
+            .unwrap_or_else(|| "unknown".to_owned());
+        Host {
+            cpu_model,
+            cores: std::thread::available_parallelism().map_or(0, |n| n.get()),
+            rustc: env!("POLYBENCH_RUSTC_VERSION"),
+            rustflags: env!("POLYBENCH_RUSTFLAGS"),
+            git_revision: env!("POLYBENCH_GIT_REVISION"),
+        }
+    }
+}
+
+/// The settings every kernel of a run was measured with.
+pub struct Run {
+    pub host: Host,
+    /// The name of the timer as given to `--timer`.
+    pub timer: String,
+    /// Ticks per second of the timer.
+    pub timer_frequency: u64,
+    /// The resolution and overhead of the timer; the overhead is subtracted from every sample.
+    pub calibration: Calibration,
+    pub cache_flush: String,
+    pub prefault: String,
+    /// The alignment of the arrays in bytes.
+    pub alignment: usize,
+    /// The elements of padding after every row of the arrays with two or three dimensions.
+    pub padding: usize,
+    /// The CPUs the measuring thread was allowed to run on, e.g. `0-3`.
+    pub affinity: String,
+    /// The scheduling policy and priority of the measuring thread, e.g. `SCHED_FIFO 99`.
+    pub scheduler: String,
+    /// The limit of the time a kernel may run for all its samples.
+    pub timeout: Option<Duration>,
+    /// The memory a kernel's arrays may take, if not what is available when it starts.
+    pub memory_budget: Option<u64>,
+    pub environment: Environment,
+    /// The warnings of the pre-flight check of the environment.
+    pub warnings: Vec<String>,
+}
+
+/// The measurements of one kernel at one size.
+pub struct Record<'a> {
+    pub kernel: &'a Kernel,
+    /// The PolyBench/C name of the dataset, or `-` for dimensions given at runtime.
+    pub dataset: &'a str,
+    pub dims: &'a [usize],
+    /// `ok`, or why the kernel was skipped or failed, in which case there are no samples.
+    pub status: &'a str,
+    pub summary: &'a Summary,
+    /// The count of every performance counter in every sample, empty without `--counters`.
+    pub counters: &'a [(String, Vec<u64>)],
+    /// The pages the arrays were backed with, `thp` if hugetlbfs ran out of the requested ones.
+    pub pages: &'a str,
+    /// The layout of the arrays with two or three dimensions, `default` for kernels without any.
+    pub layout: &'a str,
+}
+
+enum Value {
+    Str(String),
+    Int(u64),
+    Float(f64),
+    List(Vec<Value>),
+}
+
+impl Value {
+    fn json(&self, out: &mut String) {
+        match self {
+            Value::Str(s) => {
+                out.push('"');
+                for c in s.chars() {
+                    match c {
+                        '"' => out.push_str("\\\""),
+                        '\\' => out.push_str("\\\\"),
+                        '\n' => out.push_str("\\n"),
+                        c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+                        c => out.push(c),
+                    }
+                }
+                out.push('"');
+            }
+            Value::Int(n) => out.push_str(&n.to_string()),
+            Value::Float(x) if x.is_finite() => out.push_str(&x.to_string()),
+            Value::Float(_) => out.push_str("null"),
+            Value::List(values) => {
+                out.push('[');
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    value.json(out);
+                }
+                out.push(']');
+            }
+        }
+    }
+
+    /// Returns the value as a single field, with lists joined by `;`.
+    fn text(&self) -> String {
+        match self {
+            Value::Str(s) => s.clone(),
+            Value::Int(n) => n.to_string(),
+            Value::Float(x) => x.to_string(),
+            Value::List(values) => values.iter().map(Value::text).collect::<Vec<_>>().join(";"),
+        }
+    }
+
+    fn csv(&self, out: &mut String) {
+        let field = self.text();
+        if field.contains([',', '"', '\n', '\r']) {
+            out.push('"');
+            out.push_str(&field.replace('"', "\"\""));
+            out.push('"');
+        } else {
+            out.push_str(&field);
+        }
+    }
+}
+
+fn seconds(duration: Duration) -> Value {
+    Value::Float(duration.as_secs_f64())
+}
+
+/// Returns `value`, or a value that is written as `null` in JSON if it is unknown.
+fn optional(value: Option<Value>) -> Value {
+    value.unwrap_or(Value::Float(f64::NAN))
+}
+
+fn on_off(value: Option<bool>) -> Value {
+    Value::Str(match value {
+        Some(true) => "on".to_owned(),
+        Some(false) => "off".to_owned(),
+        None => "unknown".to_owned(),
+    })
+}
+
+/// Returns the columns of a record in the order they are written.
+fn fields<'a>(run: &Run, record: &'a Record) -> Vec<(&'a str, Value)> {
+    let Record {
+        kernel,
+        dataset,
+        dims,
+        status,
+        summary,
+        counters,
+        pages,
+        layout,
+    } = record;
+    let ops = (kernel.ops)(dims);
+    let median_interval = summary.median_interval(0.95);
+    let mut fields = vec![
+        ("kernel", Value::Str(kernel.name.to_owned())),
+        ("category", Value::Str(kernel.category.to_owned())),
+        ("dataset", Value::Str(dataset.to_string())),
+        (
+            "dims",
+            Value::List(dims.iter().map(|&d| Value::Int(d as u64)).collect()),
+        ),
+        ("data_type", Value::Str((kernel.data_type)().to_owned())),
+        ("ops", Value::Int(ops)),
+        ("ops_unit", Value::Str(kernel.unit.to_owned())),
+        ("footprint", Value::Int((kernel.footprint)(dims))),
+        ("status", Value::Str(status.to_string())),
+        (
+            "samples",
+            Value::List(summary.samples.iter().copied().map(seconds).collect()),
+        ),
+        (
+            "discarded",
+            Value::List(
+                summary
+                    .discarded
+                    .iter()
+                    .map(|&d| Value::Int(d as u64))
+                    .collect(),
+            ),
+        ),
+        ("min", seconds(summary.min)),
+        ("max", seconds(summary.max)),
+        ("median", seconds(summary.median)),
+        ("mean", seconds(summary.mean)),
+        ("stddev", seconds(summary.stddev)),
+        (
+            "median_ci_low",
+            optional(median_interval.map(|(low, _)| seconds(low))),
+        ),
+        (
+            "median_ci_high",
+            optional(median_interval.map(|(_, high)| seconds(high))),
+        ),
+        ("stop", Value::Str(summary.stop.to_string())),
+        (
+            "ops_per_second",
+            Value::Float(ops as f64 / summary.median.as_secs_f64()),
+        ),
+    ];
+    for (name, counts) in counters.iter() {
+        let counts = counts.iter().map(|&n| Value::Int(n)).collect();
+        fields.push((name.as_str(), Value::List(counts)));
+    }
+    fields.extend(vec![
+        ("timer", Value::Str(run.timer.clone())),
+        ("timer_frequency", Value::Int(run.timer_frequency)),
+        ("timer_resolution", seconds(run.calibration.resolution)),
+        ("timer_overhead", seconds(run.calibration.overhead)),
+        ("cache_flush", Value::Str(run.cache_flush.clone())),
+        ("prefault", Value::Str(run.prefault.clone())),
+        ("pages", Value::Str(pages.to_string())),
+        ("alignment", Value::Int(run.alignment as u64)),
+        ("padding", Value::Int(run.padding as u64)),
+        ("layout", Value::Str(layout.to_string())),
+        ("affinity", Value::Str(run.affinity.clone())),
+        ("scheduler", Value::Str(run.scheduler.clone())),
+        ("timeout", optional(run.timeout.map(seconds))),
+        ("memory_budget", optional(run.memory_budget.map(Value::Int))),
+        (
+            "governor",
+            optional(run.environment.governor.clone().map(Value::Str)),
+        ),
+        (
+            "cpu_min_freq_khz",
+            optional(run.environment.min_freq_khz.map(Value::Int)),
+        ),
+        (
+            "cpu_max_freq_khz",
+            optional(run.environment.max_freq_khz.map(Value::Int)),
+        ),
+        ("turbo", on_off(run.environment.turbo)),
+        ("smt", on_off(run.environment.smt)),
+        (
+            "load_average",
+            optional(run.environment.load_average.map(Value::Float)),
+        ),
+        (
+            "warnings",
+            Value::List(run.warnings.iter().cloned().map(Value::Str).collect()),
+        ),
+        ("cpu_model", Value::Str(run.host.cpu_model.clone())),
+        ("cores", Value::Int(run.host.cores as u64)),
+        ("rustc", Value::Str(run.host.rustc.to_owned())),
+        ("rustflags", Value::Str(run.host.rustflags.to_owned())),
+        ("git_revision", Value::Str(run.host.git_revision.to_owned())),
+    ]);
+    fields
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    /// One JSON object per record and line.
+    JsonLines,
+    /// A header line followed by one row per record.
+    Csv,
+}
+
+/// Writes every record to a file, with the metadata of the run repeated in each so that the
+/// records can be ingested one by one.
+pub struct Writer {
+    out: BufWriter<File>,
+    format: Format,
+    header: bool,
+}
+
+impl Writer {
+    pub fn create(path: &str, format: Format) -> io::Result<Writer> {
+        Ok(Writer {
+            out: BufWriter::new(File::create(path)?),
+            format,
+            header: format == Format::Csv,
+        })
+    }
+
+    pub fn write(&mut self, run: &Run, record: &Record) -> io::Result<()> {
+        let fields = fields(run, record);
+        let mut line = String::new();
+        match self.format {
+            Format::JsonLines => {
+                line.push('{');
+                for (i, (name, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        line.push(',');
+                    }
+                    Value::Str(name.to_string()).json(&mut line);
+                    line.push(':');
+                    value.json(&mut line);
+                }
+                line.push('}');
+            }
+            Format::Csv => {
+                if self.header {
+                    let names: Vec<_> = fields.iter().map(|(name, _)| *name).collect();
+                    writeln!(self.out, "{}", names.join(","))?;
+                    self.header = false;
+                }
+                for (i, (_, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        line.push(',');
+                    }
+                    value.csv(&mut line);
+                }
+            }
+        }
+        writeln!(self.out, "{}", line)?;
+        // Flushed after every record so that the results of an interrupted run are kept.
+        self.out.flush()
+    }
+}
+
+/// The kept samples of one kernel at one size, read back from a results file.
+pub struct Measurement {
+    pub kernel: String,
+    pub dataset: String,
+    pub dims: Vec<usize>,
+    /// The layout, pages, timer and padding the kernel ran with, empty if the file predates them.
+    pub layout: String,
+    pub pages: String,
+    pub timer: String,
+    pub padding: String,
+    pub samples: Vec<Duration>,
+}
+
+impl Measurement {
+    /// Builds a measurement from the fields of a record, given as text like in CSV, or returns
+    /// `None` for a kernel that failed.
+    fn from_fields(field: impl Fn(&str) -> Option<String>) -> Result<Option<Measurement>, String> {
+        if field("status").is_some_and(|status| status != "ok") {
+            return Ok(None);
+        }
+        let get = |name| field(name).ok_or_else(|| format!("missing field `{}`", name));
+        let list = |name| -> Result<Vec<String>, String> {
+            let text = get(name)?;
+            Ok(text
+                .split(';')
+                .filter(|s| !s.is_empty())
+                .map(str::to_owned)
+                .collect())
+        };
+        let parse_error = |name, value: &str| format!("invalid value `{}` of `{}`", value, name);
+
+        let dims = list("dims")?
+            .iter()
+            .map(|d| d.parse().map_err(|_| parse_error("dims", d)))
+            .collect::<Result<_, _>>()?;
+        let discarded = list("discarded")?;
+        let mut samples = Vec::new();
+        for (i, sample) in list("samples")?.iter().enumerate() {
+            let seconds: f64 = sample.parse().map_err(|_| parse_error("samples", sample))?;
+            if discarded.get(i).is_none_or(|d| d == "0") {
+                samples.push(Duration::from_secs_f64(seconds));
+            }
+        }
+        if samples.is_empty() {
+            return Err("no samples".into());
+        }
+        Ok(Some(Measurement {
+            kernel: get("kernel")?,
+            dataset: get("dataset")?,
+            dims,
+            layout: field("layout").unwrap_or_default(),
+            pages: field("pages").unwrap_or_default(),
+            timer: field("timer").unwrap_or_default(),
+            padding: field("padding").unwrap_or_default(),
+            samples,
+        }))
+    }
+}
+
+/// Reads the measurements of a results file, which is CSV if its name ends with `.csv` and JSON
+/// Lines otherwise.
+pub fn read(path: &str) -> Result<Vec<Measurement>, String> {
+    let contents =
+        fs::read_to_string(path).map_err(|e| format!("cannot read `{}`: {}", path, e))?;
+    let mut lines = contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let mut measurements = Vec::new();
+    if path.ends_with(".csv") {
+        let header = match lines.next() {
+            Some((_, header)) => parse_csv(header),
+            None => return Ok(measurements),
+        };
+        for (number, line) in lines {
+            let row = parse_csv(line);
+            let field = |name: &str| Some(row.get(header.iter().position(|h| h == name)?)?.clone());
+            measurements.extend(
+                Measurement::from_fields(field)
+                    .map_err(|e| format!("{}:{}: {}", path, number + 1, e))?,
+            );
+        }
+    } else {
+        for (number, line) in lines {
+            let error = |e| format!("{}:{}: {}", path, number + 1, e);
+            let object = parse_json(line).map_err(error)?;
+            let field = |name: &str| Some(object.iter().find(|(key, _)| key == name)?.1.text());
+            measurements.extend(Measurement::from_fields(field).map_err(error)?);
+        }
+    }
+    Ok(measurements)
+}
+
+/// Splits a CSV line into its fields; quoted fields must not span lines.
+fn parse_csv(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+/// Parses a JSON object whose values are strings, numbers or arrays of them, which is all a
+/// results file contains. `null` is read as NaN.
+fn parse_json(line: &str) -> Result<Vec<(String, Value)>, String> {
+    fn skip_whitespace(chars: &mut Peekable<Chars>) {
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+    }
+
+    fn expect(chars: &mut Peekable<Chars>, expected: char) -> Result<(), String> {
+        skip_whitespace(chars);
+        match chars.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!("expected `{}` but found `{}`", expected, c)),
+            None => Err(format!(
+                "expected `{}` but found the end of the line",
+                expected
+            )),
+        }
+    }
+
+    fn string(chars: &mut Peekable<Chars>) -> Result<String, String> {
+        expect(chars, '"')?;
+        let mut s = String::new();
+        loop {
+            match chars.next().ok_or("unterminated string")? {
+                '"' => return Ok(s),
+                '\\' => match chars.next().ok_or("unterminated string")? {
+                    'n' => s.push('\n'),
+                    't' => s.push('\t'),
+                    'r' => s.push('\r'),
+                    'u' => {
+                        let hex: String = chars.by_ref().take(4).collect();
+                        let c = u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| format!("invalid escape `\\u{}`", hex))?;
+                        s.push(c);
+                    }
+                    c => s.push(c),
+                },
+                c => s.push(c),
+            }
+        }
+    }
+
+    fn value(chars: &mut Peekable<Chars>) -> Result<Value, String> {
+        skip_whitespace(chars);
+        match chars.peek() {
+            Some('"') => Ok(Value::Str(string(chars)?)),
+            Some('[') => {
+                chars.next();
+                let mut values = Vec::new();
+                skip_whitespace(chars);
+                if chars.peek() == Some(&']') {
+                    chars.next();
+                    return Ok(Value::List(values));
+                }
+                loop {
+                    values.push(value(chars)?);
+                    skip_whitespace(chars);
+                    match chars.next() {
+                        Some(',') => {}
+                        Some(']') => return Ok(Value::List(values)),
+                        _ => return Err("expected `,` or `]`".into()),
+                    }
+                }
+            }
+            _ => {
+                let mut token = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c == ',' || c == ']' || c == '}' || c.is_whitespace() {
+                        break;
+                    }
+                    token.push(c);
+                    chars.next();
+                }
+                if token == "null" {
+                    Ok(Value::Float(f64::NAN))
+                } else if let Ok(n) = token.parse() {
+                    Ok(Value::Int(n))
+                } else {
+                    token
+                        .parse()
+                        .map(Value::Float)
+                        .map_err(|_| format!("invalid value `{}`", token))
+                }
+            }
+        }
+    }
+
+    let mut chars = line.chars().peekable();
+    let mut fields = Vec::new();
+    expect(&mut chars, '{')?;
+    skip_whitespace(&mut chars);
+    if chars.peek() == Some(&'}') {
+        chars.next();
+    } else {
+        loop {
+            let name = string(&mut chars)?;
+            expect(&mut chars, ':')?;
+            fields.push((name, value(&mut chars)?));
+            skip_whitespace(&mut chars);
+            match chars.next() {
+                Some(',') => {}
+                Some('}') => break,
+                _ => return Err("expected `,` or `}`".into()),
+            }
+        }
+    }
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_escapes_strings() {
+        let mut out = String::new();
+        Value::List(vec![
+            Value::Str("a \"b\"\\\n\u{1}".to_owned()),
+            Value::Int(3),
+            Value::Float(f64::NAN),
+        ])
+        .json(&mut out);
+        assert_eq!(r#"["a \"b\"\\\n\u0001",3,null]"#, out);
+    }
+
+    #[test]
+    fn csv_quotes_fields() {
+        let mut out = String::new();
+        Value::Str("Intel(R) \"Xeon\", 2 GHz".to_owned()).csv(&mut out);
+        assert_eq!(r#""Intel(R) ""Xeon"", 2 GHz""#, out);
+
+        let mut out = String::new();
+        Value::List(vec![Value::Int(1), Value::Int(2)]).csv(&mut out);
+        assert_eq!("1;2", out);
+    }
+
+    #[test]
+    fn read_back() {
+        let line = r#"{"kernel":"gemm","dataset":"MINI","dims":[20,25,30],"samples":[0.5,1e-3,0.25],"discarded":[0,1,0], "x": null}"#;
+        let object = parse_json(line).unwrap();
+        let field = |name: &str| Some(object.iter().find(|(key, _)| key == name)?.1.text());
+        let measurement = Measurement::from_fields(field).unwrap().unwrap();
+        assert_eq!("gemm", measurement.kernel);
+        assert_eq!(vec![20, 25, 30], measurement.dims);
+        assert_eq!(
+            vec![Duration::from_millis(500), Duration::from_millis(250)],
+            measurement.samples
+        );
+
+        assert_eq!(vec!["a", "b \"c\", d", ""], parse_csv(r#"a,"b ""c"", d","#));
+
+        let failed = |name: &str| match name {
+            "status" => Some("killed by SIGKILL, possibly out of memory".to_owned()),
+            _ => None,
+        };
+        assert!(Measurement::from_fields(failed).unwrap().is_none());
+    }
+}
@@ -0,0 +1,114 @@
+//! Where and how the measuring thread is scheduled, like PolyBench/C's
+//! `POLYBENCH_LINUX_FIFO_SCHEDULER`.
+
+use std::io;
+use std::mem;
+
+/// The CPU affinity and the scheduling policy of the calling thread, restored when dropped.
+pub struct Scheduling {
+    affinity: libc::cpu_set_t,
+    policy: libc::c_int,
+    param: libc::sched_param,
+}
+
+impl Scheduling {
+    /// Pins the calling thread to `cpu` and switches it to `SCHED_FIFO` at the highest priority if
+    /// `fifo` is set.
+    ///
+    /// Failing to pin is an error, but `SCHED_FIFO` needs `CAP_SYS_NICE` or an `RLIMIT_RTPRIO`,
+    /// so the policy is left as it is with a warning if it is not permitted.
+    pub fn apply(cpu: Option<usize>, fifo: bool) -> Result<Scheduling, String> {
+        let previous = unsafe {
+            let mut affinity: libc::cpu_set_t = mem::zeroed();
+            let mut param: libc::sched_param = mem::zeroed();
+            libc::sched_getaffinity(0, mem::size_of_val(&affinity), &mut affinity);
+            libc::sched_getparam(0, &mut param);
+            Scheduling {
+                affinity,
+                policy: libc::sched_getscheduler(0),
+                param,
+            }
+        };
+
+        if let Some(cpu) = cpu {
+            let set = unsafe {
+                let mut set: libc::cpu_set_t = mem::zeroed();
+                if cpu >= 8 * mem::size_of_val(&set) {
+                    return Err(format!("invalid CPU {}", cpu));
+                }
+                libc::CPU_SET(cpu, &mut set);
+                libc::sched_setaffinity(0, mem::size_of_val(&set), &set)
+            };
+            if set != 0 {
+                return Err(format!(
+                    "cannot pin to CPU {}: {}",
+                    cpu,
+                    io::Error::last_os_error()
+                ));
+            }
+        }
+
+        if fifo {
+            let param = libc::sched_param {
+                sched_priority: unsafe { libc::sched_get_priority_max(libc::SCHED_FIFO) },
+            };
+            if unsafe { libc::sched_setscheduler(0, libc::SCHED_FIFO, &param) } != 0 {
+                eprintln!(
+                    "warning: cannot switch to SCHED_FIFO: {}",
+                    io::Error::last_os_error()
+                );
+            }
+        }
+        Ok(previous)
+    }
+
+    /// Returns the CPUs the calling thread may run on, e.g. `0-3,6`.
+    pub fn affinity() -> String {
+        let mut set: libc::cpu_set_t = unsafe { mem::zeroed() };
+        if unsafe { libc::sched_getaffinity(0, mem::size_of_val(&set), &mut set) } != 0 {
+            return "unknown".to_owned();
+        }
+        let cpus: Vec<usize> = (0..8 * mem::size_of_val(&set))
+            .filter(|&cpu| unsafe { libc::CPU_ISSET(cpu, &set) })
+            .collect();
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+        for cpu in cpus {
+            match ranges.last_mut() {
+                Some((_, last)) if *last + 1 == cpu => *last = cpu,
+                _ => ranges.push((cpu, cpu)),
+            }
+        }
+        ranges
+            .iter()
+            .map(|&(first, last)| match last - first {
+                0 => first.to_string(),
+                _ => format!("{}-{}", first, last),
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Returns the scheduling policy of the calling thread and its priority, e.g. `SCHED_FIFO 99`.
+    pub fn policy() -> String {
+        let mut param: libc::sched_param = unsafe { mem::zeroed() };
+        unsafe { libc::sched_getparam(0, &mut param) };
+        let name = match unsafe { libc::sched_getscheduler(0) } {
+            libc::SCHED_OTHER => "SCHED_OTHER",
+            libc::SCHED_FIFO => "SCHED_FIFO",
+            libc::SCHED_RR => "SCHED_RR",
+            libc::SCHED_BATCH => "SCHED_BATCH",
+            libc::SCHED_IDLE => "SCHED_IDLE",
+            _ => "unknown",
+        };
+        format!("{} {}", name, param.sched_priority)
+    }
+}
+
+impl Drop for Scheduling {
+    fn drop(&mut self) {
+        unsafe {
+            libc::sched_setscheduler(0, self.policy, &self.param);
+            libc::sched_setaffinity(0, mem::size_of_val(&self.affinity), &self.affinity);
+        }
+    }
+}
@@ -0,0 +1,320 @@
+//! Performance counters of the calling thread around every measurement, read with Linux's
+//! `perf_event_open` and `getrusage`.
+
+use crate::timer::Timer;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::mem;
+use core::time::Duration;
+
+/// An event that `perf_event_open` can count.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    Cycles,
+    Instructions,
+    L1dMisses,
+    LlcMisses,
+    BranchMisses,
+    TaskClock,
+    PageFaults,
+    ContextSwitches,
+}
+
+impl Event {
+    pub const HARDWARE: [Event; 5] = [
+        Event::Cycles,
+        Event::Instructions,
+        Event::L1dMisses,
+        Event::LlcMisses,
+        Event::BranchMisses,
+    ];
+
+    /// The events that are counted when there are no hardware counters, e.g. in most VMs.
+    pub const SOFTWARE: [Event; 3] = [Event::TaskClock, Event::PageFaults, Event::ContextSwitches];
+
+    /// Returns the name `perf` uses for the event.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Event::Cycles => "cycles",
+            Event::Instructions => "instructions",
+            Event::L1dMisses => "L1-dcache-load-misses",
+            Event::LlcMisses => "LLC-load-misses",
+            Event::BranchMisses => "branch-misses",
+            Event::TaskClock => "task-clock",
+            Event::PageFaults => "page-faults",
+            Event::ContextSwitches => "context-switches",
+        }
+    }
+
+    /// Returns `perf_event_attr.type` and `perf_event_attr.config`.
+    const fn config(self) -> (u32, u64) {
+        const HARDWARE: u32 = 0;
+        const SOFTWARE: u32 = 1;
+        const HW_CACHE: u32 = 3;
+        // `id | op << 8 | result << 16` with `op` PERF_COUNT_HW_CACHE_OP_READ and `result`
+        // PERF_COUNT_HW_CACHE_RESULT_MISS.
+        const fn read_misses(cache: u64) -> u64 {
+            cache | 1 << 16
+        }
+        match self {
+            Event::Cycles => (HARDWARE, 0),
+            Event::Instructions => (HARDWARE, 1),
+            Event::BranchMisses => (HARDWARE, 5),
+            Event::L1dMisses => (HW_CACHE, read_misses(0)),
+            Event::LlcMisses => (HW_CACHE, read_misses(2)),
+            Event::TaskClock => (SOFTWARE, 1),
+            Event::PageFaults => (SOFTWARE, 2),
+            Event::ContextSwitches => (SOFTWARE, 3),
+        }
+    }
+}
+
+/// The first `PERF_ATTR_SIZE_VER0` bytes of `struct perf_event_attr`, which every kernel since
+/// 2.6.31 accepts.
+#[repr(C)]
+#[derive(Default)]
+struct PerfEventAttr {
+    type_: u32,
+    size: u32,
+    config: u64,
+    sample_period: u64,
+    sample_type: u64,
+    read_format: u64,
+    flags: u64,
+    wakeup_events: u32,
+    bp_type: u32,
+    config1: u64,
+}
+
+const PERF_FORMAT_TOTAL_TIME_ENABLED: u64 = 1;
+const PERF_FORMAT_TOTAL_TIME_RUNNING: u64 = 2;
+const EXCLUDE_KERNEL: u64 = 1 << 5;
+const EXCLUDE_HV: u64 = 1 << 6;
+const PERF_FLAG_FD_CLOEXEC: libc::c_ulong = 8;
+
+/// A counter value with the times the event was enabled and actually counted, which differ if the
+/// kernel multiplexes more events than there are hardware counters.
+#[derive(Clone, Copy, Default)]
+struct Reading {
+    value: u64,
+    enabled: u64,
+    running: u64,
+}
+
+/// The fields of `getrusage` that are reported, in the order of [`RUSAGE`].
+type Rusage = [u64; 6];
+
+/// The names under which the `getrusage` deltas are reported.
+pub const RUSAGE: [&str; 6] = [
+    "ru_utime_us",
+    "ru_stime_us",
+    "ru_minflt",
+    "ru_majflt",
+    "ru_nvcsw",
+    "ru_nivcsw",
+];
+
+fn rusage() -> Rusage {
+    let mut usage: libc::rusage = unsafe { mem::zeroed() };
+    unsafe { libc::getrusage(libc::RUSAGE_THREAD, &mut usage) };
+    let micros = |time: libc::timeval| time.tv_sec as u64 * 1_000_000 + time.tv_usec as u64;
+    [
+        micros(usage.ru_utime),
+        micros(usage.ru_stime),
+        usage.ru_minflt as u64,
+        usage.ru_majflt as u64,
+        usage.ru_nvcsw as u64,
+        usage.ru_nivcsw as u64,
+    ]
+}
+
+/// Counters of the calling thread that are read at the start and the end of every measurement of
+/// a [`Counted`] timer.
+///
+/// Only user-space events are counted, which is all that the default `perf_event_paranoid`
+/// setting of 2 allows.
+pub struct Counters {
+    events: Vec<(Event, libc::c_int)>,
+    begin: RefCell<(Vec<Reading>, Rusage)>,
+    last: RefCell<Vec<(&'static str, u64)>>,
+}
+
+impl Counters {
+    /// Opens the [`Event::HARDWARE`] events that are available, or the [`Event::SOFTWARE`]
+    /// events if none is. The `getrusage` deltas are always reported.
+    pub fn new() -> Counters {
+        let counters = Counters::open(&Event::HARDWARE);
+        if counters.events.is_empty() {
+            Counters::open(&Event::SOFTWARE)
+        } else {
+            counters
+        }
+    }
+
+    /// Opens the given events, skipping those that are not available.
+    pub fn open(events: &[Event]) -> Counters {
+        let events = events
+            .iter()
+            .filter_map(|&event| {
+                let (type_, config) = event.config();
+                let attr = PerfEventAttr {
+                    type_,
+                    size: mem::size_of::<PerfEventAttr>() as u32,
+                    config,
+                    read_format: PERF_FORMAT_TOTAL_TIME_ENABLED | PERF_FORMAT_TOTAL_TIME_RUNNING,
+                    flags: EXCLUDE_KERNEL | EXCLUDE_HV,
+                    ..PerfEventAttr::default()
+                };
+                // The calling thread on any CPU, without a group leader.
+                let fd = unsafe {
+                    libc::syscall(
+                        libc::SYS_perf_event_open,
+                        &attr as *const PerfEventAttr,
+                        0,
+                        -1,
+                        -1,
+                        PERF_FLAG_FD_CLOEXEC,
+                    )
+                };
+                if fd >= 0 {
+                    Some((event, fd as libc::c_int))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        Counters {
+            events,
+            begin: RefCell::new((Vec::new(), [0; 6])),
+            last: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Returns the events that could be opened.
+    pub fn events(&self) -> impl Iterator<Item = Event> + '_ {
+        self.events.iter().map(|&(event, _)| event)
+    }
+
+    fn read(&self) -> Vec<Reading> {
+        self.events
+            .iter()
+            .map(|&(_, fd)| {
+                let mut reading = [0u64; 3];
+                let size = mem::size_of_val(&reading);
+                let read = unsafe { libc::read(fd, reading.as_mut_ptr() as *mut _, size) };
+                if read == size as isize {
+                    Reading {
+                        value: reading[0],
+                        enabled: reading[1],
+                        running: reading[2],
+                    }
+                } else {
+                    Reading::default()
+                }
+            })
+            .collect()
+    }
+
+    fn start(&self) {
+        *self.begin.borrow_mut() = (self.read(), rusage());
+    }
+
+    fn stop(&self) {
+        let (end, end_rusage) = (self.read(), rusage());
+        let begin = self.begin.borrow();
+        let mut last = self.last.borrow_mut();
+        last.clear();
+        for ((&(event, _), begin), end) in self.events.iter().zip(&begin.0).zip(&end) {
+            let value = end.value.saturating_sub(begin.value);
+            let enabled = end.enabled.saturating_sub(begin.enabled);
+            let running = end.running.saturating_sub(begin.running);
+            // Scales the count up to the whole measurement if the event was multiplexed.
+            let value = if running > 0 && running < enabled {
+                (value as f64 * enabled as f64 / running as f64) as u64
+            } else {
+                value
+            };
+            last.push((event.name(), value));
+        }
+        for ((&name, begin), end) in RUSAGE.iter().zip(&begin.1).zip(&end_rusage) {
+            last.push((name, end.saturating_sub(*begin)));
+        }
+    }
+
+    /// Returns the counts of the last measurement by name, the events first and the `getrusage`
+    /// deltas after them.
+    pub fn last(&self) -> Vec<(&'static str, u64)> {
+        self.last.borrow().clone()
+    }
+}
+
+impl Default for Counters {
+    fn default() -> Counters {
+        Counters::new()
+    }
+}
+
+impl Drop for Counters {
+    fn drop(&mut self) {
+        for &(_, fd) in &self.events {
+            unsafe { libc::close(fd) };
+        }
+    }
+}
+
+/// A timer that also reads `counters` around every measurement.
+pub struct Counted<'a> {
+    pub timer: &'a dyn Timer,
+    pub counters: &'a Counters,
+}
+
+impl Timer for Counted<'_> {
+    fn ticks(&self) -> u64 {
+        self.timer.ticks()
+    }
+
+    fn frequency(&self) -> u64 {
+        self.timer.frequency()
+    }
+
+    fn elapsed(&self, begin: u64, end: u64) -> Duration {
+        self.timer.elapsed(begin, end)
+    }
+
+    fn start(&self) {
+        self.counters.start();
+        self.timer.start();
+    }
+
+    fn stop(&self) {
+        self.timer.stop();
+        self.counters.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn software_counters() {
+        let counters = Counters::open(&Event::SOFTWARE);
+        let timer = crate::timer::FnTimer::nanos(|| 0);
+        let counted = Counted {
+            timer: &timer,
+            counters: &counters,
+        };
+        counted.start();
+        let pages = alloc::vec![1u8; 1 << 20];
+        crate::util::consume(pages);
+        counted.stop();
+
+        let last = counters.last();
+        let names: Vec<_> = last.iter().map(|(name, _)| *name).collect();
+        assert!(names.ends_with(&RUSAGE));
+        // perf_event_open is not permitted everywhere, e.g. in some containers.
+        if counters.events().any(|e| e == Event::PageFaults) {
+            assert!(last.iter().any(|&(name, n)| name == "page-faults" && n > 0));
+        }
+    }
+}
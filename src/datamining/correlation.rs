@@ -1,99 +1,223 @@
-use crate::config::datamining::correlation::DataType;
-use crate::ndarray::{Array1D, Array2D, ArrayAlloc};
+pub use crate::config::datamining::correlation::DataType;
+#[cfg(any(test, feature = "print-result"))]
+use crate::dump::Dump;
+use crate::layout::{Layout, LayoutArray2D};
+use crate::ndarray::{
+    AllocError, Array1, Array1D, Array2, Array2D, ArrayAlloc, Contiguous, DynArray1D, DynArray2D,
+};
+use crate::pages::PageAllocator;
+use crate::timer::Timer;
 use crate::util;
-use core::mem::MaybeUninit;
+use core::alloc::Allocator;
 use core::time::Duration;
 
-unsafe fn init_array<const M: usize, const N: usize>(
-    m: usize,
-    n: usize,
-    float_n: &mut DataType,
-    data: &mut MaybeUninit<Array2D<DataType, M, N>>,
-) {
-    let data = unsafe { data.assume_init_mut() };
-
+unsafe fn init_array(m: usize, n: usize, float_n: &mut DataType, data: &mut impl Array2<DataType>) {
     *float_n = n as DataType;
+    for i in 0..n {
+        for j in 0..m {
+            data[i][j] = (i * j) as DataType / m as DataType + i as DataType;
+        }
+    }
+}
+
+#[cfg(any(test, feature = "print-result"))]
+fn print_array(m: usize, corr: &impl Array2<DataType>, dump: &mut impl Dump) {
+    dump.begin("corr");
     for i in 0..m {
-        for j in 0..n {
-            data[i][j] = (i * j) as DataType / (N + i) as DataType;
+        for j in 0..m {
+            if (i * m + j).is_multiple_of(20) {
+                dump.newline();
+            }
+            dump.value(corr[i][j]);
         }
     }
+    dump.end("corr");
 }
 
-unsafe fn kernel_correlation<const M: usize, const N: usize>(
+unsafe fn kernel_correlation(
     m: usize,
     n: usize,
     float_n: DataType,
-    data: &mut Array2D<DataType, M, N>,
-    corr: &mut Array2D<DataType, N, N>,
-    mean: &mut Array1D<DataType, N>,
-    stddev: &mut Array1D<DataType, N>,
+    data: &mut impl Array2<DataType>,
+    corr: &mut impl Array2<DataType>,
+    mean: &mut impl Array1<DataType>,
+    stddev: &mut impl Array1<DataType>,
 ) {
     let eps = 0.1;
 
-    for j in 0..n {
+    for j in 0..m {
         mean[j] = 0.0;
-        for i in 0..m {
+        for i in 0..n {
             mean[j] += data[i][j];
         }
         mean[j] /= float_n;
     }
 
-    for j in 0..n {
+    for j in 0..m {
         stddev[j] = 0.0;
-        for i in 0..m {
+        for i in 0..n {
             stddev[j] += (data[i][j] - mean[j]) * (data[i][j] - mean[j]);
-            stddev[j] /= float_n;
-            stddev[j] = stddev[j].sqrt();
-            stddev[j] = if stddev[j] <= eps { 1.0 } else { stddev[j] };
         }
+        stddev[j] /= float_n;
+        stddev[j] = stddev[j].sqrt();
+        stddev[j] = if stddev[j] <= eps { 1.0 } else { stddev[j] };
     }
 
-    for i in 0..m {
-        for j in 0..n {
+    for i in 0..n {
+        for j in 0..m {
             data[i][j] -= mean[j];
             data[i][j] /= float_n.sqrt() * stddev[j];
         }
     }
 
-    for i in 0..(n - 1) {
+    for i in 0..(m - 1) {
         corr[i][i] = 1.0;
-        for j in (i + 1)..n {
+        for j in (i + 1)..m {
             corr[i][j] = 0.0;
-            for k in 0..m {
+            for k in 0..n {
                 corr[i][j] += data[k][i] * data[k][j];
             }
             corr[j][i] = corr[i][j];
         }
     }
-    corr[n - 1][n - 1] = 1.0;
+    corr[m - 1][m - 1] = 1.0;
+}
+
+pub fn bench<const M: usize, const N: usize>(timer: &dyn Timer) -> Duration {
+    bench_in::<M, N, _>(timer, PageAllocator::default())
 }
 
-pub fn bench<const M: usize, const N: usize>(timing_function: &dyn Fn() -> u64) -> Duration {
+/// Like [`bench`], but allocates the arrays with `alloc` instead of a [`PageAllocator`].
+pub fn bench_in<const M: usize, const N: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Duration {
+    try_bench_in::<M, N, _>(timer, alloc).unwrap_or_else(|e| e.handle())
+}
+
+/// Like [`bench`], but returns an error instead of aborting if the arrays cannot be allocated.
+pub fn try_bench<const M: usize, const N: usize>(
+    timer: &dyn Timer,
+) -> Result<Duration, AllocError> {
+    try_bench_in::<M, N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench_in`], but returns an error instead of aborting if the arrays cannot be
+/// allocated.
+pub fn try_bench_in<const M: usize, const N: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Result<Duration, AllocError> {
     let m = M;
     let n = N;
 
     let mut float_n = 0.0;
-    let mut data = Array2D::<DataType, M, N>::maybe_uninit();
-    let corr = Array2D::<DataType, N, N>::maybe_uninit();
-    let mean = Array1D::<DataType, N>::maybe_uninit();
-    let stddev = Array1D::<DataType, N>::maybe_uninit();
+    let mut data = Array2D::<DataType, N, M>::try_maybe_uninit_in(&alloc)?;
+    let mut corr = Array2D::<DataType, M, M>::try_maybe_uninit_in(&alloc)?;
+    let mut mean = Array1D::<DataType, M>::try_maybe_uninit_in(&alloc)?;
+    let mut stddev = Array1D::<DataType, M>::try_maybe_uninit_in(&alloc)?;
+
+    unsafe {
+        let data = data.assume_init_mut();
+        let corr = corr.assume_init_mut();
+        let mean = mean.assume_init_mut();
+        let stddev = stddev.assume_init_mut();
+        init_array(m, n, &mut float_n, data);
+
+        let elapsed = util::benchmark_with_timer(
+            &[data.extent(), corr.extent(), mean.extent(), stddev.extent()],
+            || kernel_correlation(m, n, float_n, data, corr, mean, stddev),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(m, corr, &mut crate::dump::Results::start());
+        util::consume(corr);
+        Ok(elapsed)
+    }
+}
+
+/// Runs the same benchmark as [`bench`] with dimensions that are only known at runtime, given in
+/// the order of its const generics.
+pub fn bench_dyn(dims: [usize; 2], timer: &dyn Timer) -> Duration {
+    let [m, n] = dims;
+
+    let mut float_n = 0.0;
 
     unsafe {
+        let mut data = DynArray2D::<DataType>::uninit(n, m);
+        let mut corr = DynArray2D::<DataType>::uninit(m, m);
+        let mut mean = DynArray1D::<DataType>::uninit(m);
+        let mut stddev = DynArray1D::<DataType>::uninit(m);
         init_array(m, n, &mut float_n, &mut data);
-        let mut data = data.assume_init();
-        let mut corr = corr.assume_init();
-        let mut mean = mean.assume_init();
-        let mut stddev = stddev.assume_init();
 
-        let elapsed = util::benchmark_with_timing_function(
+        let elapsed = util::benchmark_with_timer(
+            &[data.extent(), corr.extent(), mean.extent(), stddev.extent()],
             || kernel_correlation(m, n, float_n, &mut data, &mut corr, &mut mean, &mut stddev),
-            timing_function,
+            timer,
         );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(m, &corr, &mut crate::dump::Results::start());
         util::consume(corr);
         elapsed
     }
 }
 
-#[test]
-fn check() {}
+/// Like [`bench_dyn`], but stores the arrays with two or three dimensions in the layout `L`.
+pub fn bench_layout<L: Layout>(dims: [usize; 2], timer: &dyn Timer) -> Duration {
+    let [m, n] = dims;
+
+    let mut float_n = 0.0;
+
+    unsafe {
+        let mut data = LayoutArray2D::<DataType, L>::uninit(n, m);
+        let mut corr = LayoutArray2D::<DataType, L>::uninit(m, m);
+        let mut mean = DynArray1D::<DataType>::uninit(m);
+        let mut stddev = DynArray1D::<DataType>::uninit(m);
+        init_array(m, n, &mut float_n, &mut data);
+
+        let elapsed = util::benchmark_with_timer(
+            &[data.extent(), corr.extent(), mean.extent(), stddev.extent()],
+            || kernel_correlation(m, n, float_n, &mut data, &mut corr, &mut mean, &mut stddev),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(m, &corr, &mut crate::dump::Results::start());
+        util::consume(corr);
+        elapsed
+    }
+}
+
+/// Floating-point operations of one run of [`bench`] with `dims`: M·((M + 6)·N + 3), the sum of
+///
+/// - M·N additions and M divisions for the means,
+/// - 3·M·N + 2·M for the standard deviations: a subtraction, a multiplication and an addition per
+///   data point, counting the difference that is squared once, and a division and a square root
+///   per attribute,
+/// - 3·M·N for the normalization: a subtraction, a multiplication and a division per data point,
+///   not counting the square root of N, which is the same for all of them,
+/// - M·(M − 1)·N for the correlations: a multiply-add per data point for each of the M·(M − 1)/2
+///   pairs of attributes.
+pub fn flops(dims: [usize; 2]) -> u64 {
+    let [m, n] = dims.map(|d| d as u64);
+    m * ((m + 6) * n + 3)
+}
+
+/// Bytes of the arrays [`bench_dyn`] allocates with `dims`: N·M + M² + 2·M elements.
+pub fn footprint(dims: [usize; 2]) -> u64 {
+    let [m, n] = dims;
+    crate::ndarray::footprint::<DataType>(&[&[n, m], &[m, m], &[m], &[m]])
+}
+
+crate::dataset::datasets! {
+    Mini: [28, 32],
+    Small: [80, 100],
+    Medium: [240, 260],
+    Large: [1200, 1400],
+    ExtraLarge: [2600, 3000],
+}
+
+#[cfg(test)]
+crate::dataset::checksums! {
+    Mini: [("corr", 784.0, 307720.0)],
+    Small: [("corr", 6400.0, 20483200.0)],
+}
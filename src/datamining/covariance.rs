@@ -1,51 +1,65 @@
-use crate::config::datamining::covariance::DataType;
-use crate::ndarray::{Array1D, Array2D, ArrayAlloc};
+pub use crate::config::datamining::covariance::DataType;
+#[cfg(any(test, feature = "print-result"))]
+use crate::dump::Dump;
+use crate::layout::{Layout, LayoutArray2D};
+use crate::ndarray::{
+    AllocError, Array1, Array1D, Array2, Array2D, ArrayAlloc, Contiguous, DynArray1D, DynArray2D,
+};
+use crate::pages::PageAllocator;
+use crate::timer::Timer;
 use crate::util;
-use core::mem::MaybeUninit;
+use core::alloc::Allocator;
 use core::time::Duration;
 
-unsafe fn init_array<const M: usize, const N: usize>(
-    m: usize,
-    n: usize,
-    float_n: &mut DataType,
-    data: &mut MaybeUninit<Array2D<DataType, M, N>>,
-) {
-    let data = unsafe { data.assume_init_mut() };
-
+unsafe fn init_array(m: usize, n: usize, float_n: &mut DataType, data: &mut impl Array2<DataType>) {
     *float_n = n as DataType;
+    for i in 0..n {
+        for j in 0..m {
+            data[i][j] = (i * j) as DataType / m as DataType;
+        }
+    }
+}
+
+#[cfg(any(test, feature = "print-result"))]
+fn print_array(m: usize, cov: &impl Array2<DataType>, dump: &mut impl Dump) {
+    dump.begin("cov");
     for i in 0..m {
-        for j in 0..n {
-            data[i][j] = (i * j) as DataType / N as DataType;
+        for j in 0..m {
+            if (i * m + j).is_multiple_of(20) {
+                dump.newline();
+            }
+            dump.value(cov[i][j]);
         }
     }
+    dump.end("cov");
 }
 
-unsafe fn kernel_covariance<const M: usize, const N: usize>(
+unsafe fn kernel_covariance(
     m: usize,
     n: usize,
     float_n: DataType,
-    data: &mut Array2D<DataType, M, N>,
-    cov: &mut Array2D<DataType, N, N>,
-    mean: &mut Array1D<DataType, N>,
+    data: &mut impl Array2<DataType>,
+    cov: &mut impl Array2<DataType>,
+    mean: &mut impl Array1<DataType>,
 ) {
-    for j in 0..n {
+    for j in 0..m {
         mean[j] = 0.0;
-        for i in 0..m {
+        for i in 0..n {
             mean[j] += data[i][j];
         }
         mean[j] /= float_n;
     }
 
-    for i in 0..m {
-        for j in 0..n {
+    for i in 0..n {
+        for j in 0..m {
             data[i][j] -= mean[j];
         }
     }
 
-    for i in 0..n {
-        for j in i..n {
+    for i in 0..m {
+        for j in i..m {
             cov[i][j] = 0.0;
-            for k in 0..m {
+            for k in 0..n {
                 cov[i][j] += data[k][i] * data[k][j];
             }
             cov[i][j] /= float_n - 1.0;
@@ -54,29 +68,132 @@ unsafe fn kernel_covariance<const M: usize, const N: usize>(
     }
 }
 
-pub fn bench<const M: usize, const N: usize>(timing_function: &dyn Fn() -> u64) -> Duration {
+pub fn bench<const M: usize, const N: usize>(timer: &dyn Timer) -> Duration {
+    bench_in::<M, N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench`], but allocates the arrays with `alloc` instead of a [`PageAllocator`].
+pub fn bench_in<const M: usize, const N: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Duration {
+    try_bench_in::<M, N, _>(timer, alloc).unwrap_or_else(|e| e.handle())
+}
+
+/// Like [`bench`], but returns an error instead of aborting if the arrays cannot be allocated.
+pub fn try_bench<const M: usize, const N: usize>(
+    timer: &dyn Timer,
+) -> Result<Duration, AllocError> {
+    try_bench_in::<M, N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench_in`], but returns an error instead of aborting if the arrays cannot be
+/// allocated.
+pub fn try_bench_in<const M: usize, const N: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Result<Duration, AllocError> {
     let m = M;
     let n = N;
 
     let mut float_n = 0.0;
-    let mut data = Array2D::<DataType, M, N>::maybe_uninit();
-    let cov = Array2D::<DataType, N, N>::maybe_uninit();
-    let mean = Array1D::<DataType, N>::maybe_uninit();
+    let mut data = Array2D::<DataType, N, M>::try_maybe_uninit_in(&alloc)?;
+    let mut cov = Array2D::<DataType, M, M>::try_maybe_uninit_in(&alloc)?;
+    let mut mean = Array1D::<DataType, M>::try_maybe_uninit_in(&alloc)?;
+
+    unsafe {
+        let data = data.assume_init_mut();
+        let cov = cov.assume_init_mut();
+        let mean = mean.assume_init_mut();
+        init_array(m, n, &mut float_n, data);
+
+        let elapsed = util::benchmark_with_timer(
+            &[data.extent(), cov.extent(), mean.extent()],
+            || kernel_covariance(m, n, float_n, data, cov, mean),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(m, cov, &mut crate::dump::Results::start());
+        util::consume(cov);
+        Ok(elapsed)
+    }
+}
+
+/// Runs the same benchmark as [`bench`] with dimensions that are only known at runtime, given in
+/// the order of its const generics.
+pub fn bench_dyn(dims: [usize; 2], timer: &dyn Timer) -> Duration {
+    let [m, n] = dims;
+
+    let mut float_n = 0.0;
 
     unsafe {
+        let mut data = DynArray2D::<DataType>::uninit(n, m);
+        let mut cov = DynArray2D::<DataType>::uninit(m, m);
+        let mut mean = DynArray1D::<DataType>::uninit(m);
         init_array(m, n, &mut float_n, &mut data);
-        let mut data = data.assume_init();
-        let mut cov = cov.assume_init();
-        let mut mean = mean.assume_init();
 
-        let elapsed = util::benchmark_with_timing_function(
+        let elapsed = util::benchmark_with_timer(
+            &[data.extent(), cov.extent(), mean.extent()],
             || kernel_covariance(m, n, float_n, &mut data, &mut cov, &mut mean),
-            timing_function,
+            timer,
         );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(m, &cov, &mut crate::dump::Results::start());
         util::consume(cov);
         elapsed
     }
 }
 
-#[test]
-fn check() {}
+/// Like [`bench_dyn`], but stores the arrays with two or three dimensions in the layout `L`.
+pub fn bench_layout<L: Layout>(dims: [usize; 2], timer: &dyn Timer) -> Duration {
+    let [m, n] = dims;
+
+    let mut float_n = 0.0;
+
+    unsafe {
+        let mut data = LayoutArray2D::<DataType, L>::uninit(n, m);
+        let mut cov = LayoutArray2D::<DataType, L>::uninit(m, m);
+        let mut mean = DynArray1D::<DataType>::uninit(m);
+        init_array(m, n, &mut float_n, &mut data);
+
+        let elapsed = util::benchmark_with_timer(
+            &[data.extent(), cov.extent(), mean.extent()],
+            || kernel_covariance(m, n, float_n, &mut data, &mut cov, &mut mean),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(m, &cov, &mut crate::dump::Results::start());
+        util::consume(cov);
+        elapsed
+    }
+}
+
+/// Floating-point operations of one run of [`bench`] with `dims`:
+/// M·(N + 1) + M·N + M·(M + 1)·(2·N + 1) / 2.
+///
+/// The means and the centering take one or two operations per data point and attribute, and every
+/// entry of the upper triangle of `cov` a multiply-add per data point and a division.
+pub fn flops(dims: [usize; 2]) -> u64 {
+    let [m, n] = dims.map(|d| d as u64);
+    m * (n + 1) + m * n + m * (m + 1) * (2 * n + 1) / 2
+}
+
+/// Bytes of the arrays [`bench_dyn`] allocates with `dims`: N·M + M² + M elements.
+pub fn footprint(dims: [usize; 2]) -> u64 {
+    let [m, n] = dims;
+    crate::ndarray::footprint::<DataType>(&[&[n, m], &[m, m], &[m]])
+}
+
+crate::dataset::datasets! {
+    Mini: [28, 32],
+    Small: [80, 100],
+    Medium: [240, 260],
+    Large: [1200, 1400],
+    ExtraLarge: [2600, 3000],
+}
+
+#[cfg(test)]
+crate::dataset::checksums! {
+    Mini: [("cov", 16038.0, 8542907.999999998)],
+    Small: [("cov", 1313210.4166666667, 5638925529.166683)],
+}
@@ -0,0 +1,122 @@
+/// The standard problem sizes defined by PolyBench/C 4.2.
+///
+/// Every kernel module re-exports this type together with a `dims` function that gives the
+/// official dimensions of each preset and a `bench_dataset` function that runs `bench` with them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Dataset {
+    Mini,
+    Small,
+    Medium,
+    Large,
+    ExtraLarge,
+}
+
+impl Dataset {
+    pub const ALL: [Dataset; 5] = [
+        Dataset::Mini,
+        Dataset::Small,
+        Dataset::Medium,
+        Dataset::Large,
+        Dataset::ExtraLarge,
+    ];
+
+    /// Returns the name used by PolyBench/C, without the `_DATASET` suffix.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Dataset::Mini => "MINI",
+            Dataset::Small => "SMALL",
+            Dataset::Medium => "MEDIUM",
+            Dataset::Large => "LARGE",
+            Dataset::ExtraLarge => "EXTRALARGE",
+        }
+    }
+
+    /// Parses a dataset name case-insensitively, e.g. `large` or `EXTRALARGE_DATASET`.
+    pub fn from_name(name: &str) -> Option<Dataset> {
+        let name = name
+            .strip_suffix("_DATASET")
+            .or_else(|| name.strip_suffix("_dataset"))
+            .unwrap_or(name);
+        Dataset::ALL.iter().copied().find(|d| {
+            d.name().eq_ignore_ascii_case(name)
+                || (*d == Dataset::ExtraLarge && name.eq_ignore_ascii_case("extra_large"))
+        })
+    }
+}
+
+/// Defines `dims` and `bench_dataset` for the kernel module it is invoked in.
+///
+/// The dimensions of every preset must be listed in the order of the const generics of `bench`.
+macro_rules! datasets {
+    ($($dataset:ident: [$($dim:literal),+],)+) => {
+        pub use crate::dataset::Dataset;
+
+        /// Returns the PolyBench/C dimensions of `dataset` in the order of the const generics of
+        /// [`bench`].
+        pub const fn dims(dataset: Dataset) -> &'static [usize] {
+            match dataset {
+                $(Dataset::$dataset => &[$($dim),+],)+
+            }
+        }
+
+        /// Runs [`bench`] with the PolyBench/C dimensions of `dataset`.
+        pub fn bench_dataset(
+            dataset: Dataset,
+            timer: &dyn crate::timer::Timer,
+        ) -> core::time::Duration {
+            match dataset {
+                $(Dataset::$dataset => bench::<$($dim),+>(timer),)+
+            }
+        }
+    };
+}
+
+pub(crate) use datasets;
+
+/// Runs `bench` with `dims` and returns the checksums of the arrays it dumps.
+#[cfg(test)]
+pub(crate) fn results<const D: usize>(
+    bench: fn([usize; D], &dyn crate::timer::Timer) -> core::time::Duration,
+    dims: &[usize],
+) -> crate::dump::Checksums {
+    use core::convert::TryInto;
+
+    bench(
+        dims.try_into().unwrap(),
+        &crate::timer::FnTimer::nanos(|| 0),
+    );
+    crate::dump::Checksums::last()
+}
+
+/// Defines the `check` test of the kernel module it is invoked in, which runs `bench_dyn` at the
+/// MINI and SMALL sizes and compares the checksums of the dumped arrays with the stored
+/// `(name, sum, weighted sum)` of every array.
+#[cfg(test)]
+macro_rules! checksums {
+    (Mini: $mini:expr, Small: $small:expr $(,)?) => {
+        #[test]
+        fn check() {
+            crate::dataset::results(bench_dyn, dims(Dataset::Mini)).assert_eq(&$mini);
+            crate::dataset::results(bench_dyn, dims(Dataset::Small)).assert_eq(&$small);
+        }
+    };
+}
+
+#[cfg(test)]
+pub(crate) use checksums;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_names() {
+        for dataset in Dataset::ALL {
+            assert_eq!(Some(dataset), Dataset::from_name(dataset.name()));
+        }
+        assert_eq!(Some(Dataset::Large), Dataset::from_name("large"));
+        assert_eq!(Some(Dataset::Mini), Dataset::from_name("MINI_DATASET"));
+        assert_eq!(Some(Dataset::ExtraLarge), Dataset::from_name("extra_large"));
+        assert_eq!(None, Dataset::from_name("huge"));
+    }
+}
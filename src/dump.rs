@@ -0,0 +1,291 @@
+/// Receives the live-out arrays of a kernel from its `print_array` function.
+///
+/// `print_array` walks the arrays exactly like its PolyBench/C counterpart: one
+/// [`Dump::begin`]/[`Dump::end`] pair per array, with [`Dump::newline`] wherever the C loop prints
+/// a line break.
+pub trait Dump {
+    fn begin(&mut self, name: &'static str);
+    fn end(&mut self, name: &'static str);
+    fn newline(&mut self);
+    fn value<T: DumpValue>(&mut self, value: T);
+}
+
+/// A value of one of the data types in [`crate::config`].
+pub trait DumpValue: Copy {
+    /// The relative error that is tolerated when a result is compared with PolyBench/C's.
+    const TOLERANCE: f64;
+
+    fn to_f64(self) -> f64;
+
+    /// Writes the value with the `DATA_PRINTF_MODIFIER` of its C counterpart.
+    #[cfg(feature = "print-result")]
+    fn write(self, out: &mut impl std::io::Write) -> std::io::Result<()>;
+}
+
+/// `%0.2lf ` and `%0.2f `, including C's spelling of the special values.
+macro_rules! dump_float {
+    ($($t:ty: $tolerance:expr),*) => {$(
+        impl DumpValue for $t {
+            const TOLERANCE: f64 = $tolerance;
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            #[cfg(feature = "print-result")]
+            fn write(self, out: &mut impl std::io::Write) -> std::io::Result<()> {
+                let sign = if self.is_sign_negative() { "-" } else { "" };
+                if self.is_nan() {
+                    write!(out, "{}nan ", sign)
+                } else if self.is_infinite() {
+                    write!(out, "{}inf ", sign)
+                } else {
+                    write!(out, "{:.2} ", self)
+                }
+            }
+        }
+    )*};
+}
+
+dump_float!(f32: 1e-5, f64: 1e-10);
+
+/// `%d `.
+impl DumpValue for i32 {
+    const TOLERANCE: f64 = 0.0;
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    #[cfg(feature = "print-result")]
+    fn write(self, out: &mut impl std::io::Write) -> std::io::Result<()> {
+        write!(out, "{} ", self)
+    }
+}
+
+/// Writes live-out arrays to stderr in the format of PolyBench/C's `POLYBENCH_DUMP_ARRAYS`, so the
+/// results can be diffed against the dumps of the C reference.
+///
+/// `==END   DUMP_ARRAYS==` is written when the `Stderr` is dropped.
+#[cfg(feature = "print-result")]
+pub struct Stderr {
+    out: std::io::BufWriter<std::io::StderrLock<'static>>,
+}
+
+#[cfg(feature = "print-result")]
+impl Stderr {
+    pub fn start() -> Stderr {
+        let mut dump = Stderr {
+            out: std::io::BufWriter::new(std::io::stderr().lock()),
+        };
+        dump.write(format_args!("==BEGIN DUMP_ARRAYS==\n"));
+        dump
+    }
+
+    /// Errors are ignored like those of `fprintf` in PolyBench/C.
+    fn write(&mut self, args: core::fmt::Arguments<'_>) {
+        let _ = std::io::Write::write_fmt(&mut self.out, args);
+    }
+}
+
+#[cfg(feature = "print-result")]
+impl Dump for Stderr {
+    fn begin(&mut self, name: &'static str) {
+        self.write(format_args!("begin dump: {}", name));
+    }
+
+    fn end(&mut self, name: &'static str) {
+        self.write(format_args!("\nend   dump: {}\n", name));
+    }
+
+    fn newline(&mut self) {
+        self.write(format_args!("\n"));
+    }
+
+    fn value<T: DumpValue>(&mut self, value: T) {
+        let _ = value.write(&mut self.out);
+    }
+}
+
+#[cfg(feature = "print-result")]
+impl Drop for Stderr {
+    fn drop(&mut self) {
+        self.write(format_args!("==END   DUMP_ARRAYS==\n"));
+    }
+}
+
+#[cfg(feature = "print-result")]
+static DUMP: core::sync::atomic::AtomicBool = core::sync::atomic::AtomicBool::new(true);
+
+/// Sets whether the benchmarks write their results to [`Stderr`], which they do by default with
+/// the `print-result` feature. The runner turns it off after the first run of every job, so that
+/// the warmup runs and samples after it do not repeat the same dump.
+#[cfg(feature = "print-result")]
+pub fn set_dump(on: bool) {
+    DUMP.store(on, core::sync::atomic::Ordering::Relaxed);
+}
+
+/// Where the benchmarks dump the live-out arrays of their kernel after measuring it: to
+/// [`Stderr`] with the `print-result` feature unless [`set_dump`] turned it off and, in tests, to
+/// the [`Checksums`] that [`Checksums::last`] returns.
+pub(crate) struct Results {
+    #[cfg(feature = "print-result")]
+    stderr: Option<Stderr>,
+    #[cfg(test)]
+    checksums: Checksums,
+}
+
+impl Results {
+    pub(crate) fn start() -> Results {
+        Results {
+            #[cfg(feature = "print-result")]
+            stderr: DUMP
+                .load(core::sync::atomic::Ordering::Relaxed)
+                .then(Stderr::start),
+            #[cfg(test)]
+            checksums: Checksums::default(),
+        }
+    }
+}
+
+impl Dump for Results {
+    fn begin(&mut self, name: &'static str) {
+        #[cfg(feature = "print-result")]
+        if let Some(stderr) = &mut self.stderr {
+            stderr.begin(name);
+        }
+        #[cfg(test)]
+        self.checksums.begin(name);
+    }
+
+    fn end(&mut self, name: &'static str) {
+        #[cfg(feature = "print-result")]
+        if let Some(stderr) = &mut self.stderr {
+            stderr.end(name);
+        }
+        #[cfg(test)]
+        self.checksums.end(name);
+    }
+
+    fn newline(&mut self) {
+        #[cfg(feature = "print-result")]
+        if let Some(stderr) = &mut self.stderr {
+            stderr.newline();
+        }
+        #[cfg(test)]
+        self.checksums.newline();
+    }
+
+    fn value<T: DumpValue>(&mut self, value: T) {
+        #[cfg(feature = "print-result")]
+        if let Some(stderr) = &mut self.stderr {
+            stderr.value(value);
+        }
+        #[cfg(test)]
+        self.checksums.value(value);
+    }
+}
+
+#[cfg(test)]
+extern crate std;
+
+#[cfg(test)]
+std::thread_local! {
+    static LAST: core::cell::RefCell<Option<Checksums>> = const { core::cell::RefCell::new(None) };
+}
+
+#[cfg(test)]
+impl Drop for Results {
+    fn drop(&mut self) {
+        let checksums = core::mem::take(&mut self.checksums);
+        LAST.with(|last| *last.borrow_mut() = Some(checksums));
+    }
+}
+
+/// Checksums of the dumped arrays, compared by the `check` tests of the kernels with stored values.
+/// These are not yet the output of `tools/golden.sh`, which computes them from PolyBench/C.
+///
+/// Every array is summarized by the sum of its values and the sum of its values weighted by their
+/// 1-based position in the dump, so that values which end up in the wrong place are caught too.
+#[cfg(test)]
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Checksums {
+    arrays: alloc::vec::Vec<Checksum>,
+}
+
+#[cfg(test)]
+#[derive(Debug, PartialEq)]
+struct Checksum {
+    name: &'static str,
+    tolerance: f64,
+    len: usize,
+    sum: f64,
+    weighted: f64,
+    /// The sums of the absolute values, which the tolerance is relative to.
+    abs_sum: f64,
+    abs_weighted: f64,
+}
+
+#[cfg(test)]
+impl Checksums {
+    /// Returns the checksums of the arrays that the last benchmark run on this thread dumped.
+    pub(crate) fn last() -> Checksums {
+        LAST.with(|last| last.borrow_mut().take())
+            .expect("no benchmark has dumped its results on this thread")
+    }
+
+    /// Panics unless the arrays and their checksums match `expected`, given as
+    /// `(name, sum, weighted sum)`.
+    pub(crate) fn assert_eq(&self, expected: &[(&str, f64, f64)]) {
+        let names: alloc::vec::Vec<_> = self.arrays.iter().map(|a| a.name).collect();
+        let expected_names: alloc::vec::Vec<_> = expected.iter().map(|e| e.0).collect();
+        assert_eq!(expected_names, names);
+
+        for (array, &(name, sum, weighted)) in self.arrays.iter().zip(expected) {
+            assert!(
+                (array.sum - sum).abs() <= array.tolerance * array.abs_sum,
+                "sum of `{}` is {:e}, expected {:e}",
+                name,
+                array.sum,
+                sum
+            );
+            assert!(
+                (array.weighted - weighted).abs() <= array.tolerance * array.abs_weighted,
+                "weighted sum of `{}` is {:e}, expected {:e}",
+                name,
+                array.weighted,
+                weighted
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+impl Dump for Checksums {
+    fn begin(&mut self, name: &'static str) {
+        self.arrays.push(Checksum {
+            name,
+            tolerance: 0.0,
+            len: 0,
+            sum: 0.0,
+            weighted: 0.0,
+            abs_sum: 0.0,
+            abs_weighted: 0.0,
+        });
+    }
+
+    fn end(&mut self, _name: &'static str) {}
+
+    fn newline(&mut self) {}
+
+    fn value<T: DumpValue>(&mut self, value: T) {
+        let array = self.arrays.last_mut().unwrap();
+        let value = value.to_f64();
+        array.tolerance = T::TOLERANCE;
+        array.len += 1;
+        array.sum += value;
+        array.weighted += value * array.len as f64;
+        array.abs_sum += value.abs();
+        array.abs_weighted += value.abs() * array.len as f64;
+    }
+}
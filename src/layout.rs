@@ -0,0 +1,547 @@
+//! Storage orders for runtime-sized arrays with two or three dimensions, so that a kernel can be
+//! measured with its arrays laid out in different ways without changing how it indexes them.
+//!
+//! Every layout places element `(i, j)` at the offset `row(i) + col(j)`, which lets the rows of a
+//! [`LayoutArray2D`] be handed out like those of a [`DynArray2D`]. Unlike the latter, the arrays
+//! here reach every element through a row that is looked up first, a cost that all of their
+//! layouts share, so [`RowMajor`] is the baseline to compare the others against.
+//!
+//! [`DynArray2D`]: crate::ndarray::DynArray2D
+
+use crate::ndarray::{self, Aligned, AllocError, Array2, Array3, Contiguous, Extent};
+use crate::ndarray::{PositiveSemiDefinite, Scratch};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::alloc::Allocator;
+use core::marker::PhantomData;
+use core::ops::{self, Index, IndexMut};
+use core::ptr::NonNull;
+
+/// Where the elements of a `rows` × `cols` array are stored.
+pub trait Layout {
+    /// Returns the elements a `rows` × `cols` array takes, including any it leaves unused.
+    fn len(rows: usize, cols: usize) -> usize;
+
+    /// Returns the offset of element `(i, 0)`.
+    fn row(i: usize, rows: usize, cols: usize) -> usize;
+
+    /// Returns the offset of element `(i, j)` relative to that of element `(i, 0)`, which must
+    /// not depend on `i`.
+    fn col(j: usize, rows: usize, cols: usize) -> usize;
+}
+
+/// Rows one after the other, the layout of [`crate::ndarray::Array2D`].
+#[derive(Clone, Copy, Debug)]
+pub struct RowMajor;
+
+/// Columns one after the other, the layout of Fortran.
+#[derive(Clone, Copy, Debug)]
+pub struct ColumnMajor;
+
+/// Row-major tiles of `B` × `B` elements that are stored row-major themselves. Tiles at the
+/// bottom or right edge are stored whole even if the array covers only part of them.
+#[derive(Clone, Copy, Debug)]
+pub struct Tiled<const B: usize>;
+
+/// The Z-order curve, which interleaves the bits of the row and the column index so that every
+/// aligned square of a power-of-two size is contiguous.
+///
+/// Arrays whose dimensions are not powers of two leave parts of the enclosing square unused, up
+/// to three quarters of it for a very wide or tall array.
+#[derive(Clone, Copy, Debug)]
+pub struct Morton;
+
+impl Layout for RowMajor {
+    #[inline(always)]
+    fn len(rows: usize, cols: usize) -> usize {
+        rows * cols
+    }
+
+    #[inline(always)]
+    fn row(i: usize, _rows: usize, cols: usize) -> usize {
+        i * cols
+    }
+
+    #[inline(always)]
+    fn col(j: usize, _rows: usize, _cols: usize) -> usize {
+        j
+    }
+}
+
+impl Layout for ColumnMajor {
+    #[inline(always)]
+    fn len(rows: usize, cols: usize) -> usize {
+        rows * cols
+    }
+
+    #[inline(always)]
+    fn row(i: usize, _rows: usize, _cols: usize) -> usize {
+        i
+    }
+
+    #[inline(always)]
+    fn col(j: usize, rows: usize, _cols: usize) -> usize {
+        j * rows
+    }
+}
+
+impl<const B: usize> Layout for Tiled<B> {
+    #[inline(always)]
+    fn len(rows: usize, cols: usize) -> usize {
+        rows.next_multiple_of(B) * cols.next_multiple_of(B)
+    }
+
+    #[inline(always)]
+    fn row(i: usize, _rows: usize, cols: usize) -> usize {
+        i / B * B * cols.next_multiple_of(B) + i % B * B
+    }
+
+    #[inline(always)]
+    fn col(j: usize, _rows: usize, _cols: usize) -> usize {
+        j / B * B * B + j % B
+    }
+}
+
+/// Moves bit `k` of the lower half of `x` to bit `2k`.
+#[inline(always)]
+fn spread(x: usize) -> usize {
+    let mut x = x as u64 & 0xffff_ffff;
+    x = (x | x << 16) & 0x0000_ffff_0000_ffff;
+    x = (x | x << 8) & 0x00ff_00ff_00ff_00ff;
+    x = (x | x << 4) & 0x0f0f_0f0f_0f0f_0f0f;
+    x = (x | x << 2) & 0x3333_3333_3333_3333;
+    x = (x | x << 1) & 0x5555_5555_5555_5555;
+    x as usize
+}
+
+impl Layout for Morton {
+    #[inline(always)]
+    fn len(rows: usize, cols: usize) -> usize {
+        match rows == 0 || cols == 0 {
+            true => 0,
+            false => Self::row(rows - 1, rows, cols) + Self::col(cols - 1, rows, cols) + 1,
+        }
+    }
+
+    #[inline(always)]
+    fn row(i: usize, _rows: usize, _cols: usize) -> usize {
+        spread(i) << 1
+    }
+
+    #[inline(always)]
+    fn col(j: usize, _rows: usize, _cols: usize) -> usize {
+        spread(j)
+    }
+}
+
+/// A row of a [`LayoutArray2D`] or a [`LayoutArray3D`].
+pub struct LayoutRow<T, L> {
+    ptr: NonNull<T>,
+    rows: usize,
+    cols: usize,
+    layout: PhantomData<L>,
+}
+
+/// A runtime-sized counterpart of [`crate::ndarray::Array2D`] whose elements are stored in the
+/// layout `L`.
+///
+/// The arrays are allocated like [`ndarray::DynArray2D`], with the
+/// [`crate::pages::PageAllocator`] for [`crate::pages::pages`] and aligned to
+/// [`ndarray::alignment`], but without padding.
+pub struct LayoutArray2D<T, L> {
+    data: NonNull<[T]>,
+    alloc: Aligned,
+    rows: Box<[LayoutRow<T, L>]>,
+    cols: usize,
+}
+
+/// A plane of a [`LayoutArray3D`].
+pub struct LayoutPlane<T, L> {
+    rows: NonNull<LayoutRow<T, L>>,
+    len: usize,
+}
+
+/// A runtime-sized counterpart of [`crate::ndarray::Array3D`] whose planes are stored one after
+/// the other, each in the layout `L`.
+pub struct LayoutArray3D<T, L> {
+    data: NonNull<[T]>,
+    alloc: Aligned,
+    rows: NonNull<[LayoutRow<T, L>]>,
+    planes: Box<[LayoutPlane<T, L>]>,
+    cols: usize,
+}
+
+/// Returns the `m` rows of the `m` × `n` array in the layout `L` that starts at `base`.
+///
+/// # Safety
+///
+/// `base` must point to `L::len(m, n)` elements.
+unsafe fn rows<T, L: Layout>(
+    base: NonNull<T>,
+    m: usize,
+    n: usize,
+) -> impl Iterator<Item = LayoutRow<T, L>> {
+    (0..m).map(move |i| LayoutRow {
+        // SAFETY: every element of the row lies within the `L::len(m, n)` elements.
+        ptr: unsafe { base.add(L::row(i, m, n)) },
+        rows: m,
+        cols: n,
+        layout: PhantomData,
+    })
+}
+
+impl<T, L: Layout> LayoutArray2D<T, L> {
+    /// Returns an array of `m` rows and `n` columns for `init_array` to fill in, faulted in
+    /// according to [`crate::util::prefault`].
+    ///
+    /// # Safety
+    ///
+    /// Every element must be written before it is read. The elements are zero, but that is not
+    /// part of the contract.
+    pub unsafe fn uninit(m: usize, n: usize) -> Self {
+        Self::with_slice(ndarray::prefaulted_slice, m, n)
+    }
+
+    /// Returns an array of `m` rows and `n` columns whose bytes are all zero.
+    pub fn zeroed(m: usize, n: usize) -> Self {
+        Self::with_slice(ndarray::zeroed_slice, m, n)
+    }
+
+    fn with_slice(slice: fn(usize) -> Box<[T], Aligned>, m: usize, n: usize) -> Self {
+        let (data, alloc) = Box::into_raw_with_allocator(slice(L::len(m, n)));
+        // SAFETY: `Box` never holds a null pointer.
+        let data = unsafe { NonNull::new_unchecked(data) };
+        LayoutArray2D {
+            data,
+            alloc,
+            rows: unsafe { rows(data.cast(), m, n) }.collect(),
+            cols: n,
+        }
+    }
+
+    /// Returns the dimensions as `(rows, columns)`.
+    pub fn dims(&self) -> (usize, usize) {
+        (self.rows.len(), self.cols)
+    }
+}
+
+impl<T, L: Layout> LayoutArray3D<T, L> {
+    /// Returns an array of `m` planes, `n` rows and `p` columns for `init_array` to fill in,
+    /// faulted in according to [`crate::util::prefault`].
+    ///
+    /// # Safety
+    ///
+    /// Every element must be written before it is read. The elements are zero, but that is not
+    /// part of the contract.
+    pub unsafe fn uninit(m: usize, n: usize, p: usize) -> Self {
+        Self::with_slice(ndarray::prefaulted_slice, m, n, p)
+    }
+
+    /// Returns an array of `m` planes, `n` rows and `p` columns whose bytes are all zero.
+    pub fn zeroed(m: usize, n: usize, p: usize) -> Self {
+        Self::with_slice(ndarray::zeroed_slice, m, n, p)
+    }
+
+    fn with_slice(slice: fn(usize) -> Box<[T], Aligned>, m: usize, n: usize, p: usize) -> Self {
+        let len = L::len(n, p);
+        let (data, alloc) = Box::into_raw_with_allocator(slice(m * len));
+        // SAFETY: `Box` never holds a null pointer.
+        let data = unsafe { NonNull::new_unchecked(data) };
+        let base = data.cast::<T>();
+        let rows: Vec<_> = (0..m)
+            // SAFETY: plane `i` takes the `len` elements from `i * len` on.
+            .flat_map(|i| unsafe { rows::<T, L>(base.add(i * len), n, p) })
+            .collect();
+        // SAFETY: as for `data`.
+        let rows = unsafe { NonNull::new_unchecked(Box::into_raw(rows.into_boxed_slice())) };
+        let planes = (0..m)
+            .map(|i| LayoutPlane {
+                // SAFETY: the rows of plane `i` are the `n` from `i * n` on.
+                rows: unsafe { rows.cast().add(i * n) },
+                len: n,
+            })
+            .collect();
+        LayoutArray3D {
+            data,
+            alloc,
+            rows,
+            planes,
+            cols: p,
+        }
+    }
+
+    /// Returns the dimensions as `(planes, rows, columns)`.
+    pub fn dims(&self) -> (usize, usize, usize) {
+        let rows = self.planes.first().map_or(0, |p| p.len);
+        (self.planes.len(), rows, self.cols)
+    }
+}
+
+impl<T, L> Drop for LayoutArray2D<T, L> {
+    fn drop(&mut self) {
+        // SAFETY: `data` was taken from a `Box` with `alloc` in `with_slice`.
+        drop(unsafe { Box::from_raw_in(self.data.as_ptr(), self.alloc) });
+    }
+}
+
+impl<T, L> Drop for LayoutArray3D<T, L> {
+    fn drop(&mut self) {
+        // SAFETY: `data` and `rows` were taken from a `Box` in `with_slice`.
+        unsafe {
+            drop(Box::from_raw_in(self.data.as_ptr(), self.alloc));
+            drop(Box::from_raw(self.rows.as_ptr()));
+        }
+    }
+}
+
+impl<T, L: Layout> Index<usize> for LayoutRow<T, L> {
+    type Output = T;
+
+    #[inline(always)]
+    fn index(&self, index: usize) -> &Self::Output {
+        debug_assert!(index < self.cols);
+        // SAFETY: the element lies within the array, which is borrowed through the row.
+        unsafe { &*self.ptr.as_ptr().add(L::col(index, self.rows, self.cols)) }
+    }
+}
+
+impl<T, L: Layout> IndexMut<usize> for LayoutRow<T, L> {
+    #[inline(always)]
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        debug_assert!(index < self.cols);
+        // SAFETY: as above, and no two rows share an element so it is not aliased.
+        unsafe { &mut *self.ptr.as_ptr().add(L::col(index, self.rows, self.cols)) }
+    }
+}
+
+impl<T, L> Index<usize> for LayoutArray2D<T, L> {
+    type Output = LayoutRow<T, L>;
+
+    #[inline(always)]
+    fn index(&self, index: usize) -> &Self::Output {
+        debug_assert!(index < self.rows.len());
+        unsafe { self.rows.get_unchecked(index) }
+    }
+}
+
+impl<T, L> IndexMut<usize> for LayoutArray2D<T, L> {
+    #[inline(always)]
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        debug_assert!(index < self.rows.len());
+        unsafe { self.rows.get_unchecked_mut(index) }
+    }
+}
+
+impl<T, L> Index<usize> for LayoutPlane<T, L> {
+    type Output = LayoutRow<T, L>;
+
+    #[inline(always)]
+    fn index(&self, index: usize) -> &Self::Output {
+        debug_assert!(index < self.len);
+        // SAFETY: the rows of the plane are owned by the borrowed `LayoutArray3D`.
+        unsafe { &*self.rows.as_ptr().add(index) }
+    }
+}
+
+impl<T, L> IndexMut<usize> for LayoutPlane<T, L> {
+    #[inline(always)]
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        debug_assert!(index < self.len);
+        // SAFETY: as above, and planes never share rows so the row is not aliased.
+        unsafe { &mut *self.rows.as_ptr().add(index) }
+    }
+}
+
+impl<T, L> Index<usize> for LayoutArray3D<T, L> {
+    type Output = LayoutPlane<T, L>;
+
+    #[inline(always)]
+    fn index(&self, index: usize) -> &Self::Output {
+        debug_assert!(index < self.planes.len());
+        unsafe { self.planes.get_unchecked(index) }
+    }
+}
+
+impl<T, L> IndexMut<usize> for LayoutArray3D<T, L> {
+    #[inline(always)]
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        debug_assert!(index < self.planes.len());
+        unsafe { self.planes.get_unchecked_mut(index) }
+    }
+}
+
+impl<T, L: Layout> Array2<T> for LayoutArray2D<T, L> {
+    type Row = LayoutRow<T, L>;
+}
+
+impl<T, L: Layout> Array2<T> for LayoutPlane<T, L> {
+    type Row = LayoutRow<T, L>;
+}
+
+impl<T, L: Layout> Array3<T> for LayoutArray3D<T, L> {
+    type Plane = LayoutPlane<T, L>;
+}
+
+impl<T, L> Contiguous for LayoutArray2D<T, L> {
+    fn extent(&self) -> Extent {
+        Extent {
+            ptr: self.data.as_ptr() as *const u8,
+            len: self.data.len() * core::mem::size_of::<T>(),
+        }
+    }
+}
+
+impl<T, L> Contiguous for LayoutArray3D<T, L> {
+    fn extent(&self) -> Extent {
+        Extent {
+            ptr: self.data.as_ptr() as *const u8,
+            len: self.data.len() * core::mem::size_of::<T>(),
+        }
+    }
+}
+
+impl<T, L: Layout> PositiveSemiDefinite for LayoutArray2D<T, L>
+where
+    T: Copy + ops::Mul<Output = T> + ops::AddAssign<T>,
+{
+    fn try_make_positive_semi_definite_in<Alloc: Allocator>(
+        &mut self,
+        alloc: Alloc,
+    ) -> Result<(), AllocError> {
+        debug_assert_eq!(self.rows.len(), self.cols);
+        let mut b = Scratch::try_new_in(self.cols, alloc)?;
+        ndarray::make_positive_semi_definite(self.cols, self, &mut b);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that `L` stores every element of an array with the given dimensions at its own
+    /// offset below `L::len`.
+    fn is_bijective<L: Layout>(rows: usize, cols: usize) -> bool {
+        let len = L::len(rows, cols);
+        let mut seen = alloc::vec![false; len];
+        for i in 0..rows {
+            for j in 0..cols {
+                let offset = L::row(i, rows, cols) + L::col(j, rows, cols);
+                if offset >= len || seen[offset] {
+                    return false;
+                }
+                seen[offset] = true;
+            }
+        }
+        true
+    }
+
+    #[test]
+    fn layouts_are_bijective() {
+        for (rows, cols) in [(1, 1), (3, 4), (16, 16), (17, 5), (5, 33), (64, 100)] {
+            assert!(is_bijective::<RowMajor>(rows, cols));
+            assert!(is_bijective::<ColumnMajor>(rows, cols));
+            assert!(is_bijective::<Tiled<4>>(rows, cols));
+            assert!(is_bijective::<Tiled<16>>(rows, cols));
+            assert!(is_bijective::<Morton>(rows, cols));
+        }
+        assert_eq!(0, Morton::len(0, 5));
+        assert_eq!(64, Morton::len(8, 8));
+        assert_eq!(20 * 8, Tiled::<4>::len(17, 5));
+    }
+
+    /// Returns the offsets of the elements of `array` in row-major order.
+    fn offsets<L: Layout>(array: &LayoutArray2D<usize, L>) -> Vec<isize> {
+        let (rows, cols) = array.dims();
+        let base = array.data.as_ptr() as *const usize;
+        (0..rows)
+            .flat_map(|i| (0..cols).map(move |j| (i, j)))
+            .map(|(i, j)| unsafe { (&array[i][j] as *const usize).offset_from(base) })
+            .collect()
+    }
+
+    #[test]
+    fn layout_offsets() {
+        let column_major = LayoutArray2D::<usize, ColumnMajor>::zeroed(3, 4);
+        assert_eq!(
+            [0, 3, 6, 9, 1, 4, 7, 10, 2, 5, 8, 11],
+            offsets(&column_major)[..]
+        );
+        let tiled = LayoutArray2D::<usize, Tiled<2>>::zeroed(3, 4);
+        assert_eq!([0, 1, 4, 5, 2, 3, 6, 7, 8, 9, 12, 13], offsets(&tiled)[..]);
+        let morton = LayoutArray2D::<usize, Morton>::zeroed(2, 8);
+        assert_eq!(
+            [0, 1, 4, 5, 16, 17, 20, 21, 2, 3, 6, 7, 18, 19, 22, 23],
+            offsets(&morton)[..]
+        );
+    }
+
+    #[test]
+    fn planes_are_stored_one_after_the_other() {
+        let mut a = LayoutArray3D::<usize, ColumnMajor>::zeroed(2, 3, 4);
+        for k in 0..2 {
+            for i in 0..3 {
+                for j in 0..4 {
+                    a[k][i][j] = (k * 4 + j) * 3 + i;
+                }
+            }
+        }
+        assert!(unsafe { a.data.as_ref() }.iter().copied().eq(0..24));
+        assert_eq!((2, 3, 4), a.dims());
+        assert_eq!(23, a[1][2][3]);
+    }
+
+    /// Checks that every kernel with a `bench_layout` computes the same results in each layout as
+    /// `bench_dyn` does with the arrays of [`DynArray2D`].
+    #[test]
+    fn layouts_do_not_change_the_results() {
+        macro_rules! check {
+            ($($module:ident)::+) => {{
+                use crate::$($module)::+ as kernel;
+                use crate::dataset::results;
+
+                let dims = kernel::dims(kernel::Dataset::Mini);
+                let expected = results(kernel::bench_dyn, dims);
+                let layouts = [
+                    ("row-major", results(kernel::bench_layout::<RowMajor>, dims)),
+                    ("column-major", results(kernel::bench_layout::<ColumnMajor>, dims)),
+                    ("tiled-8", results(kernel::bench_layout::<Tiled<8>>, dims)),
+                    ("tiled-64", results(kernel::bench_layout::<Tiled<64>>, dims)),
+                    ("morton", results(kernel::bench_layout::<Morton>, dims)),
+                ];
+                for (layout, checksums) in layouts {
+                    assert_eq!(expected, checksums, "{} in {}", stringify!($($module)::+), layout);
+                }
+            }};
+        }
+
+        check!(datamining::correlation);
+        check!(datamining::covariance);
+        check!(linear_algebra::blas::gemm);
+        check!(linear_algebra::blas::gemver);
+        check!(linear_algebra::blas::gesummv);
+        check!(linear_algebra::blas::symm);
+        check!(linear_algebra::blas::syr2k);
+        check!(linear_algebra::blas::syrk);
+        check!(linear_algebra::blas::trmm);
+        check!(linear_algebra::kernels::_2mm);
+        check!(linear_algebra::kernels::_3mm);
+        check!(linear_algebra::kernels::atax);
+        check!(linear_algebra::kernels::bicg);
+        check!(linear_algebra::kernels::doitgen);
+        check!(linear_algebra::kernels::mvt);
+        check!(linear_algebra::solvers::cholesky);
+        check!(linear_algebra::solvers::gramschmidt);
+        check!(linear_algebra::solvers::lu);
+        check!(linear_algebra::solvers::ludcmp);
+        check!(linear_algebra::solvers::trisolv);
+        check!(medley::deriche);
+        check!(medley::floyd_warshall);
+        check!(medley::nussinov);
+        check!(stencils::adi);
+        check!(stencils::fdtd_2d);
+        check!(stencils::heat_3d);
+        check!(stencils::jacobi_2d);
+        check!(stencils::seidel_2d);
+    }
+}
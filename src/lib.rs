@@ -1,5 +1,4 @@
-#![cfg_attr(not(std), no_std)]
-
+#![cfg_attr(not(feature = "std"), no_std)]
 #![feature(rustc_attrs)]
 #![feature(new_uninit)]
 #![feature(core_intrinsics)]
@@ -13,12 +12,22 @@ pub mod medley;
 pub mod stencils;
 
 pub mod config;
+#[cfg(target_os = "linux")]
+pub mod counters;
+pub mod dataset;
+#[cfg(any(test, feature = "print-result"))]
+pub mod dump;
+pub mod layout;
 pub mod ndarray;
+pub mod pages;
+pub mod stats;
+pub mod timer;
 pub mod util;
+pub mod view;
 
-#[cfg(not(std))]
+#[cfg(not(feature = "std"))]
 mod cmath;
-#[cfg(not(std))]
+#[cfg(not(feature = "std"))]
 mod f32;
-#[cfg(not(std))]
+#[cfg(not(feature = "std"))]
 mod f64;
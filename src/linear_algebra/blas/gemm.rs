@@ -1,25 +1,30 @@
 #![allow(non_snake_case)]
 
-use crate::config::linear_algebra::blas::gemm::DataType;
-use crate::ndarray::{Array2D, ArrayAlloc};
+pub use crate::config::linear_algebra::blas::gemm::DataType;
+#[cfg(any(test, feature = "print-result"))]
+use crate::dump::Dump;
+use crate::layout::{Layout, LayoutArray2D};
+use crate::ndarray::{AllocError, Array2, Array2D, ArrayAlloc, Contiguous, DynArray2D};
+use crate::pages::PageAllocator;
+use crate::timer::Timer;
 use crate::util;
-use core::mem::MaybeUninit;
+use core::alloc::Allocator;
 use core::time::Duration;
 
-unsafe fn init_array<const NI: usize, const NJ: usize, const NK: usize>(
+#[allow(
+    clippy::too_many_arguments,
+    reason = "the parameters of PolyBench/C's init_array for gemm"
+)]
+unsafe fn init_array(
     ni: usize,
     nj: usize,
     nk: usize,
     alpha: &mut DataType,
     beta: &mut DataType,
-    C: &mut MaybeUninit<Array2D<DataType, NI, NJ>>,
-    A: &mut MaybeUninit<Array2D<DataType, NI, NK>>,
-    B: &mut MaybeUninit<Array2D<DataType, NK, NJ>>,
+    C: &mut impl Array2<DataType>,
+    A: &mut impl Array2<DataType>,
+    B: &mut impl Array2<DataType>,
 ) {
-    let A = unsafe { A.assume_init_mut() };
-    let B = unsafe { B.assume_init_mut() };
-    let C = unsafe { C.assume_init_mut() };
-
     *alpha = 1.5;
     *beta = 1.2;
     for i in 0..ni {
@@ -39,15 +44,33 @@ unsafe fn init_array<const NI: usize, const NJ: usize, const NK: usize>(
     }
 }
 
-unsafe fn kernel_gemm<const NI: usize, const NJ: usize, const NK: usize>(
+#[cfg(any(test, feature = "print-result"))]
+fn print_array(ni: usize, nj: usize, C: &impl Array2<DataType>, dump: &mut impl Dump) {
+    dump.begin("C");
+    for i in 0..ni {
+        for j in 0..nj {
+            if (i * ni + j).is_multiple_of(20) {
+                dump.newline();
+            }
+            dump.value(C[i][j]);
+        }
+    }
+    dump.end("C");
+}
+
+#[allow(
+    clippy::too_many_arguments,
+    reason = "the parameters of PolyBench/C's kernel_gemm"
+)]
+unsafe fn kernel_gemm(
     ni: usize,
     nj: usize,
     nk: usize,
     alpha: DataType,
     beta: DataType,
-    C: &mut Array2D<DataType, NI, NJ>,
-    A: &Array2D<DataType, NI, NK>,
-    B: &Array2D<DataType, NK, NJ>,
+    C: &mut impl Array2<DataType>,
+    A: &impl Array2<DataType>,
+    B: &impl Array2<DataType>,
 ) {
     for i in 0..ni {
         for j in 0..nj {
@@ -59,33 +82,157 @@ unsafe fn kernel_gemm<const NI: usize, const NJ: usize, const NK: usize>(
     }
 }
 
-pub fn bench<const NI: usize, const NJ: usize, const NK: usize>(
-    timing_function: &dyn Fn() -> u64,
+pub fn bench<const NI: usize, const NJ: usize, const NK: usize>(timer: &dyn Timer) -> Duration {
+    bench_in::<NI, NJ, NK, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench`], but allocates the arrays with `alloc` instead of a [`PageAllocator`].
+pub fn bench_in<const NI: usize, const NJ: usize, const NK: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
 ) -> Duration {
+    try_bench_in::<NI, NJ, NK, _>(timer, alloc).unwrap_or_else(|e| e.handle())
+}
+
+/// Like [`bench`], but returns an error instead of aborting if the arrays cannot be allocated.
+pub fn try_bench<const NI: usize, const NJ: usize, const NK: usize>(
+    timer: &dyn Timer,
+) -> Result<Duration, AllocError> {
+    try_bench_in::<NI, NJ, NK, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench_in`], but returns an error instead of aborting if the arrays cannot be
+/// allocated.
+pub fn try_bench_in<const NI: usize, const NJ: usize, const NK: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Result<Duration, AllocError> {
     let ni = NI;
     let nj = NJ;
     let nk = NK;
 
     let mut alpha = 0.0;
     let mut beta = 0.0;
-    let mut C = Array2D::<DataType, NI, NJ>::maybe_uninit();
-    let mut A = Array2D::<DataType, NI, NK>::maybe_uninit();
-    let mut B = Array2D::<DataType, NK, NJ>::maybe_uninit();
+    let mut C = Array2D::<DataType, NI, NJ>::try_maybe_uninit_in(&alloc)?;
+    let mut A = Array2D::<DataType, NI, NK>::try_maybe_uninit_in(&alloc)?;
+    let mut B = Array2D::<DataType, NK, NJ>::try_maybe_uninit_in(&alloc)?;
 
     unsafe {
+        let C = C.assume_init_mut();
+        let A = A.assume_init_mut();
+        let B = B.assume_init_mut();
+        init_array(ni, nj, nk, &mut alpha, &mut beta, C, A, B);
+
+        let elapsed = util::benchmark_with_timer(
+            &[C.extent(), A.extent(), B.extent()],
+            || kernel_gemm(ni, nj, nk, alpha, beta, C, A, B),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(ni, nj, C, &mut crate::dump::Results::start());
+        util::consume(C);
+        Ok(elapsed)
+    }
+}
+
+/// Runs the same benchmark as [`bench`] with dimensions that are only known at runtime, given in
+/// the order of its const generics.
+pub fn bench_dyn(dims: [usize; 3], timer: &dyn Timer) -> Duration {
+    let [ni, nj, nk] = dims;
+
+    let mut alpha = 0.0;
+    let mut beta = 0.0;
+
+    unsafe {
+        let mut C = DynArray2D::<DataType>::uninit(ni, nj);
+        let mut A = DynArray2D::<DataType>::uninit(ni, nk);
+        let mut B = DynArray2D::<DataType>::uninit(nk, nj);
         init_array(ni, nj, nk, &mut alpha, &mut beta, &mut C, &mut A, &mut B);
-        let A = A.assume_init();
-        let B = B.assume_init();
-        let mut C = C.assume_init();
 
-        let elapsed = util::benchmark_with_timing_function(
+        let elapsed = util::benchmark_with_timer(
+            &[C.extent(), A.extent(), B.extent()],
             || kernel_gemm(ni, nj, nk, alpha, beta, &mut C, &A, &B),
-            timing_function,
+            timer,
         );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(ni, nj, &C, &mut crate::dump::Results::start());
         util::consume(C);
         elapsed
     }
 }
 
+/// Like [`bench_dyn`], but stores the arrays with two or three dimensions in the layout `L`.
+pub fn bench_layout<L: Layout>(dims: [usize; 3], timer: &dyn Timer) -> Duration {
+    let [ni, nj, nk] = dims;
+
+    let mut alpha = 0.0;
+    let mut beta = 0.0;
+
+    unsafe {
+        let mut C = LayoutArray2D::<DataType, L>::uninit(ni, nj);
+        let mut A = LayoutArray2D::<DataType, L>::uninit(ni, nk);
+        let mut B = LayoutArray2D::<DataType, L>::uninit(nk, nj);
+        init_array(ni, nj, nk, &mut alpha, &mut beta, &mut C, &mut A, &mut B);
+
+        let elapsed = util::benchmark_with_timer(
+            &[C.extent(), A.extent(), B.extent()],
+            || kernel_gemm(ni, nj, nk, alpha, beta, &mut C, &A, &B),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(ni, nj, &C, &mut crate::dump::Results::start());
+        util::consume(C);
+        elapsed
+    }
+}
+
+/// Floating-point operations of one run of [`bench`] with `dims`: 2·NI·NJ·NK.
+pub fn flops(dims: [usize; 3]) -> u64 {
+    let [ni, nj, nk] = dims.map(|d| d as u64);
+    2 * ni * nj * nk
+}
+
+/// Bytes of the arrays [`bench_dyn`] allocates with `dims`: NI·NJ + NI·NK + NK·NJ elements.
+pub fn footprint(dims: [usize; 3]) -> u64 {
+    let [ni, nj, nk] = dims;
+    crate::ndarray::footprint::<DataType>(&[&[ni, nj], &[ni, nk], &[nk, nj]])
+}
+
+crate::dataset::datasets! {
+    Mini: [20, 25, 30],
+    Small: [60, 70, 80],
+    Medium: [200, 220, 240],
+    Large: [1000, 1100, 1200],
+    ExtraLarge: [2000, 2300, 2600],
+}
+
+#[cfg(test)]
+crate::dataset::checksums! {
+    Mini: [("C", 4364.999999999998, 1127310.7999999996)],
+    Small: [("C", 109987.875, 233817295.20000014)],
+}
+
+/// Runs the kernel on fixed-size arrays whose rows are padded, like `bench` with an `LD` above
+/// the number of columns.
 #[test]
-fn check() {}
+fn check_padded() {
+    use crate::dump::Checksums;
+    use crate::ndarray::Align64;
+
+    let (ni, nj, nk) = (20, 25, 30);
+
+    let mut alpha = 0.0;
+    let mut beta = 0.0;
+    let mut C = Array2D::<DataType, 20, 25, Align64, 27>::maybe_uninit_zeroed();
+    let mut A = Array2D::<DataType, 20, 30, Align64, 33>::maybe_uninit_zeroed();
+    let mut B = Array2D::<DataType, 30, 25, Align64, 32>::maybe_uninit_zeroed();
+
+    unsafe {
+        init_array(ni, nj, nk, &mut alpha, &mut beta, &mut *C, &mut *A, &mut *B);
+        kernel_gemm(ni, nj, nk, alpha, beta, &mut *C, &*A, &*B);
+    }
+    let mut checksums = Checksums::default();
+    print_array(ni, nj, &*C, &mut checksums);
+    checksums.assert_eq(&[("C", 4364.999999999998, 1127310.7999999996)]);
+    assert!(C.0.iter().all(|row| row.0[25..] == [0.0; 2]));
+}
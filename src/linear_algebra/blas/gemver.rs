@@ -1,35 +1,36 @@
 #![allow(non_snake_case)]
 
-use crate::config::linear_algebra::blas::gemver::DataType;
-use crate::ndarray::{Array1D, Array2D, ArrayAlloc};
+pub use crate::config::linear_algebra::blas::gemver::DataType;
+#[cfg(any(test, feature = "print-result"))]
+use crate::dump::Dump;
+use crate::layout::{Layout, LayoutArray2D};
+use crate::ndarray::{
+    AllocError, Array1, Array1D, Array2, Array2D, ArrayAlloc, Contiguous, DynArray1D, DynArray2D,
+};
+use crate::pages::PageAllocator;
+use crate::timer::Timer;
 use crate::util;
-use core::mem::MaybeUninit;
+use core::alloc::Allocator;
 use core::time::Duration;
 
-unsafe fn init_array<const N: usize>(
+#[allow(
+    clippy::too_many_arguments,
+    reason = "the parameters of PolyBench/C's init_array for gemver"
+)]
+unsafe fn init_array(
     n: usize,
     alpha: &mut DataType,
     beta: &mut DataType,
-    A: &mut MaybeUninit<Array2D<DataType, N, N>>,
-    u1: &mut MaybeUninit<Array1D<DataType, N>>,
-    v1: &mut MaybeUninit<Array1D<DataType, N>>,
-    u2: &mut MaybeUninit<Array1D<DataType, N>>,
-    v2: &mut MaybeUninit<Array1D<DataType, N>>,
-    w: &mut MaybeUninit<Array1D<DataType, N>>,
-    x: &mut MaybeUninit<Array1D<DataType, N>>,
-    y: &mut MaybeUninit<Array1D<DataType, N>>,
-    z: &mut MaybeUninit<Array1D<DataType, N>>,
+    A: &mut impl Array2<DataType>,
+    u1: &mut impl Array1<DataType>,
+    v1: &mut impl Array1<DataType>,
+    u2: &mut impl Array1<DataType>,
+    v2: &mut impl Array1<DataType>,
+    w: &mut impl Array1<DataType>,
+    x: &mut impl Array1<DataType>,
+    y: &mut impl Array1<DataType>,
+    z: &mut impl Array1<DataType>,
 ) {
-    let A = unsafe { A.assume_init_mut() };
-    let u1 = unsafe { u1.assume_init_mut() };
-    let v1 = unsafe { v1.assume_init_mut() };
-    let u2 = unsafe { u2.assume_init_mut() };
-    let v2 = unsafe { v2.assume_init_mut() };
-    let w = unsafe { w.assume_init_mut() };
-    let x = unsafe { x.assume_init_mut() };
-    let y = unsafe { y.assume_init_mut() };
-    let z = unsafe { z.assume_init_mut() };
-
     *alpha = 1.5;
     *beta = 1.2;
 
@@ -50,19 +51,35 @@ unsafe fn init_array<const N: usize>(
     }
 }
 
-unsafe fn kernel_gemver<const N: usize>(
+#[cfg(any(test, feature = "print-result"))]
+fn print_array(n: usize, w: &impl Array1<DataType>, dump: &mut impl Dump) {
+    dump.begin("w");
+    for i in 0..n {
+        if i.is_multiple_of(20) {
+            dump.newline();
+        }
+        dump.value(w[i]);
+    }
+    dump.end("w");
+}
+
+#[allow(
+    clippy::too_many_arguments,
+    reason = "the parameters of PolyBench/C's kernel_gemver"
+)]
+unsafe fn kernel_gemver(
     n: usize,
     alpha: DataType,
     beta: DataType,
-    A: &mut Array2D<DataType, N, N>,
-    u1: &Array1D<DataType, N>,
-    v1: &Array1D<DataType, N>,
-    u2: &Array1D<DataType, N>,
-    v2: &Array1D<DataType, N>,
-    w: &mut Array1D<DataType, N>,
-    x: &mut Array1D<DataType, N>,
-    y: &Array1D<DataType, N>,
-    z: &Array1D<DataType, N>,
+    A: &mut impl Array2<DataType>,
+    u1: &impl Array1<DataType>,
+    v1: &impl Array1<DataType>,
+    u2: &impl Array1<DataType>,
+    v2: &impl Array1<DataType>,
+    w: &mut impl Array1<DataType>,
+    x: &mut impl Array1<DataType>,
+    y: &impl Array1<DataType>,
+    z: &impl Array1<DataType>,
 ) {
     for i in 0..n {
         for j in 0..n {
@@ -72,64 +89,218 @@ unsafe fn kernel_gemver<const N: usize>(
 
     for i in 0..n {
         for j in 0..n {
-            x[i] = x[i] + beta * A[j][i] * y[j];
+            x[i] += beta * A[j][i] * y[j];
         }
     }
 
     for i in 0..n {
-        x[i] = x[i] + z[i];
+        x[i] += z[i];
     }
 
     for i in 0..n {
         for j in 0..n {
-            w[i] = w[i] + alpha * A[i][j] * x[j];
+            w[i] += alpha * A[i][j] * x[j];
         }
     }
 }
 
-pub fn bench<const N: usize>(timing_function: &dyn Fn() -> u64) -> Duration {
+pub fn bench<const N: usize>(timer: &dyn Timer) -> Duration {
+    bench_in::<N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench`], but allocates the arrays with `alloc` instead of a [`PageAllocator`].
+pub fn bench_in<const N: usize, Alloc: Allocator>(timer: &dyn Timer, alloc: Alloc) -> Duration {
+    try_bench_in::<N, _>(timer, alloc).unwrap_or_else(|e| e.handle())
+}
+
+/// Like [`bench`], but returns an error instead of aborting if the arrays cannot be allocated.
+pub fn try_bench<const N: usize>(timer: &dyn Timer) -> Result<Duration, AllocError> {
+    try_bench_in::<N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench_in`], but returns an error instead of aborting if the arrays cannot be
+/// allocated.
+pub fn try_bench_in<const N: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Result<Duration, AllocError> {
     let n = N;
 
     let mut alpha = 0.0;
     let mut beta = 0.0;
-    let mut A = Array2D::<DataType, N, N>::maybe_uninit();
-    let mut u1 = Array1D::<DataType, N>::maybe_uninit();
-    let mut v1 = Array1D::<DataType, N>::maybe_uninit();
-    let mut u2 = Array1D::<DataType, N>::maybe_uninit();
-    let mut v2 = Array1D::<DataType, N>::maybe_uninit();
-    let mut w = Array1D::<DataType, N>::maybe_uninit();
-    let mut x = Array1D::<DataType, N>::maybe_uninit();
-    let mut y = Array1D::<DataType, N>::maybe_uninit();
-    let mut z = Array1D::<DataType, N>::maybe_uninit();
+    let mut A = Array2D::<DataType, N, N>::try_maybe_uninit_in(&alloc)?;
+    let mut u1 = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
+    let mut v1 = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
+    let mut u2 = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
+    let mut v2 = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
+    let mut w = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
+    let mut x = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
+    let mut y = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
+    let mut z = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
+
+    unsafe {
+        let A = A.assume_init_mut();
+        let u1 = u1.assume_init_mut();
+        let v1 = v1.assume_init_mut();
+        let u2 = u2.assume_init_mut();
+        let v2 = v2.assume_init_mut();
+        let w = w.assume_init_mut();
+        let x = x.assume_init_mut();
+        let y = y.assume_init_mut();
+        let z = z.assume_init_mut();
+        init_array(n, &mut alpha, &mut beta, A, u1, v1, u2, v2, w, x, y, z);
+
+        let elapsed = util::benchmark_with_timer(
+            &[
+                A.extent(),
+                u1.extent(),
+                v1.extent(),
+                u2.extent(),
+                v2.extent(),
+                w.extent(),
+                x.extent(),
+                y.extent(),
+                z.extent(),
+            ],
+            || kernel_gemver(n, alpha, beta, A, u1, v1, u2, v2, w, x, y, z),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, w, &mut crate::dump::Results::start());
+        util::consume(w);
+        Ok(elapsed)
+    }
+}
+
+/// Runs the same benchmark as [`bench`] with dimensions that are only known at runtime, given in
+/// the order of its const generics.
+pub fn bench_dyn(dims: [usize; 1], timer: &dyn Timer) -> Duration {
+    let [n] = dims;
+
+    let mut alpha = 0.0;
+    let mut beta = 0.0;
 
     unsafe {
+        let mut A = DynArray2D::<DataType>::uninit(n, n);
+        let mut u1 = DynArray1D::<DataType>::uninit(n);
+        let mut v1 = DynArray1D::<DataType>::uninit(n);
+        let mut u2 = DynArray1D::<DataType>::uninit(n);
+        let mut v2 = DynArray1D::<DataType>::uninit(n);
+        let mut w = DynArray1D::<DataType>::uninit(n);
+        let mut x = DynArray1D::<DataType>::uninit(n);
+        let mut y = DynArray1D::<DataType>::uninit(n);
+        let mut z = DynArray1D::<DataType>::uninit(n);
         init_array(
             n, &mut alpha, &mut beta, &mut A, &mut u1, &mut v1, &mut u2, &mut v2, &mut w, &mut x,
             &mut y, &mut z,
         );
 
-        let mut A = A.assume_init();
-        let u1 = u1.assume_init();
-        let v1 = v1.assume_init();
-        let u2 = u2.assume_init();
-        let v2 = v2.assume_init();
-        let mut w = w.assume_init();
-        let mut x = x.assume_init();
-        let y = y.assume_init();
-        let z = z.assume_init();
-
-        let elapsed = util::benchmark_with_timing_function(
+        let elapsed = util::benchmark_with_timer(
+            &[
+                A.extent(),
+                u1.extent(),
+                v1.extent(),
+                u2.extent(),
+                v2.extent(),
+                w.extent(),
+                x.extent(),
+                y.extent(),
+                z.extent(),
+            ],
             || {
                 kernel_gemver(
                     n, alpha, beta, &mut A, &u1, &v1, &u2, &v2, &mut w, &mut x, &y, &z,
                 )
             },
-            timing_function,
+            timer,
         );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, &w, &mut crate::dump::Results::start());
         util::consume(w);
         elapsed
     }
 }
 
-#[test]
-fn check() {}
+/// Like [`bench_dyn`], but stores the arrays with two or three dimensions in the layout `L`.
+pub fn bench_layout<L: Layout>(dims: [usize; 1], timer: &dyn Timer) -> Duration {
+    let [n] = dims;
+
+    let mut alpha = 0.0;
+    let mut beta = 0.0;
+
+    unsafe {
+        let mut A = LayoutArray2D::<DataType, L>::uninit(n, n);
+        let mut u1 = DynArray1D::<DataType>::uninit(n);
+        let mut v1 = DynArray1D::<DataType>::uninit(n);
+        let mut u2 = DynArray1D::<DataType>::uninit(n);
+        let mut v2 = DynArray1D::<DataType>::uninit(n);
+        let mut w = DynArray1D::<DataType>::uninit(n);
+        let mut x = DynArray1D::<DataType>::uninit(n);
+        let mut y = DynArray1D::<DataType>::uninit(n);
+        let mut z = DynArray1D::<DataType>::uninit(n);
+        init_array(
+            n, &mut alpha, &mut beta, &mut A, &mut u1, &mut v1, &mut u2, &mut v2, &mut w, &mut x,
+            &mut y, &mut z,
+        );
+
+        let elapsed = util::benchmark_with_timer(
+            &[
+                A.extent(),
+                u1.extent(),
+                v1.extent(),
+                u2.extent(),
+                v2.extent(),
+                w.extent(),
+                x.extent(),
+                y.extent(),
+                z.extent(),
+            ],
+            || {
+                kernel_gemver(
+                    n, alpha, beta, &mut A, &u1, &v1, &u2, &v2, &mut w, &mut x, &y, &z,
+                )
+            },
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, &w, &mut crate::dump::Results::start());
+        util::consume(w);
+        elapsed
+    }
+}
+
+/// Floating-point operations of one run of [`bench`] with `dims`: 8·N² + N.
+pub fn flops(dims: [usize; 1]) -> u64 {
+    let [n] = dims.map(|d| d as u64);
+    8 * n * n + n
+}
+
+/// Bytes of the arrays [`bench_dyn`] allocates with `dims`: N² + 8·N elements.
+pub fn footprint(dims: [usize; 1]) -> u64 {
+    let [n] = dims;
+    crate::ndarray::footprint::<DataType>(&[
+        &[n, n],
+        &[n],
+        &[n],
+        &[n],
+        &[n],
+        &[n],
+        &[n],
+        &[n],
+        &[n],
+    ])
+}
+
+crate::dataset::datasets! {
+    Mini: [40],
+    Small: [120],
+    Medium: [400],
+    Large: [2000],
+    ExtraLarge: [4000],
+}
+
+#[cfg(test)]
+crate::dataset::checksums! {
+    Mini: [("w", 104024.79100109865, 2754037.7892526453)],
+    Small: [("w", 21304686.588775635, 1698451273.1137538)],
+}
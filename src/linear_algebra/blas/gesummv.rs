@@ -1,23 +1,26 @@
 #![allow(non_snake_case)]
 
-use crate::config::linear_algebra::blas::gesummv::DataType;
-use crate::ndarray::{Array1D, Array2D, ArrayAlloc};
+pub use crate::config::linear_algebra::blas::gesummv::DataType;
+#[cfg(any(test, feature = "print-result"))]
+use crate::dump::Dump;
+use crate::layout::{Layout, LayoutArray2D};
+use crate::ndarray::{
+    AllocError, Array1, Array1D, Array2, Array2D, ArrayAlloc, Contiguous, DynArray1D, DynArray2D,
+};
+use crate::pages::PageAllocator;
+use crate::timer::Timer;
 use crate::util;
-use core::mem::MaybeUninit;
+use core::alloc::Allocator;
 use core::time::Duration;
 
-unsafe fn init_array<const N: usize>(
+unsafe fn init_array(
     n: usize,
     alpha: &mut DataType,
     beta: &mut DataType,
-    A: &mut MaybeUninit<Array2D<DataType, N, N>>,
-    B: &mut MaybeUninit<Array2D<DataType, N, N>>,
-    x: &mut MaybeUninit<Array1D<DataType, N>>,
+    A: &mut impl Array2<DataType>,
+    B: &mut impl Array2<DataType>,
+    x: &mut impl Array1<DataType>,
 ) {
-    let A = unsafe { A.assume_init_mut() };
-    let B = unsafe { B.assume_init_mut() };
-    let x = unsafe { x.assume_init_mut() };
-
     *alpha = 1.5;
     *beta = 1.2;
     for i in 0..n {
@@ -29,54 +32,170 @@ unsafe fn init_array<const N: usize>(
     }
 }
 
-unsafe fn kernel_gesummv<const N: usize>(
+#[cfg(any(test, feature = "print-result"))]
+fn print_array(n: usize, y: &impl Array1<DataType>, dump: &mut impl Dump) {
+    dump.begin("y");
+    for i in 0..n {
+        if i.is_multiple_of(20) {
+            dump.newline();
+        }
+        dump.value(y[i]);
+    }
+    dump.end("y");
+}
+
+#[allow(
+    clippy::too_many_arguments,
+    reason = "the parameters of PolyBench/C's kernel_gesummv"
+)]
+unsafe fn kernel_gesummv(
     n: usize,
     alpha: DataType,
     beta: DataType,
-    A: &Array2D<DataType, N, N>,
-    B: &Array2D<DataType, N, N>,
-    tmp: &mut Array1D<DataType, N>,
-    x: &Array1D<DataType, N>,
-    y: &mut Array1D<DataType, N>,
+    A: &impl Array2<DataType>,
+    B: &impl Array2<DataType>,
+    tmp: &mut impl Array1<DataType>,
+    x: &impl Array1<DataType>,
+    y: &mut impl Array1<DataType>,
 ) {
     for i in 0..n {
         tmp[i] = 0.0;
         y[i] = 0.0;
         for j in 0..n {
-            tmp[i] = A[i][j] * x[j] + tmp[i];
-            y[i] = B[i][j] * x[j] + y[i];
+            tmp[i] += A[i][j] * x[j];
+            y[i] += B[i][j] * x[j];
         }
         y[i] = alpha * tmp[i] + beta * y[i];
     }
 }
 
-pub fn bench<const N: usize>(timing_function: &dyn Fn() -> u64) -> Duration {
+pub fn bench<const N: usize>(timer: &dyn Timer) -> Duration {
+    bench_in::<N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench`], but allocates the arrays with `alloc` instead of a [`PageAllocator`].
+pub fn bench_in<const N: usize, Alloc: Allocator>(timer: &dyn Timer, alloc: Alloc) -> Duration {
+    try_bench_in::<N, _>(timer, alloc).unwrap_or_else(|e| e.handle())
+}
+
+/// Like [`bench`], but returns an error instead of aborting if the arrays cannot be allocated.
+pub fn try_bench<const N: usize>(timer: &dyn Timer) -> Result<Duration, AllocError> {
+    try_bench_in::<N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench_in`], but returns an error instead of aborting if the arrays cannot be
+/// allocated.
+pub fn try_bench_in<const N: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Result<Duration, AllocError> {
     let n = N;
 
     let mut alpha = 0.0;
     let mut beta = 0.0;
-    let mut A = Array2D::<DataType, N, N>::maybe_uninit();
-    let mut B = Array2D::<DataType, N, N>::maybe_uninit();
-    let tmp = Array1D::<DataType, N>::maybe_uninit();
-    let mut x = Array1D::<DataType, N>::maybe_uninit();
-    let y = Array1D::<DataType, N>::maybe_uninit();
+    let mut A = Array2D::<DataType, N, N>::try_maybe_uninit_in(&alloc)?;
+    let mut B = Array2D::<DataType, N, N>::try_maybe_uninit_in(&alloc)?;
+    let mut tmp = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
+    let mut x = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
+    let mut y = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
+
+    unsafe {
+        let A = A.assume_init_mut();
+        let B = B.assume_init_mut();
+        let tmp = tmp.assume_init_mut();
+        let x = x.assume_init_mut();
+        let y = y.assume_init_mut();
+        init_array(n, &mut alpha, &mut beta, A, B, x);
+
+        let elapsed = util::benchmark_with_timer(
+            &[A.extent(), B.extent(), tmp.extent(), x.extent(), y.extent()],
+            || kernel_gesummv(n, alpha, beta, A, B, tmp, x, y),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, y, &mut crate::dump::Results::start());
+        util::consume(y);
+        Ok(elapsed)
+    }
+}
+
+/// Runs the same benchmark as [`bench`] with dimensions that are only known at runtime, given in
+/// the order of its const generics.
+pub fn bench_dyn(dims: [usize; 1], timer: &dyn Timer) -> Duration {
+    let [n] = dims;
+
+    let mut alpha = 0.0;
+    let mut beta = 0.0;
+
+    unsafe {
+        let mut A = DynArray2D::<DataType>::uninit(n, n);
+        let mut B = DynArray2D::<DataType>::uninit(n, n);
+        let mut tmp = DynArray1D::<DataType>::uninit(n);
+        let mut x = DynArray1D::<DataType>::uninit(n);
+        let mut y = DynArray1D::<DataType>::uninit(n);
+        init_array(n, &mut alpha, &mut beta, &mut A, &mut B, &mut x);
+
+        let elapsed = util::benchmark_with_timer(
+            &[A.extent(), B.extent(), tmp.extent(), x.extent(), y.extent()],
+            || kernel_gesummv(n, alpha, beta, &A, &B, &mut tmp, &x, &mut y),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, &y, &mut crate::dump::Results::start());
+        util::consume(y);
+        elapsed
+    }
+}
+
+/// Like [`bench_dyn`], but stores the arrays with two or three dimensions in the layout `L`.
+pub fn bench_layout<L: Layout>(dims: [usize; 1], timer: &dyn Timer) -> Duration {
+    let [n] = dims;
+
+    let mut alpha = 0.0;
+    let mut beta = 0.0;
 
     unsafe {
+        let mut A = LayoutArray2D::<DataType, L>::uninit(n, n);
+        let mut B = LayoutArray2D::<DataType, L>::uninit(n, n);
+        let mut tmp = DynArray1D::<DataType>::uninit(n);
+        let mut x = DynArray1D::<DataType>::uninit(n);
+        let mut y = DynArray1D::<DataType>::uninit(n);
         init_array(n, &mut alpha, &mut beta, &mut A, &mut B, &mut x);
-        let A = A.assume_init();
-        let B = B.assume_init();
-        let x = x.assume_init();
-        let mut tmp = tmp.assume_init();
-        let mut y = y.assume_init();
 
-        let elapsed = util::benchmark_with_timing_function(
+        let elapsed = util::benchmark_with_timer(
+            &[A.extent(), B.extent(), tmp.extent(), x.extent(), y.extent()],
             || kernel_gesummv(n, alpha, beta, &A, &B, &mut tmp, &x, &mut y),
-            timing_function,
+            timer,
         );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, &y, &mut crate::dump::Results::start());
         util::consume(y);
         elapsed
     }
 }
 
-#[test]
-fn check() {}
+/// Floating-point operations of one run of [`bench`] with `dims`: 4·N² + N.
+pub fn flops(dims: [usize; 1]) -> u64 {
+    let [n] = dims.map(|d| d as u64);
+    4 * n * n + n
+}
+
+/// Bytes of the arrays [`bench_dyn`] allocates with `dims`: 2·N² + 3·N elements.
+pub fn footprint(dims: [usize; 1]) -> u64 {
+    let [n] = dims;
+    crate::ndarray::footprint::<DataType>(&[&[n, n], &[n, n], &[n], &[n], &[n]])
+}
+
+crate::dataset::datasets! {
+    Mini: [30],
+    Small: [90],
+    Medium: [250],
+    Large: [1300],
+    ExtraLarge: [2800],
+}
+
+#[cfg(test)]
+crate::dataset::checksums! {
+    Mini: [("y", 547.725, 8458.665)],
+    Small: [("y", 5182.725000000004, 233586.915)],
+}
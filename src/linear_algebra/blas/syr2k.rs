@@ -1,54 +1,69 @@
 #![allow(non_snake_case)]
 
-use crate::config::linear_algebra::blas::syr2k::DataType;
-use crate::ndarray::{Array2D, ArrayAlloc};
+pub use crate::config::linear_algebra::blas::syr2k::DataType;
+#[cfg(any(test, feature = "print-result"))]
+use crate::dump::Dump;
+use crate::layout::{Layout, LayoutArray2D};
+use crate::ndarray::{AllocError, Array2, Array2D, ArrayAlloc, Contiguous, DynArray2D};
+use crate::pages::PageAllocator;
+use crate::timer::Timer;
 use crate::util;
-use core::mem::MaybeUninit;
+use core::alloc::Allocator;
 use core::time::Duration;
 
-unsafe fn init_array<const M: usize, const N: usize>(
+unsafe fn init_array(
     m: usize,
     n: usize,
     alpha: &mut DataType,
     beta: &mut DataType,
-    C: &mut MaybeUninit<Array2D<DataType, M, M>>,
-    A: &mut MaybeUninit<Array2D<DataType, M, N>>,
-    B: &mut MaybeUninit<Array2D<DataType, M, N>>,
+    C: &mut impl Array2<DataType>,
+    A: &mut impl Array2<DataType>,
+    B: &mut impl Array2<DataType>,
 ) {
-    let A = unsafe { A.assume_init_mut() };
-    let B = unsafe { B.assume_init_mut() };
-    let C = unsafe { C.assume_init_mut() };
-
     *alpha = 1.5;
     *beta = 1.2;
-    for i in 0..m {
+    for i in 0..n {
+        for j in 0..m {
+            A[i][j] = ((i * j + 1) % n) as DataType / n as DataType;
+            B[i][j] = ((i * j + 2) % m) as DataType / m as DataType;
+        }
+    }
+
+    for i in 0..n {
         for j in 0..n {
-            A[i][j] = ((i * j + 1) % m) as DataType / m as DataType;
-            B[i][j] = ((i * j + 2) % n) as DataType / n as DataType;
+            C[i][j] = ((i * j + 3) % n) as DataType / m as DataType;
         }
     }
+}
 
-    for i in 0..m {
-        for j in 0..m {
-            C[i][j] = ((i * j + 3) % m) as DataType / n as DataType;
+#[cfg(any(test, feature = "print-result"))]
+fn print_array(n: usize, C: &impl Array2<DataType>, dump: &mut impl Dump) {
+    dump.begin("C");
+    for i in 0..n {
+        for j in 0..n {
+            if (i * n + j).is_multiple_of(20) {
+                dump.newline();
+            }
+            dump.value(C[i][j]);
         }
     }
+    dump.end("C");
 }
 
-unsafe fn kernel_syr2k<const M: usize, const N: usize>(
+unsafe fn kernel_syr2k(
     m: usize,
     n: usize,
     alpha: DataType,
     beta: DataType,
-    C: &mut Array2D<DataType, M, M>,
-    A: &Array2D<DataType, M, N>,
-    B: &Array2D<DataType, M, N>,
+    C: &mut impl Array2<DataType>,
+    A: &impl Array2<DataType>,
+    B: &impl Array2<DataType>,
 ) {
-    for i in 0..m {
+    for i in 0..n {
         for j in 0..=i {
             C[i][j] *= beta;
         }
-        for k in 0..n {
+        for k in 0..m {
             for j in 0..=i {
                 C[i][j] += A[j][k] * alpha * B[i][k] + B[j][k] * alpha * A[i][k];
             }
@@ -56,30 +71,132 @@ unsafe fn kernel_syr2k<const M: usize, const N: usize>(
     }
 }
 
-pub fn bench<const M: usize, const N: usize>(timing_function: &dyn Fn() -> u64) -> Duration {
+pub fn bench<const M: usize, const N: usize>(timer: &dyn Timer) -> Duration {
+    bench_in::<M, N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench`], but allocates the arrays with `alloc` instead of a [`PageAllocator`].
+pub fn bench_in<const M: usize, const N: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Duration {
+    try_bench_in::<M, N, _>(timer, alloc).unwrap_or_else(|e| e.handle())
+}
+
+/// Like [`bench`], but returns an error instead of aborting if the arrays cannot be allocated.
+pub fn try_bench<const M: usize, const N: usize>(
+    timer: &dyn Timer,
+) -> Result<Duration, AllocError> {
+    try_bench_in::<M, N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench_in`], but returns an error instead of aborting if the arrays cannot be
+/// allocated.
+pub fn try_bench_in<const M: usize, const N: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Result<Duration, AllocError> {
     let m = M;
     let n = N;
 
     let mut alpha = 0.0;
     let mut beta = 0.0;
-    let mut C = Array2D::<DataType, M, M>::maybe_uninit();
-    let mut A = Array2D::<DataType, M, N>::maybe_uninit();
-    let mut B = Array2D::<DataType, M, N>::maybe_uninit();
+    let mut C = Array2D::<DataType, N, N>::try_maybe_uninit_in(&alloc)?;
+    let mut A = Array2D::<DataType, N, M>::try_maybe_uninit_in(&alloc)?;
+    let mut B = Array2D::<DataType, N, M>::try_maybe_uninit_in(&alloc)?;
 
     unsafe {
+        let C = C.assume_init_mut();
+        let A = A.assume_init_mut();
+        let B = B.assume_init_mut();
+        init_array(m, n, &mut alpha, &mut beta, C, A, B);
+
+        let elapsed = util::benchmark_with_timer(
+            &[C.extent(), A.extent(), B.extent()],
+            || kernel_syr2k(m, n, alpha, beta, C, A, B),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, C, &mut crate::dump::Results::start());
+        util::consume(C);
+        Ok(elapsed)
+    }
+}
+
+/// Runs the same benchmark as [`bench`] with dimensions that are only known at runtime, given in
+/// the order of its const generics.
+pub fn bench_dyn(dims: [usize; 2], timer: &dyn Timer) -> Duration {
+    let [m, n] = dims;
+
+    let mut alpha = 0.0;
+    let mut beta = 0.0;
+
+    unsafe {
+        let mut C = DynArray2D::<DataType>::uninit(n, n);
+        let mut A = DynArray2D::<DataType>::uninit(n, m);
+        let mut B = DynArray2D::<DataType>::uninit(n, m);
         init_array(m, n, &mut alpha, &mut beta, &mut C, &mut A, &mut B);
-        let A = A.assume_init();
-        let B = B.assume_init();
-        let mut C = C.assume_init();
 
-        let elapsed = util::benchmark_with_timing_function(
+        let elapsed = util::benchmark_with_timer(
+            &[C.extent(), A.extent(), B.extent()],
             || kernel_syr2k(m, n, alpha, beta, &mut C, &A, &B),
-            timing_function,
+            timer,
         );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, &C, &mut crate::dump::Results::start());
         util::consume(C);
         elapsed
     }
 }
 
-#[test]
-fn check() {}
+/// Like [`bench_dyn`], but stores the arrays with two or three dimensions in the layout `L`.
+pub fn bench_layout<L: Layout>(dims: [usize; 2], timer: &dyn Timer) -> Duration {
+    let [m, n] = dims;
+
+    let mut alpha = 0.0;
+    let mut beta = 0.0;
+
+    unsafe {
+        let mut C = LayoutArray2D::<DataType, L>::uninit(n, n);
+        let mut A = LayoutArray2D::<DataType, L>::uninit(n, m);
+        let mut B = LayoutArray2D::<DataType, L>::uninit(n, m);
+        init_array(m, n, &mut alpha, &mut beta, &mut C, &mut A, &mut B);
+
+        let elapsed = util::benchmark_with_timer(
+            &[C.extent(), A.extent(), B.extent()],
+            || kernel_syr2k(m, n, alpha, beta, &mut C, &A, &B),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, &C, &mut crate::dump::Results::start());
+        util::consume(C);
+        elapsed
+    }
+}
+
+/// Floating-point operations of one run of [`bench`] with `dims`: 2·M·N·(N + 1), two multiply-adds
+/// per entry of the lower triangle and step.
+pub fn flops(dims: [usize; 2]) -> u64 {
+    let [m, n] = dims.map(|d| d as u64);
+    2 * m * n * (n + 1)
+}
+
+/// Bytes of the arrays [`bench_dyn`] allocates with `dims`: N² + 2·N·M elements.
+pub fn footprint(dims: [usize; 2]) -> u64 {
+    let [m, n] = dims;
+    crate::ndarray::footprint::<DataType>(&[&[n, n], &[n, m], &[n, m]])
+}
+
+crate::dataset::datasets! {
+    Mini: [20, 30],
+    Small: [60, 80],
+    Medium: [200, 240],
+    Large: [1000, 1200],
+    ExtraLarge: [2000, 2600],
+}
+
+#[cfg(test)]
+crate::dataset::checksums! {
+    Mini: [("C", 6400.900000000004, 3802510.18)],
+    Small: [("C", 135708.0104166672, 577757750.8933331)],
+}
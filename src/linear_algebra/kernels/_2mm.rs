@@ -1,65 +1,87 @@
 #![allow(non_snake_case)]
 
-use crate::config::linear_algebra::kernels::_2mm::DataType;
-use crate::ndarray::{Array2D, ArrayAlloc};
+pub use crate::config::linear_algebra::kernels::_2mm::DataType;
+#[cfg(any(test, feature = "print-result"))]
+use crate::dump::Dump;
+use crate::layout::{Layout, LayoutArray2D};
+use crate::ndarray::{AllocError, Array2, Array2D, ArrayAlloc, Contiguous, DynArray2D};
+use crate::pages::PageAllocator;
+use crate::timer::Timer;
 use crate::util;
-use core::mem::MaybeUninit;
+use core::alloc::Allocator;
 use core::time::Duration;
 
-unsafe fn init_array<const NI: usize, const NJ: usize, const NK: usize, const NL: usize>(
+#[allow(
+    clippy::too_many_arguments,
+    reason = "the parameters of PolyBench/C's init_array for 2mm"
+)]
+unsafe fn init_array(
     ni: usize,
     nj: usize,
     nk: usize,
     nl: usize,
     alpha: &mut DataType,
     beta: &mut DataType,
-    A: &mut MaybeUninit<Array2D<DataType, NI, NK>>,
-    B: &mut MaybeUninit<Array2D<DataType, NK, NJ>>,
-    C: &mut MaybeUninit<Array2D<DataType, NJ, NL>>,
-    D: &mut MaybeUninit<Array2D<DataType, NI, NL>>,
+    A: &mut impl Array2<DataType>,
+    B: &mut impl Array2<DataType>,
+    C: &mut impl Array2<DataType>,
+    D: &mut impl Array2<DataType>,
 ) {
     *alpha = 1.5;
     *beta = 1.2;
 
-    let a_mut = unsafe { A.assume_init_mut() };
-    let b_mut = unsafe { B.assume_init_mut() };
-    let c_mut = unsafe { C.assume_init_mut() };
-    let d_mut = unsafe { D.assume_init_mut() };
-
     for i in 0..ni {
         for j in 0..nk {
-            a_mut[i][j] = ((i * j + 1) % ni) as DataType / ni as DataType;
+            A[i][j] = ((i * j + 1) % ni) as DataType / ni as DataType;
         }
     }
     for i in 0..nk {
         for j in 0..nj {
-            b_mut[i][j] = (i * (j + 1) % nj) as DataType / nj as DataType;
+            B[i][j] = (i * (j + 1) % nj) as DataType / nj as DataType;
         }
     }
     for i in 0..nj {
         for j in 0..nl {
-            c_mut[i][j] = ((i * (j + 3) + 1) % nl) as DataType / nl as DataType;
+            C[i][j] = ((i * (j + 3) + 1) % nl) as DataType / nl as DataType;
         }
     }
     for i in 0..ni {
         for j in 0..nl {
-            d_mut[i][j] = (i * (j + 2) % nk) as DataType / nk as DataType;
+            D[i][j] = (i * (j + 2) % nk) as DataType / nk as DataType;
         }
     }
 }
 
-unsafe fn kernel_2mm<const NI: usize, const NJ: usize, const NK: usize, const NL: usize>(
+#[cfg(any(test, feature = "print-result"))]
+fn print_array(ni: usize, nl: usize, D: &impl Array2<DataType>, dump: &mut impl Dump) {
+    dump.begin("D");
+    for i in 0..ni {
+        for j in 0..nl {
+            if (i * ni + j).is_multiple_of(20) {
+                dump.newline();
+            }
+            dump.value(D[i][j]);
+        }
+    }
+    dump.end("D");
+}
+
+#[allow(
+    clippy::too_many_arguments,
+    reason = "the parameters of PolyBench/C's kernel_2mm"
+)]
+unsafe fn kernel_2mm(
     ni: usize,
     nj: usize,
     nk: usize,
     nl: usize,
     alpha: DataType,
     beta: DataType,
-    tmp: &mut Array2D<DataType, NI, NJ>,
-    A: &Array2D<DataType, NI, NK>,
-    B: &Array2D<DataType, NK, NJ>,
-    C: &Array2D<DataType, NJ, NL>,
-    D: &mut Array2D<DataType, NI, NL>,
+    tmp: &mut impl Array2<DataType>,
+    A: &impl Array2<DataType>,
+    B: &impl Array2<DataType>,
+    C: &impl Array2<DataType>,
+    D: &mut impl Array2<DataType>,
 ) {
     for i in 0..ni {
         for j in 0..nj {
@@ -80,8 +102,44 @@ unsafe fn kernel_2mm<const NI: usize, const NJ: usize, const NK: usize, const NL
 }
 
 pub fn bench<const NI: usize, const NJ: usize, const NK: usize, const NL: usize>(
-    timing_function: &dyn Fn() -> u64,
+    timer: &dyn Timer,
+) -> Duration {
+    bench_in::<NI, NJ, NK, NL, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench`], but allocates the arrays with `alloc` instead of a [`PageAllocator`].
+pub fn bench_in<
+    const NI: usize,
+    const NJ: usize,
+    const NK: usize,
+    const NL: usize,
+    Alloc: Allocator,
+>(
+    timer: &dyn Timer,
+    alloc: Alloc,
 ) -> Duration {
+    try_bench_in::<NI, NJ, NK, NL, _>(timer, alloc).unwrap_or_else(|e| e.handle())
+}
+
+/// Like [`bench`], but returns an error instead of aborting if the arrays cannot be allocated.
+pub fn try_bench<const NI: usize, const NJ: usize, const NK: usize, const NL: usize>(
+    timer: &dyn Timer,
+) -> Result<Duration, AllocError> {
+    try_bench_in::<NI, NJ, NK, NL, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench_in`], but returns an error instead of aborting if the arrays cannot be
+/// allocated.
+pub fn try_bench_in<
+    const NI: usize,
+    const NJ: usize,
+    const NK: usize,
+    const NL: usize,
+    Alloc: Allocator,
+>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Result<Duration, AllocError> {
     let ni = NI;
     let nj = NJ;
     let nk = NK;
@@ -89,31 +147,114 @@ pub fn bench<const NI: usize, const NJ: usize, const NK: usize, const NL: usize>
 
     let mut alpha = 0.0;
     let mut beta = 0.0;
-    let mut A = Array2D::<DataType, NI, NK>::maybe_uninit();
-    let mut B = Array2D::<DataType, NK, NJ>::maybe_uninit();
-    let mut C = Array2D::<DataType, NJ, NL>::maybe_uninit();
-    let mut D = Array2D::<DataType, NI, NL>::maybe_uninit();
+    let mut A = Array2D::<DataType, NI, NK>::try_maybe_uninit_in(&alloc)?;
+    let mut B = Array2D::<DataType, NK, NJ>::try_maybe_uninit_in(&alloc)?;
+    let mut C = Array2D::<DataType, NJ, NL>::try_maybe_uninit_in(&alloc)?;
+    let mut D = Array2D::<DataType, NI, NL>::try_maybe_uninit_in(&alloc)?;
+    let mut tmp = Array2D::<DataType, NI, NJ>::try_maybe_uninit_in(&alloc)?;
 
     unsafe {
+        let A = A.assume_init_mut();
+        let B = B.assume_init_mut();
+        let C = C.assume_init_mut();
+        let D = D.assume_init_mut();
+        let tmp = tmp.assume_init_mut();
+        init_array(ni, nj, nk, nl, &mut alpha, &mut beta, A, B, C, D);
+
+        let elapsed = util::benchmark_with_timer(
+            &[tmp.extent(), A.extent(), B.extent(), C.extent(), D.extent()],
+            || kernel_2mm(ni, nj, nk, nl, alpha, beta, tmp, A, B, C, D),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(ni, nl, D, &mut crate::dump::Results::start());
+        util::consume(D);
+        Ok(elapsed)
+    }
+}
+
+/// Runs the same benchmark as [`bench`] with dimensions that are only known at runtime, given in
+/// the order of its const generics.
+pub fn bench_dyn(dims: [usize; 4], timer: &dyn Timer) -> Duration {
+    let [ni, nj, nk, nl] = dims;
+
+    let mut alpha = 0.0;
+    let mut beta = 0.0;
+
+    unsafe {
+        let mut A = DynArray2D::<DataType>::uninit(ni, nk);
+        let mut B = DynArray2D::<DataType>::uninit(nk, nj);
+        let mut C = DynArray2D::<DataType>::uninit(nj, nl);
+        let mut D = DynArray2D::<DataType>::uninit(ni, nl);
+        let mut tmp = DynArray2D::<DataType>::uninit(ni, nj);
         init_array(
             ni, nj, nk, nl, &mut alpha, &mut beta, &mut A, &mut B, &mut C, &mut D,
         );
 
-        let A = A.assume_init();
-        let B = B.assume_init();
-        let C = C.assume_init();
-        let D = D.assume_init_mut();
-        let mut tmp = Array2D::<DataType, NI, NJ>::maybe_uninit();
-        let tmp = tmp.assume_init_mut();
+        let elapsed = util::benchmark_with_timer(
+            &[tmp.extent(), A.extent(), B.extent(), C.extent(), D.extent()],
+            || kernel_2mm(ni, nj, nk, nl, alpha, beta, &mut tmp, &A, &B, &C, &mut D),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(ni, nl, &D, &mut crate::dump::Results::start());
+        util::consume(D);
+        elapsed
+    }
+}
+
+/// Like [`bench_dyn`], but stores the arrays with two or three dimensions in the layout `L`.
+pub fn bench_layout<L: Layout>(dims: [usize; 4], timer: &dyn Timer) -> Duration {
+    let [ni, nj, nk, nl] = dims;
+
+    let mut alpha = 0.0;
+    let mut beta = 0.0;
 
-        let elapsed = util::benchmark_with_timing_function(
-            || kernel_2mm(ni, nj, nk, nl, alpha, beta, tmp, &A, &B, &C, D),
-            timing_function,
+    unsafe {
+        let mut A = LayoutArray2D::<DataType, L>::uninit(ni, nk);
+        let mut B = LayoutArray2D::<DataType, L>::uninit(nk, nj);
+        let mut C = LayoutArray2D::<DataType, L>::uninit(nj, nl);
+        let mut D = LayoutArray2D::<DataType, L>::uninit(ni, nl);
+        let mut tmp = LayoutArray2D::<DataType, L>::uninit(ni, nj);
+        init_array(
+            ni, nj, nk, nl, &mut alpha, &mut beta, &mut A, &mut B, &mut C, &mut D,
         );
+
+        let elapsed = util::benchmark_with_timer(
+            &[tmp.extent(), A.extent(), B.extent(), C.extent(), D.extent()],
+            || kernel_2mm(ni, nj, nk, nl, alpha, beta, &mut tmp, &A, &B, &C, &mut D),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(ni, nl, &D, &mut crate::dump::Results::start());
         util::consume(D);
         elapsed
     }
 }
 
-#[test]
-fn check() {}
+/// Floating-point operations of one run of [`bench`] with `dims`: 2·NI·NJ·(NK + NL).
+pub fn flops(dims: [usize; 4]) -> u64 {
+    let [ni, nj, nk, nl] = dims.map(|d| d as u64);
+    2 * ni * nj * (nk + nl)
+}
+
+/// Bytes of the arrays [`bench_dyn`] allocates with `dims`: NI·NK + NK·NJ + NJ·NL + NI·NL + NI·NJ
+/// elements.
+pub fn footprint(dims: [usize; 4]) -> u64 {
+    let [ni, nj, nk, nl] = dims;
+    crate::ndarray::footprint::<DataType>(&[&[ni, nk], &[nk, nj], &[nj, nl], &[ni, nl], &[ni, nj]])
+}
+
+crate::dataset::datasets! {
+    Mini: [16, 18, 22, 24],
+    Small: [40, 50, 70, 80],
+    Medium: [180, 190, 210, 220],
+    Large: [800, 900, 1100, 1200],
+    ExtraLarge: [1600, 1800, 2200, 2400],
+}
+
+#[cfg(test)]
+crate::dataset::checksums! {
+    Mini: [("D", 17079.47727272726, 3526943.8147727265)],
+    Small: [("D", 1689742.377857143, 2799577664.486793)],
+}
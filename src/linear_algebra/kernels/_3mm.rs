@@ -1,74 +1,84 @@
 #![allow(non_snake_case)]
 
-use crate::config::linear_algebra::kernels::_3mm::DataType;
-use crate::ndarray::{Array2D, ArrayAlloc};
+pub use crate::config::linear_algebra::kernels::_3mm::DataType;
+#[cfg(any(test, feature = "print-result"))]
+use crate::dump::Dump;
+use crate::layout::{Layout, LayoutArray2D};
+use crate::ndarray::{AllocError, Array2, Array2D, ArrayAlloc, Contiguous, DynArray2D};
+use crate::pages::PageAllocator;
+use crate::timer::Timer;
 use crate::util;
-use core::mem::MaybeUninit;
+use core::alloc::Allocator;
 use core::time::Duration;
 
-unsafe fn init_array<
-    const NI: usize,
-    const NJ: usize,
-    const NK: usize,
-    const NL: usize,
-    const NM: usize,
->(
+#[allow(
+    clippy::too_many_arguments,
+    reason = "the parameters of PolyBench/C's init_array for 3mm"
+)]
+unsafe fn init_array(
     ni: usize,
     nj: usize,
     nk: usize,
     nl: usize,
     nm: usize,
-    A: &mut MaybeUninit<Array2D<DataType, NI, NK>>,
-    B: &mut MaybeUninit<Array2D<DataType, NK, NJ>>,
-    C: &mut MaybeUninit<Array2D<DataType, NJ, NM>>,
-    D: &mut MaybeUninit<Array2D<DataType, NM, NL>>,
+    A: &mut impl Array2<DataType>,
+    B: &mut impl Array2<DataType>,
+    C: &mut impl Array2<DataType>,
+    D: &mut impl Array2<DataType>,
 ) {
-    let a_mut = unsafe { A.assume_init_mut() };
-    let b_mut = unsafe { B.assume_init_mut() };
-    let c_mut = unsafe { C.assume_init_mut() };
-    let d_mut = unsafe { D.assume_init_mut() };
-
     for i in 0..ni {
         for j in 0..nk {
-            a_mut[i][j] = ((i * j + 1) % ni) as DataType / (5 * ni) as DataType;
+            A[i][j] = ((i * j + 1) % ni) as DataType / (5 * ni) as DataType;
         }
     }
     for i in 0..nk {
         for j in 0..nj {
-            b_mut[i][j] = ((i * (j + 1) + 2) % nj) as DataType / (5 * nj) as DataType;
+            B[i][j] = ((i * (j + 1) + 2) % nj) as DataType / (5 * nj) as DataType;
         }
     }
     for i in 0..nj {
         for j in 0..nm {
-            c_mut[i][j] = (i * (j + 3) % nl) as DataType / (5 * nl) as DataType;
+            C[i][j] = (i * (j + 3) % nl) as DataType / (5 * nl) as DataType;
         }
     }
     for i in 0..nm {
         for j in 0..nl {
-            d_mut[i][j] = ((i * (j + 2) + 2) % nk) as DataType / (5 * nk) as DataType;
+            D[i][j] = ((i * (j + 2) + 2) % nk) as DataType / (5 * nk) as DataType;
         }
     }
 }
 
-unsafe fn kernel_3mm<
-    const NI: usize,
-    const NJ: usize,
-    const NK: usize,
-    const NL: usize,
-    const NM: usize,
->(
+#[cfg(any(test, feature = "print-result"))]
+fn print_array(ni: usize, nl: usize, G: &impl Array2<DataType>, dump: &mut impl Dump) {
+    dump.begin("G");
+    for i in 0..ni {
+        for j in 0..nl {
+            if (i * ni + j).is_multiple_of(20) {
+                dump.newline();
+            }
+            dump.value(G[i][j]);
+        }
+    }
+    dump.end("G");
+}
+
+#[allow(
+    clippy::too_many_arguments,
+    reason = "the parameters of PolyBench/C's kernel_3mm"
+)]
+unsafe fn kernel_3mm(
     ni: usize,
     nj: usize,
     nk: usize,
     nl: usize,
     nm: usize,
-    E: &mut Array2D<DataType, NI, NJ>,
-    A: &Array2D<DataType, NI, NK>,
-    B: &Array2D<DataType, NK, NJ>,
-    F: &mut Array2D<DataType, NJ, NL>,
-    C: &Array2D<DataType, NJ, NM>,
-    D: &Array2D<DataType, NM, NL>,
-    G: &mut Array2D<DataType, NI, NL>,
+    E: &mut impl Array2<DataType>,
+    A: &impl Array2<DataType>,
+    B: &impl Array2<DataType>,
+    F: &mut impl Array2<DataType>,
+    C: &impl Array2<DataType>,
+    D: &impl Array2<DataType>,
+    G: &mut impl Array2<DataType>,
 ) {
     for i in 0..ni {
         for j in 0..nj {
@@ -103,40 +113,197 @@ pub fn bench<
     const NL: usize,
     const NM: usize,
 >(
-    timing_function: &dyn Fn() -> u64,
+    timer: &dyn Timer,
+) -> Duration {
+    bench_in::<NI, NJ, NK, NL, NM, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench`], but allocates the arrays with `alloc` instead of a [`PageAllocator`].
+pub fn bench_in<
+    const NI: usize,
+    const NJ: usize,
+    const NK: usize,
+    const NL: usize,
+    const NM: usize,
+    Alloc: Allocator,
+>(
+    timer: &dyn Timer,
+    alloc: Alloc,
 ) -> Duration {
+    try_bench_in::<NI, NJ, NK, NL, NM, _>(timer, alloc).unwrap_or_else(|e| e.handle())
+}
+
+/// Like [`bench`], but returns an error instead of aborting if the arrays cannot be allocated.
+pub fn try_bench<
+    const NI: usize,
+    const NJ: usize,
+    const NK: usize,
+    const NL: usize,
+    const NM: usize,
+>(
+    timer: &dyn Timer,
+) -> Result<Duration, AllocError> {
+    try_bench_in::<NI, NJ, NK, NL, NM, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench_in`], but returns an error instead of aborting if the arrays cannot be
+/// allocated.
+pub fn try_bench_in<
+    const NI: usize,
+    const NJ: usize,
+    const NK: usize,
+    const NL: usize,
+    const NM: usize,
+    Alloc: Allocator,
+>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Result<Duration, AllocError> {
     let ni = NI;
     let nj = NJ;
     let nk = NK;
     let nl = NL;
     let nm = NM;
 
-    let E = Array2D::<DataType, NI, NJ>::maybe_uninit();
-    let mut A = Array2D::<DataType, NI, NK>::maybe_uninit();
-    let mut B = Array2D::<DataType, NK, NJ>::maybe_uninit();
-    let F = Array2D::<DataType, NJ, NL>::maybe_uninit();
-    let mut C = Array2D::<DataType, NJ, NM>::maybe_uninit();
-    let mut D = Array2D::<DataType, NM, NL>::maybe_uninit();
-    let G = Array2D::<DataType, NI, NL>::maybe_uninit();
+    let mut E = Array2D::<DataType, NI, NJ>::try_maybe_uninit_in(&alloc)?;
+    let mut A = Array2D::<DataType, NI, NK>::try_maybe_uninit_in(&alloc)?;
+    let mut B = Array2D::<DataType, NK, NJ>::try_maybe_uninit_in(&alloc)?;
+    let mut F = Array2D::<DataType, NJ, NL>::try_maybe_uninit_in(&alloc)?;
+    let mut C = Array2D::<DataType, NJ, NM>::try_maybe_uninit_in(&alloc)?;
+    let mut D = Array2D::<DataType, NM, NL>::try_maybe_uninit_in(&alloc)?;
+    let mut G = Array2D::<DataType, NI, NL>::try_maybe_uninit_in(&alloc)?;
+
+    unsafe {
+        let E = E.assume_init_mut();
+        let A = A.assume_init_mut();
+        let B = B.assume_init_mut();
+        let F = F.assume_init_mut();
+        let C = C.assume_init_mut();
+        let D = D.assume_init_mut();
+        let G = G.assume_init_mut();
+        init_array(ni, nj, nk, nl, nm, A, B, C, D);
+
+        let elapsed = util::benchmark_with_timer(
+            &[
+                E.extent(),
+                A.extent(),
+                B.extent(),
+                F.extent(),
+                C.extent(),
+                D.extent(),
+                G.extent(),
+            ],
+            || kernel_3mm(ni, nj, nk, nl, nm, E, A, B, F, C, D, G),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(ni, nl, G, &mut crate::dump::Results::start());
+        util::consume(G);
+        Ok(elapsed)
+    }
+}
+
+/// Runs the same benchmark as [`bench`] with dimensions that are only known at runtime, given in
+/// the order of its const generics.
+pub fn bench_dyn(dims: [usize; 5], timer: &dyn Timer) -> Duration {
+    let [ni, nj, nk, nl, nm] = dims;
+
+    unsafe {
+        let mut E = DynArray2D::<DataType>::uninit(ni, nj);
+        let mut A = DynArray2D::<DataType>::uninit(ni, nk);
+        let mut B = DynArray2D::<DataType>::uninit(nk, nj);
+        let mut F = DynArray2D::<DataType>::uninit(nj, nl);
+        let mut C = DynArray2D::<DataType>::uninit(nj, nm);
+        let mut D = DynArray2D::<DataType>::uninit(nm, nl);
+        let mut G = DynArray2D::<DataType>::uninit(ni, nl);
+        init_array(ni, nj, nk, nl, nm, &mut A, &mut B, &mut C, &mut D);
+
+        let elapsed = util::benchmark_with_timer(
+            &[
+                E.extent(),
+                A.extent(),
+                B.extent(),
+                F.extent(),
+                C.extent(),
+                D.extent(),
+                G.extent(),
+            ],
+            || kernel_3mm(ni, nj, nk, nl, nm, &mut E, &A, &B, &mut F, &C, &D, &mut G),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(ni, nl, &G, &mut crate::dump::Results::start());
+        util::consume(G);
+        elapsed
+    }
+}
+
+/// Like [`bench_dyn`], but stores the arrays with two or three dimensions in the layout `L`.
+pub fn bench_layout<L: Layout>(dims: [usize; 5], timer: &dyn Timer) -> Duration {
+    let [ni, nj, nk, nl, nm] = dims;
 
     unsafe {
+        let mut E = LayoutArray2D::<DataType, L>::uninit(ni, nj);
+        let mut A = LayoutArray2D::<DataType, L>::uninit(ni, nk);
+        let mut B = LayoutArray2D::<DataType, L>::uninit(nk, nj);
+        let mut F = LayoutArray2D::<DataType, L>::uninit(nj, nl);
+        let mut C = LayoutArray2D::<DataType, L>::uninit(nj, nm);
+        let mut D = LayoutArray2D::<DataType, L>::uninit(nm, nl);
+        let mut G = LayoutArray2D::<DataType, L>::uninit(ni, nl);
         init_array(ni, nj, nk, nl, nm, &mut A, &mut B, &mut C, &mut D);
-        let A = A.assume_init();
-        let B = B.assume_init();
-        let C = C.assume_init();
-        let D = D.assume_init();
-        let mut E = E.assume_init();
-        let mut F = F.assume_init();
-        let mut G = G.assume_init();
-
-        let elapsed = util::benchmark_with_timing_function(
+
+        let elapsed = util::benchmark_with_timer(
+            &[
+                E.extent(),
+                A.extent(),
+                B.extent(),
+                F.extent(),
+                C.extent(),
+                D.extent(),
+                G.extent(),
+            ],
             || kernel_3mm(ni, nj, nk, nl, nm, &mut E, &A, &B, &mut F, &C, &D, &mut G),
-            timing_function,
+            timer,
         );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(ni, nl, &G, &mut crate::dump::Results::start());
         util::consume(G);
         elapsed
     }
 }
 
-#[test]
-fn check() {}
+/// Floating-point operations of one run of [`bench`] with `dims`:
+/// 2·(NI·NJ·NK + NJ·NL·NM + NI·NL·NJ).
+pub fn flops(dims: [usize; 5]) -> u64 {
+    let [ni, nj, nk, nl, nm] = dims.map(|d| d as u64);
+    2 * (ni * nj * nk + nj * nl * nm + ni * nl * nj)
+}
+
+/// Bytes of the arrays [`bench_dyn`] allocates with `dims`: NI·NJ + NI·NK + NK·NJ + NJ·NL + NJ·NM +
+/// NM·NL + NI·NL elements.
+pub fn footprint(dims: [usize; 5]) -> u64 {
+    let [ni, nj, nk, nl, nm] = dims;
+    crate::ndarray::footprint::<DataType>(&[
+        &[ni, nj],
+        &[ni, nk],
+        &[nk, nj],
+        &[nj, nl],
+        &[nj, nm],
+        &[nm, nl],
+        &[ni, nl],
+    ])
+}
+
+crate::dataset::datasets! {
+    Mini: [16, 18, 20, 22, 24],
+    Small: [40, 50, 60, 70, 80],
+    Medium: [180, 190, 200, 210, 220],
+    Large: [800, 900, 1000, 1100, 1200],
+    ExtraLarge: [1600, 1800, 2000, 2200, 2400],
+}
+
+#[cfg(test)]
+crate::dataset::checksums! {
+    Mini: [("G", 169.06272484848495, 31969.365488484837)],
+    Small: [("G", 50186.45508228571, 72784545.9042523)],
+}
@@ -1,39 +1,55 @@
 #![allow(non_snake_case)]
 
-use crate::config::linear_algebra::kernels::atax::DataType;
-use crate::ndarray::{Array1D, Array2D, ArrayAlloc};
+pub use crate::config::linear_algebra::kernels::atax::DataType;
+#[cfg(any(test, feature = "print-result"))]
+use crate::dump::Dump;
+use crate::layout::{Layout, LayoutArray2D};
+use crate::ndarray::{
+    AllocError, Array1, Array1D, Array2, Array2D, ArrayAlloc, Contiguous, DynArray1D, DynArray2D,
+};
+use crate::pages::PageAllocator;
+use crate::timer::Timer;
 use crate::util;
-use core::mem::MaybeUninit;
+use core::alloc::Allocator;
 use core::time::Duration;
 
-unsafe fn init_array<const M: usize, const N: usize>(
+unsafe fn init_array(
     m: usize,
     n: usize,
-    A: &mut MaybeUninit<Array2D<DataType, M, N>>,
-    x: &mut MaybeUninit<Array1D<DataType, N>>,
+    A: &mut impl Array2<DataType>,
+    x: &mut impl Array1<DataType>,
 ) {
-    let a_mut = unsafe { A.assume_init_mut() };
-    let x_mut = unsafe { x.assume_init_mut() };
-
     let float_n = n as DataType;
     for i in 0..n {
-        x_mut[i] = 1.0 + (i as DataType / float_n);
+        x[i] = 1.0 + (i as DataType / float_n);
     }
 
     for i in 0..m {
         for j in 0..n {
-            a_mut[i][j] = ((i + j) % n) as DataType / (5 * m) as DataType;
+            A[i][j] = ((i + j) % n) as DataType / (5 * m) as DataType;
+        }
+    }
+}
+
+#[cfg(any(test, feature = "print-result"))]
+fn print_array(n: usize, y: &impl Array1<DataType>, dump: &mut impl Dump) {
+    dump.begin("y");
+    for i in 0..n {
+        if i.is_multiple_of(20) {
+            dump.newline();
         }
+        dump.value(y[i]);
     }
+    dump.end("y");
 }
 
-unsafe fn kernel_atax<const M: usize, const N: usize>(
+unsafe fn kernel_atax(
     m: usize,
     n: usize,
-    A: &Array2D<DataType, M, N>,
-    x: &Array1D<DataType, N>,
-    y: &mut Array1D<DataType, N>,
-    tmp: &mut Array1D<DataType, M>,
+    A: &impl Array2<DataType>,
+    x: &impl Array1<DataType>,
+    y: &mut impl Array1<DataType>,
+    tmp: &mut impl Array1<DataType>,
 ) {
     for i in 0..n {
         y[i] = 0.0;
@@ -41,39 +57,135 @@ unsafe fn kernel_atax<const M: usize, const N: usize>(
     for i in 0..m {
         tmp[i] = 0.0;
         for j in 0..n {
-            tmp[i] = tmp[i] + A[i][j] * x[j];
+            tmp[i] += A[i][j] * x[j];
         }
         for j in 0..n {
-            y[j] = y[j] + A[i][j] + tmp[i];
+            y[j] += A[i][j] * tmp[i];
         }
     }
 }
 
-pub fn bench<const M: usize, const N: usize>(timing_function: &dyn Fn() -> u64) -> Duration {
+pub fn bench<const M: usize, const N: usize>(timer: &dyn Timer) -> Duration {
+    bench_in::<M, N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench`], but allocates the arrays with `alloc` instead of a [`PageAllocator`].
+pub fn bench_in<const M: usize, const N: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Duration {
+    try_bench_in::<M, N, _>(timer, alloc).unwrap_or_else(|e| e.handle())
+}
+
+/// Like [`bench`], but returns an error instead of aborting if the arrays cannot be allocated.
+pub fn try_bench<const M: usize, const N: usize>(
+    timer: &dyn Timer,
+) -> Result<Duration, AllocError> {
+    try_bench_in::<M, N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench_in`], but returns an error instead of aborting if the arrays cannot be
+/// allocated.
+pub fn try_bench_in<const M: usize, const N: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Result<Duration, AllocError> {
     let m = M;
     let n = N;
 
-    let mut A = Array2D::<DataType, M, N>::maybe_uninit();
-    let mut x = Array1D::<DataType, N>::maybe_uninit();
-    let mut y = Array1D::<DataType, N>::maybe_uninit();
-    let mut tmp = Array1D::<DataType, M>::maybe_uninit();
+    let mut A = Array2D::<DataType, M, N>::try_maybe_uninit_in(&alloc)?;
+    let mut x = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
+    let mut y = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
+    let mut tmp = Array1D::<DataType, M>::try_maybe_uninit_in(&alloc)?;
 
     unsafe {
-        init_array(m, n, &mut A, &mut x);
-
-        let A = A.assume_init();
-        let x = x.assume_init();
+        let A = A.assume_init_mut();
+        let x = x.assume_init_mut();
         let y = y.assume_init_mut();
         let tmp = tmp.assume_init_mut();
+        init_array(m, n, A, x);
+
+        let elapsed = util::benchmark_with_timer(
+            &[A.extent(), x.extent(), y.extent(), tmp.extent()],
+            || kernel_atax(m, n, A, x, y, tmp),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, y, &mut crate::dump::Results::start());
+        util::consume(y);
+        Ok(elapsed)
+    }
+}
+
+/// Runs the same benchmark as [`bench`] with dimensions that are only known at runtime, given in
+/// the order of its const generics.
+pub fn bench_dyn(dims: [usize; 2], timer: &dyn Timer) -> Duration {
+    let [m, n] = dims;
 
-        let elapsed = util::benchmark_with_timing_function(
-            || kernel_atax(m, n, &A, &x, y, tmp),
-            timing_function,
+    unsafe {
+        let mut A = DynArray2D::<DataType>::uninit(m, n);
+        let mut x = DynArray1D::<DataType>::uninit(n);
+        let mut y = DynArray1D::<DataType>::uninit(n);
+        let mut tmp = DynArray1D::<DataType>::uninit(m);
+        init_array(m, n, &mut A, &mut x);
+
+        let elapsed = util::benchmark_with_timer(
+            &[A.extent(), x.extent(), y.extent(), tmp.extent()],
+            || kernel_atax(m, n, &A, &x, &mut y, &mut tmp),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, &y, &mut crate::dump::Results::start());
+        util::consume(y);
+        elapsed
+    }
+}
+
+/// Like [`bench_dyn`], but stores the arrays with two or three dimensions in the layout `L`.
+pub fn bench_layout<L: Layout>(dims: [usize; 2], timer: &dyn Timer) -> Duration {
+    let [m, n] = dims;
+
+    unsafe {
+        let mut A = LayoutArray2D::<DataType, L>::uninit(m, n);
+        let mut x = DynArray1D::<DataType>::uninit(n);
+        let mut y = DynArray1D::<DataType>::uninit(n);
+        let mut tmp = DynArray1D::<DataType>::uninit(m);
+        init_array(m, n, &mut A, &mut x);
+
+        let elapsed = util::benchmark_with_timer(
+            &[A.extent(), x.extent(), y.extent(), tmp.extent()],
+            || kernel_atax(m, n, &A, &x, &mut y, &mut tmp),
+            timer,
         );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, &y, &mut crate::dump::Results::start());
         util::consume(y);
         elapsed
     }
 }
 
-#[test]
-fn check() {}
+/// Floating-point operations of one run of [`bench`] with `dims`: 4·M·N.
+pub fn flops(dims: [usize; 2]) -> u64 {
+    let [m, n] = dims.map(|d| d as u64);
+    4 * m * n
+}
+
+/// Bytes of the arrays [`bench_dyn`] allocates with `dims`: M·N + 2·N + M elements.
+pub fn footprint(dims: [usize; 2]) -> u64 {
+    let [m, n] = dims;
+    crate::ndarray::footprint::<DataType>(&[&[m, n], &[n], &[n], &[m]])
+}
+
+crate::dataset::datasets! {
+    Mini: [38, 42],
+    Small: [116, 124],
+    Medium: [390, 410],
+    Large: [1900, 2100],
+    ExtraLarge: [1800, 2200],
+}
+
+#[cfg(test)]
+crate::dataset::checksums! {
+    Mini: [("y", 1151.8518421052634, 24345.024912280696)],
+    Small: [("y", 29815.687758620687, 1841263.6213793098)],
+}
@@ -1,81 +1,217 @@
 #![allow(non_snake_case)]
 
-use crate::config::linear_algebra::kernels::bicg::DataType;
-use crate::ndarray::{Array1D, Array2D, ArrayAlloc};
+pub use crate::config::linear_algebra::kernels::bicg::DataType;
+#[cfg(any(test, feature = "print-result"))]
+use crate::dump::Dump;
+use crate::layout::{Layout, LayoutArray2D};
+use crate::ndarray::{
+    AllocError, Array1, Array1D, Array2, Array2D, ArrayAlloc, Contiguous, DynArray1D, DynArray2D,
+};
+use crate::pages::PageAllocator;
+use crate::timer::Timer;
 use crate::util;
-use core::mem::MaybeUninit;
+use core::alloc::Allocator;
 use core::time::Duration;
 
-unsafe fn init_array<const M: usize, const N: usize>(
+unsafe fn init_array(
     m: usize,
     n: usize,
-    A: &mut MaybeUninit<Array2D<DataType, M, N>>,
-    r: &mut MaybeUninit<Array1D<DataType, M>>,
-    p: &mut MaybeUninit<Array1D<DataType, N>>,
+    A: &mut impl Array2<DataType>,
+    r: &mut impl Array1<DataType>,
+    p: &mut impl Array1<DataType>,
 ) {
-    let a_mut = unsafe { A.assume_init_mut() };
-    let r_mut = unsafe { r.assume_init_mut() };
-    let p_mut = unsafe { p.assume_init_mut() };
-
+    for i in 0..m {
+        p[i] = (i % m) as DataType / m as DataType;
+    }
     for i in 0..n {
-        p_mut[i] = (i % n) as DataType / n as DataType;
+        r[i] = (i % n) as DataType / n as DataType;
+        for j in 0..m {
+            A[i][j] = (i * (j + 1) % n) as DataType / n as DataType;
+        }
     }
+}
+
+#[cfg(any(test, feature = "print-result"))]
+fn print_array(
+    m: usize,
+    n: usize,
+    s: &impl Array1<DataType>,
+    q: &impl Array1<DataType>,
+    dump: &mut impl Dump,
+) {
+    dump.begin("s");
     for i in 0..m {
-        r_mut[i] = (i % m) as DataType / m as DataType;
-        for j in 0..n {
-            a_mut[i][j] = (i * (j + 1) % m) as DataType / m as DataType;
+        if i.is_multiple_of(20) {
+            dump.newline();
+        }
+        dump.value(s[i]);
+    }
+    dump.end("s");
+    dump.begin("q");
+    for i in 0..n {
+        if i.is_multiple_of(20) {
+            dump.newline();
         }
+        dump.value(q[i]);
     }
+    dump.end("q");
 }
 
-unsafe fn kernel_bicg<const M: usize, const N: usize>(
+unsafe fn kernel_bicg(
     m: usize,
     n: usize,
-    A: &Array2D<DataType, M, N>,
-    s: &mut Array1D<DataType, N>,
-    q: &mut Array1D<DataType, M>,
-    p: &Array1D<DataType, N>,
-    r: &Array1D<DataType, M>,
+    A: &impl Array2<DataType>,
+    s: &mut impl Array1<DataType>,
+    q: &mut impl Array1<DataType>,
+    p: &impl Array1<DataType>,
+    r: &impl Array1<DataType>,
 ) {
-    for i in 0..n {
+    for i in 0..m {
         s[i] = 0.0;
     }
-    for i in 0..m {
+    for i in 0..n {
         q[i] = 0.0;
-        for j in 0..n {
-            s[j] = s[j] + r[i] * A[i][j];
-            q[i] = q[i] + A[i][j] * p[j];
+        for j in 0..m {
+            s[j] += r[i] * A[i][j];
+            q[i] += A[i][j] * p[j];
         }
     }
 }
 
-pub fn bench<const M: usize, const N: usize>(timing_function: &dyn Fn() -> u64) -> Duration {
+pub fn bench<const M: usize, const N: usize>(timer: &dyn Timer) -> Duration {
+    bench_in::<M, N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench`], but allocates the arrays with `alloc` instead of a [`PageAllocator`].
+pub fn bench_in<const M: usize, const N: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Duration {
+    try_bench_in::<M, N, _>(timer, alloc).unwrap_or_else(|e| e.handle())
+}
+
+/// Like [`bench`], but returns an error instead of aborting if the arrays cannot be allocated.
+pub fn try_bench<const M: usize, const N: usize>(
+    timer: &dyn Timer,
+) -> Result<Duration, AllocError> {
+    try_bench_in::<M, N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench_in`], but returns an error instead of aborting if the arrays cannot be
+/// allocated.
+pub fn try_bench_in<const M: usize, const N: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Result<Duration, AllocError> {
     let m = M;
     let n = N;
 
-    let mut A = Array2D::<DataType, M, N>::maybe_uninit();
-    let s = Array1D::<DataType, N>::maybe_uninit();
-    let q = Array1D::<DataType, M>::maybe_uninit();
-    let mut p = Array1D::<DataType, N>::maybe_uninit();
-    let mut r = Array1D::<DataType, M>::maybe_uninit();
+    let mut A = Array2D::<DataType, N, M>::try_maybe_uninit_in(&alloc)?;
+    let mut s = Array1D::<DataType, M>::try_maybe_uninit_in(&alloc)?;
+    let mut q = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
+    let mut p = Array1D::<DataType, M>::try_maybe_uninit_in(&alloc)?;
+    let mut r = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
 
     unsafe {
-        init_array(m, n, &mut A, &mut r, &mut p);
         let A = A.assume_init_mut();
-        let mut s = s.assume_init();
-        let r = r.assume_init();
-        let mut q = q.assume_init();
-        let p = p.assume_init();
+        let s = s.assume_init_mut();
+        let q = q.assume_init_mut();
+        let p = p.assume_init_mut();
+        let r = r.assume_init_mut();
+        init_array(m, n, A, r, p);
+
+        let elapsed = util::benchmark_with_timer(
+            &[A.extent(), s.extent(), q.extent(), p.extent(), r.extent()],
+            || kernel_bicg(m, n, A, s, q, p, r),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(m, n, s, q, &mut crate::dump::Results::start());
+        util::consume(s);
+        util::consume(q);
+        Ok(elapsed)
+    }
+}
+
+/// Runs the same benchmark as [`bench`] with dimensions that are only known at runtime, given in
+/// the order of its const generics.
+pub fn bench_dyn(dims: [usize; 2], timer: &dyn Timer) -> Duration {
+    let [m, n] = dims;
+
+    unsafe {
+        let mut A = DynArray2D::<DataType>::uninit(n, m);
+        let mut s = DynArray1D::<DataType>::uninit(m);
+        let mut q = DynArray1D::<DataType>::uninit(n);
+        let mut p = DynArray1D::<DataType>::uninit(m);
+        let mut r = DynArray1D::<DataType>::uninit(n);
+        init_array(m, n, &mut A, &mut r, &mut p);
+
+        let elapsed = util::benchmark_with_timer(
+            &[A.extent(), s.extent(), q.extent(), p.extent(), r.extent()],
+            || kernel_bicg(m, n, &A, &mut s, &mut q, &p, &r),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(m, n, &s, &q, &mut crate::dump::Results::start());
+        util::consume(s);
+        util::consume(q);
+        elapsed
+    }
+}
 
-        let elapsed = util::benchmark_with_timing_function(
+/// Like [`bench_dyn`], but stores the arrays with two or three dimensions in the layout `L`.
+pub fn bench_layout<L: Layout>(dims: [usize; 2], timer: &dyn Timer) -> Duration {
+    let [m, n] = dims;
+
+    unsafe {
+        let mut A = LayoutArray2D::<DataType, L>::uninit(n, m);
+        let mut s = DynArray1D::<DataType>::uninit(m);
+        let mut q = DynArray1D::<DataType>::uninit(n);
+        let mut p = DynArray1D::<DataType>::uninit(m);
+        let mut r = DynArray1D::<DataType>::uninit(n);
+        init_array(m, n, &mut A, &mut r, &mut p);
+
+        let elapsed = util::benchmark_with_timer(
+            &[A.extent(), s.extent(), q.extent(), p.extent(), r.extent()],
             || kernel_bicg(m, n, &A, &mut s, &mut q, &p, &r),
-            timing_function,
+            timer,
         );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(m, n, &s, &q, &mut crate::dump::Results::start());
         util::consume(s);
         util::consume(q);
         elapsed
     }
 }
 
-#[test]
-fn check() {}
+/// Floating-point operations of one run of [`bench`] with `dims`: 4·M·N.
+pub fn flops(dims: [usize; 2]) -> u64 {
+    let [m, n] = dims.map(|d| d as u64);
+    4 * m * n
+}
+
+/// Bytes of the arrays [`bench_dyn`] allocates with `dims`: N·M + 2·M + 2·N elements.
+pub fn footprint(dims: [usize; 2]) -> u64 {
+    let [m, n] = dims;
+    crate::ndarray::footprint::<DataType>(&[&[n, m], &[m], &[n], &[m], &[n]])
+}
+
+crate::dataset::datasets! {
+    Mini: [38, 42],
+    Small: [116, 124],
+    Medium: [390, 410],
+    Large: [1900, 2100],
+    ExtraLarge: [1800, 2200],
+}
+
+#[cfg(test)]
+crate::dataset::checksums! {
+    Mini: [
+        ("s", 367.94047619047615, 6973.710317460319),
+        ("q", 351.2894736842105, 7652.40350877193),
+    ],
+    Small: [
+        ("s", 3533.3387096774186, 204278.83064516124),
+        ("q", 3475.448275862069, 218065.4568965517),
+    ],
+}
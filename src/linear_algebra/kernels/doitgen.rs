@@ -1,42 +1,63 @@
 #![allow(non_snake_case)]
 
-use crate::config::linear_algebra::kernels::doitgen::DataType;
-use crate::ndarray::{Array1D, Array2D, Array3D, ArrayAlloc};
+pub use crate::config::linear_algebra::kernels::doitgen::DataType;
+#[cfg(any(test, feature = "print-result"))]
+use crate::dump::Dump;
+use crate::layout::{Layout, LayoutArray2D, LayoutArray3D};
+use crate::ndarray::{
+    AllocError, Array1, Array1D, Array2, Array2D, Array3, Array3D, ArrayAlloc, Contiguous,
+    DynArray1D, DynArray2D, DynArray3D,
+};
+use crate::pages::PageAllocator;
+use crate::timer::Timer;
 use crate::util;
-use core::mem::MaybeUninit;
+use core::alloc::Allocator;
 use core::time::Duration;
 
-unsafe fn init_array<const NP: usize, const NQ: usize, const NR: usize>(
+unsafe fn init_array(
     nr: usize,
     nq: usize,
     np: usize,
-    A: &mut MaybeUninit<Array3D<DataType, NR, NQ, NP>>,
-    C4: &mut MaybeUninit<Array2D<DataType, NP, NP>>,
+    A: &mut impl Array3<DataType>,
+    C4: &mut impl Array2<DataType>,
 ) {
-    let a_mut = unsafe { A.assume_init_mut() };
-    let c4_mut = unsafe { C4.assume_init_mut() };
-
     for i in 0..nr {
         for j in 0..nq {
             for k in 0..np {
-                a_mut[i][j][k] = ((i * j + k) % np) as DataType / np as DataType;
+                A[i][j][k] = ((i * j + k) % np) as DataType / np as DataType;
             }
         }
     }
     for i in 0..np {
         for j in 0..np {
-            c4_mut[i][j] = (i * j % np) as DataType / np as DataType;
+            C4[i][j] = (i * j % np) as DataType / np as DataType;
         }
     }
 }
 
-unsafe fn kernel_doitgen<const NP: usize, const NQ: usize, const NR: usize>(
+#[cfg(any(test, feature = "print-result"))]
+fn print_array(nr: usize, nq: usize, np: usize, A: &impl Array3<DataType>, dump: &mut impl Dump) {
+    dump.begin("A");
+    for i in 0..nr {
+        for j in 0..nq {
+            for k in 0..np {
+                if (i * nq * np + j * np + k).is_multiple_of(20) {
+                    dump.newline();
+                }
+                dump.value(A[i][j][k]);
+            }
+        }
+    }
+    dump.end("A");
+}
+
+unsafe fn kernel_doitgen(
     nr: usize,
     nq: usize,
     np: usize,
-    A: &mut Array3D<DataType, NR, NQ, NP>,
-    C4: &Array2D<DataType, NP, NP>,
-    sum: &mut Array1D<DataType, NP>,
+    A: &mut impl Array3<DataType>,
+    C4: &impl Array2<DataType>,
+    sum: &mut impl Array1<DataType>,
 ) {
     for r in 0..nr {
         for q in 0..nq {
@@ -53,31 +74,124 @@ unsafe fn kernel_doitgen<const NP: usize, const NQ: usize, const NR: usize>(
     }
 }
 
-pub fn bench<const NP: usize, const NQ: usize, const NR: usize>(
-    timing_function: &dyn Fn() -> u64,
+pub fn bench<const NP: usize, const NQ: usize, const NR: usize>(timer: &dyn Timer) -> Duration {
+    bench_in::<NP, NQ, NR, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench`], but allocates the arrays with `alloc` instead of a [`PageAllocator`].
+pub fn bench_in<const NP: usize, const NQ: usize, const NR: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
 ) -> Duration {
+    try_bench_in::<NP, NQ, NR, _>(timer, alloc).unwrap_or_else(|e| e.handle())
+}
+
+/// Like [`bench`], but returns an error instead of aborting if the arrays cannot be allocated.
+pub fn try_bench<const NP: usize, const NQ: usize, const NR: usize>(
+    timer: &dyn Timer,
+) -> Result<Duration, AllocError> {
+    try_bench_in::<NP, NQ, NR, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench_in`], but returns an error instead of aborting if the arrays cannot be
+/// allocated.
+pub fn try_bench_in<const NP: usize, const NQ: usize, const NR: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Result<Duration, AllocError> {
     let nr = NR;
     let nq = NQ;
     let np = NP;
 
-    let mut A = Array3D::<DataType, NR, NQ, NP>::maybe_uninit();
-    let sum = Array1D::<DataType, NP>::maybe_uninit();
-    let mut C4 = Array2D::<DataType, NP, NP>::maybe_uninit();
+    let mut A = Array3D::<DataType, NR, NQ, NP>::try_maybe_uninit_in(&alloc)?;
+    let mut sum = Array1D::<DataType, NP>::try_maybe_uninit_in(&alloc)?;
+    let mut C4 = Array2D::<DataType, NP, NP>::try_maybe_uninit_in(&alloc)?;
+
+    unsafe {
+        let A = A.assume_init_mut();
+        let sum = sum.assume_init_mut();
+        let C4 = C4.assume_init_mut();
+        init_array(nr, nq, np, A, C4);
+
+        let elapsed = util::benchmark_with_timer(
+            &[A.extent(), C4.extent(), sum.extent()],
+            || kernel_doitgen(nr, nq, np, A, C4, sum),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(nr, nq, np, A, &mut crate::dump::Results::start());
+        util::consume(A);
+        Ok(elapsed)
+    }
+}
+
+/// Runs the same benchmark as [`bench`] with dimensions that are only known at runtime, given in
+/// the order of its const generics.
+pub fn bench_dyn(dims: [usize; 3], timer: &dyn Timer) -> Duration {
+    let [np, nq, nr] = dims;
 
     unsafe {
+        let mut A = DynArray3D::<DataType>::uninit(nr, nq, np);
+        let mut sum = DynArray1D::<DataType>::uninit(np);
+        let mut C4 = DynArray2D::<DataType>::uninit(np, np);
         init_array(nr, nq, np, &mut A, &mut C4);
-        let mut A = A.assume_init();
-        let C4 = C4.assume_init();
-        let mut sum = sum.assume_init();
 
-        let elapsed = util::benchmark_with_timing_function(
+        let elapsed = util::benchmark_with_timer(
+            &[A.extent(), C4.extent(), sum.extent()],
             || kernel_doitgen(nr, nq, np, &mut A, &C4, &mut sum),
-            timing_function,
+            timer,
         );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(nr, nq, np, &A, &mut crate::dump::Results::start());
         util::consume(A);
         elapsed
     }
 }
 
-#[test]
-fn check() {}
+/// Like [`bench_dyn`], but stores the arrays with two or three dimensions in the layout `L`.
+pub fn bench_layout<L: Layout>(dims: [usize; 3], timer: &dyn Timer) -> Duration {
+    let [np, nq, nr] = dims;
+
+    unsafe {
+        let mut A = LayoutArray3D::<DataType, L>::uninit(nr, nq, np);
+        let mut sum = DynArray1D::<DataType>::uninit(np);
+        let mut C4 = LayoutArray2D::<DataType, L>::uninit(np, np);
+        init_array(nr, nq, np, &mut A, &mut C4);
+
+        let elapsed = util::benchmark_with_timer(
+            &[A.extent(), C4.extent(), sum.extent()],
+            || kernel_doitgen(nr, nq, np, &mut A, &C4, &mut sum),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(nr, nq, np, &A, &mut crate::dump::Results::start());
+        util::consume(A);
+        elapsed
+    }
+}
+
+/// Floating-point operations of one run of [`bench`] with `dims`: 2·NR·NQ·NP².
+pub fn flops(dims: [usize; 3]) -> u64 {
+    let [np, nq, nr] = dims.map(|d| d as u64);
+    2 * nr * nq * np * np
+}
+
+/// Bytes of the arrays [`bench_dyn`] allocates with `dims`: NR·NQ·NP + NP + NP² elements.
+pub fn footprint(dims: [usize; 3]) -> u64 {
+    let [np, nq, nr] = dims;
+    crate::ndarray::footprint::<DataType>(&[&[nr, nq, np], &[np], &[np, np]])
+}
+
+crate::dataset::datasets! {
+    Mini: [12, 8, 10],
+    Small: [30, 20, 25],
+    Medium: [60, 40, 50],
+    Large: [160, 140, 150],
+    ExtraLarge: [270, 220, 250],
+}
+
+#[cfg(test)]
+crate::dataset::checksums! {
+    Mini: [("A", 1971.0000000000005, 942902.5555555568)],
+    Small: [("A", 93114.99999999678, 697680916.5555573)],
+}
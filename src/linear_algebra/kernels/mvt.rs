@@ -1,25 +1,26 @@
 #![allow(non_snake_case)]
 
-use crate::config::linear_algebra::kernels::mvt::DataType;
-use crate::ndarray::{Array1D, Array2D, ArrayAlloc};
+pub use crate::config::linear_algebra::kernels::mvt::DataType;
+#[cfg(any(test, feature = "print-result"))]
+use crate::dump::Dump;
+use crate::layout::{Layout, LayoutArray2D};
+use crate::ndarray::{
+    AllocError, Array1, Array1D, Array2, Array2D, ArrayAlloc, Contiguous, DynArray1D, DynArray2D,
+};
+use crate::pages::PageAllocator;
+use crate::timer::Timer;
 use crate::util;
-use core::mem::MaybeUninit;
+use core::alloc::Allocator;
 use core::time::Duration;
 
-unsafe fn init_array<const N: usize>(
+unsafe fn init_array(
     n: usize,
-    x1: &mut MaybeUninit<Array1D<DataType, N>>,
-    x2: &mut MaybeUninit<Array1D<DataType, N>>,
-    y_1: &mut MaybeUninit<Array1D<DataType, N>>,
-    y_2: &mut MaybeUninit<Array1D<DataType, N>>,
-    A: &mut MaybeUninit<Array2D<DataType, N, N>>,
+    x1: &mut impl Array1<DataType>,
+    x2: &mut impl Array1<DataType>,
+    y_1: &mut impl Array1<DataType>,
+    y_2: &mut impl Array1<DataType>,
+    A: &mut impl Array2<DataType>,
 ) {
-    let x1 = unsafe { x1.assume_init_mut() };
-    let x2 = unsafe { x2.assume_init_mut() };
-    let y_1 = unsafe { y_1.assume_init_mut() };
-    let y_2 = unsafe { y_2.assume_init_mut() };
-    let A = unsafe { A.assume_init_mut() };
-
     for i in 0..n {
         x1[i] = (i % n) as DataType / n as DataType;
         x2[i] = ((i + 1) % n) as DataType / n as DataType;
@@ -31,53 +32,194 @@ unsafe fn init_array<const N: usize>(
     }
 }
 
-unsafe fn kernel_mvt<const N: usize>(
+#[cfg(any(test, feature = "print-result"))]
+fn print_array(
+    n: usize,
+    x1: &impl Array1<DataType>,
+    x2: &impl Array1<DataType>,
+    dump: &mut impl Dump,
+) {
+    dump.begin("x1");
+    for i in 0..n {
+        if i.is_multiple_of(20) {
+            dump.newline();
+        }
+        dump.value(x1[i]);
+    }
+    dump.end("x1");
+    dump.begin("x2");
+    for i in 0..n {
+        if i.is_multiple_of(20) {
+            dump.newline();
+        }
+        dump.value(x2[i]);
+    }
+    dump.end("x2");
+}
+
+unsafe fn kernel_mvt(
     n: usize,
-    x1: &mut Array1D<DataType, N>,
-    x2: &mut Array1D<DataType, N>,
-    y_1: &Array1D<DataType, N>,
-    y_2: &Array1D<DataType, N>,
-    A: &Array2D<DataType, N, N>,
+    x1: &mut impl Array1<DataType>,
+    x2: &mut impl Array1<DataType>,
+    y_1: &impl Array1<DataType>,
+    y_2: &impl Array1<DataType>,
+    A: &impl Array2<DataType>,
 ) {
     for i in 0..n {
         for j in 0..n {
-            x1[i] = x1[i] + A[i][j] * y_1[j];
+            x1[i] += A[i][j] * y_1[j];
         }
     }
     for i in 0..n {
         for j in 0..n {
-            x2[i] = x2[i] + A[j][i] * y_2[j];
+            x2[i] += A[j][i] * y_2[j];
         }
     }
 }
 
-pub fn bench<const N: usize>(timing_function: &dyn Fn() -> u64) -> Duration {
+pub fn bench<const N: usize>(timer: &dyn Timer) -> Duration {
+    bench_in::<N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench`], but allocates the arrays with `alloc` instead of a [`PageAllocator`].
+pub fn bench_in<const N: usize, Alloc: Allocator>(timer: &dyn Timer, alloc: Alloc) -> Duration {
+    try_bench_in::<N, _>(timer, alloc).unwrap_or_else(|e| e.handle())
+}
+
+/// Like [`bench`], but returns an error instead of aborting if the arrays cannot be allocated.
+pub fn try_bench<const N: usize>(timer: &dyn Timer) -> Result<Duration, AllocError> {
+    try_bench_in::<N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench_in`], but returns an error instead of aborting if the arrays cannot be
+/// allocated.
+pub fn try_bench_in<const N: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Result<Duration, AllocError> {
     let n = N;
 
-    let mut A = Array2D::<DataType, N, N>::maybe_uninit();
-    let mut x1 = Array1D::<DataType, N>::maybe_uninit();
-    let mut x2 = Array1D::<DataType, N>::maybe_uninit();
-    let mut y_1 = Array1D::<DataType, N>::maybe_uninit();
-    let mut y_2 = Array1D::<DataType, N>::maybe_uninit();
+    let mut A = Array2D::<DataType, N, N>::try_maybe_uninit_in(&alloc)?;
+    let mut x1 = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
+    let mut x2 = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
+    let mut y_1 = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
+    let mut y_2 = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
+
+    unsafe {
+        let A = A.assume_init_mut();
+        let x1 = x1.assume_init_mut();
+        let x2 = x2.assume_init_mut();
+        let y_1 = y_1.assume_init_mut();
+        let y_2 = y_2.assume_init_mut();
+        init_array(n, x1, x2, y_1, y_2, A);
+
+        let elapsed = util::benchmark_with_timer(
+            &[
+                x1.extent(),
+                x2.extent(),
+                y_1.extent(),
+                y_2.extent(),
+                A.extent(),
+            ],
+            || kernel_mvt(n, x1, x2, y_1, y_2, A),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, x1, x2, &mut crate::dump::Results::start());
+        util::consume(x1);
+        util::consume(x2);
+        Ok(elapsed)
+    }
+}
+
+/// Runs the same benchmark as [`bench`] with dimensions that are only known at runtime, given in
+/// the order of its const generics.
+pub fn bench_dyn(dims: [usize; 1], timer: &dyn Timer) -> Duration {
+    let [n] = dims;
 
     unsafe {
+        let mut A = DynArray2D::<DataType>::uninit(n, n);
+        let mut x1 = DynArray1D::<DataType>::uninit(n);
+        let mut x2 = DynArray1D::<DataType>::uninit(n);
+        let mut y_1 = DynArray1D::<DataType>::uninit(n);
+        let mut y_2 = DynArray1D::<DataType>::uninit(n);
         init_array(n, &mut x1, &mut x2, &mut y_1, &mut y_2, &mut A);
 
-        let mut x1 = x1.assume_init();
-        let mut x2 = x2.assume_init();
-        let y_1 = y_1.assume_init();
-        let y_2 = y_2.assume_init();
-        let A = A.assume_init();
+        let elapsed = util::benchmark_with_timer(
+            &[
+                x1.extent(),
+                x2.extent(),
+                y_1.extent(),
+                y_2.extent(),
+                A.extent(),
+            ],
+            || kernel_mvt(n, &mut x1, &mut x2, &y_1, &y_2, &A),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, &x1, &x2, &mut crate::dump::Results::start());
+        util::consume(x1);
+        util::consume(x2);
+        elapsed
+    }
+}
+
+/// Like [`bench_dyn`], but stores the arrays with two or three dimensions in the layout `L`.
+pub fn bench_layout<L: Layout>(dims: [usize; 1], timer: &dyn Timer) -> Duration {
+    let [n] = dims;
+
+    unsafe {
+        let mut A = LayoutArray2D::<DataType, L>::uninit(n, n);
+        let mut x1 = DynArray1D::<DataType>::uninit(n);
+        let mut x2 = DynArray1D::<DataType>::uninit(n);
+        let mut y_1 = DynArray1D::<DataType>::uninit(n);
+        let mut y_2 = DynArray1D::<DataType>::uninit(n);
+        init_array(n, &mut x1, &mut x2, &mut y_1, &mut y_2, &mut A);
 
-        let elapsed = util::benchmark_with_timing_function(
+        let elapsed = util::benchmark_with_timer(
+            &[
+                x1.extent(),
+                x2.extent(),
+                y_1.extent(),
+                y_2.extent(),
+                A.extent(),
+            ],
             || kernel_mvt(n, &mut x1, &mut x2, &y_1, &y_2, &A),
-            timing_function,
+            timer,
         );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, &x1, &x2, &mut crate::dump::Results::start());
         util::consume(x1);
         util::consume(x2);
         elapsed
     }
 }
 
-#[test]
-fn check() {}
+/// Floating-point operations of one run of [`bench`] with `dims`: 4·N².
+pub fn flops(dims: [usize; 1]) -> u64 {
+    let [n] = dims.map(|d| d as u64);
+    4 * n * n
+}
+
+/// Bytes of the arrays [`bench_dyn`] allocates with `dims`: N² + 4·N elements.
+pub fn footprint(dims: [usize; 1]) -> u64 {
+    let [n] = dims;
+    crate::ndarray::footprint::<DataType>(&[&[n, n], &[n], &[n], &[n], &[n]])
+}
+
+crate::dataset::datasets! {
+    Mini: [40],
+    Small: [120],
+    Medium: [400],
+    Large: [2000],
+    ExtraLarge: [4000],
+}
+
+#[cfg(test)]
+crate::dataset::checksums! {
+    Mini: [("x1", 369.75, 7846.599999999999), ("x2", 369.5, 7845.8499999999985)],
+    Small: [
+        ("x1", 3426.2500000000005, 208708.81111111116),
+        ("x2", 3424.4999999999973, 208813.22777777773),
+    ],
+}
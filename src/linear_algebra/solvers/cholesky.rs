@@ -1,14 +1,24 @@
 #![allow(non_snake_case)]
 
-use crate::config::linear_algebra::solvers::cholesky::DataType;
-use crate::ndarray::{Array2D, ArrayAlloc};
+pub use crate::config::linear_algebra::solvers::cholesky::DataType;
+#[cfg(any(test, feature = "print-result"))]
+use crate::dump::Dump;
+use crate::layout::{Layout, LayoutArray2D};
+use crate::ndarray::{
+    AllocError, Array2, Array2D, ArrayAlloc, Contiguous, DynArray2D, PositiveSemiDefinite,
+};
+use crate::pages::PageAllocator;
+use crate::timer::Timer;
 use crate::util;
-use core::mem::MaybeUninit;
+use alloc::alloc::Global;
+use core::alloc::Allocator;
 use core::time::Duration;
 
-unsafe fn init_array<const N: usize>(n: usize, A: &mut MaybeUninit<Array2D<DataType, N, N>>) {
-    let A = unsafe { A.assume_init_mut() };
-
+unsafe fn init_array(
+    n: usize,
+    A: &mut (impl Array2<DataType> + PositiveSemiDefinite),
+    alloc: impl Allocator,
+) -> Result<(), AllocError> {
     for i in 0..n {
         for j in 0..=i {
             A[i][j] = (-(j as isize) % n as isize) as DataType / n as DataType + 1.0;
@@ -19,10 +29,24 @@ unsafe fn init_array<const N: usize>(n: usize, A: &mut MaybeUninit<Array2D<DataT
         A[i][i] = 1.0;
     }
 
-    A.make_positive_semi_definite();
+    A.try_make_positive_semi_definite_in(alloc)
+}
+
+#[cfg(any(test, feature = "print-result"))]
+fn print_array(n: usize, A: &impl Array2<DataType>, dump: &mut impl Dump) {
+    dump.begin("A");
+    for i in 0..n {
+        for j in 0..=i {
+            if (i * n + j).is_multiple_of(20) {
+                dump.newline();
+            }
+            dump.value(A[i][j]);
+        }
+    }
+    dump.end("A");
 }
 
-unsafe fn kernel_cholesky<const N: usize>(n: usize, A: &mut Array2D<DataType, N, N>) {
+unsafe fn kernel_cholesky(n: usize, A: &mut impl Array2<DataType>) {
     for i in 0..n {
         for j in 0..i {
             for k in 0..j {
@@ -37,21 +61,102 @@ unsafe fn kernel_cholesky<const N: usize>(n: usize, A: &mut Array2D<DataType, N,
     }
 }
 
-pub fn bench<const N: usize>(timing_function: &dyn Fn() -> u64) -> Duration {
+pub fn bench<const N: usize>(timer: &dyn Timer) -> Duration {
+    bench_in::<N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench`], but allocates the arrays with `alloc` instead of a [`PageAllocator`].
+pub fn bench_in<const N: usize, Alloc: Allocator>(timer: &dyn Timer, alloc: Alloc) -> Duration {
+    try_bench_in::<N, _>(timer, alloc).unwrap_or_else(|e| e.handle())
+}
+
+/// Like [`bench`], but returns an error instead of aborting if the arrays cannot be allocated.
+pub fn try_bench<const N: usize>(timer: &dyn Timer) -> Result<Duration, AllocError> {
+    try_bench_in::<N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench_in`], but returns an error instead of aborting if the arrays cannot be
+/// allocated.
+pub fn try_bench_in<const N: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Result<Duration, AllocError> {
     let n = N;
 
-    let mut A = Array2D::<DataType, N, N>::maybe_uninit();
+    let mut A = Array2D::<DataType, N, N>::try_maybe_uninit_in(&alloc)?;
 
     unsafe {
-        init_array(n, &mut A);
-        let mut A = A.assume_init();
+        let A = A.assume_init_mut();
+        init_array(n, A, &alloc)?;
+
+        let elapsed = util::benchmark_with_timer(&[A.extent()], || kernel_cholesky(n, A), timer);
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, A, &mut crate::dump::Results::start());
+        util::consume(A);
+        Ok(elapsed)
+    }
+}
+
+/// Runs the same benchmark as [`bench`] with dimensions that are only known at runtime, given in
+/// the order of its const generics.
+pub fn bench_dyn(dims: [usize; 1], timer: &dyn Timer) -> Duration {
+    let [n] = dims;
+
+    unsafe {
+        let mut A = DynArray2D::<DataType>::uninit(n, n);
+        init_array(n, &mut A, Global).unwrap_or_else(|e| e.handle());
+
+        let elapsed =
+            util::benchmark_with_timer(&[A.extent()], || kernel_cholesky(n, &mut A), timer);
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, &A, &mut crate::dump::Results::start());
+        util::consume(A);
+        elapsed
+    }
+}
+
+/// Like [`bench_dyn`], but stores the arrays with two or three dimensions in the layout `L`.
+pub fn bench_layout<L: Layout>(dims: [usize; 1], timer: &dyn Timer) -> Duration {
+    let [n] = dims;
+
+    unsafe {
+        let mut A = LayoutArray2D::<DataType, L>::uninit(n, n);
+        init_array(n, &mut A, Global).unwrap_or_else(|e| e.handle());
 
         let elapsed =
-            util::benchmark_with_timing_function(|| kernel_cholesky(n, &mut A), timing_function);
+            util::benchmark_with_timer(&[A.extent()], || kernel_cholesky(n, &mut A), timer);
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, &A, &mut crate::dump::Results::start());
         util::consume(A);
         elapsed
     }
 }
 
-#[test]
-fn check() {}
+/// Floating-point operations of one run of [`bench`] with `dims`: N·(N + 1)·(2·N + 1) / 6, the same
+/// as LAPACK's `potrf`.
+pub fn flops(dims: [usize; 1]) -> u64 {
+    let [n] = dims.map(|d| d as u64);
+    n * (n + 1) * (2 * n + 1) / 6
+}
+
+/// Bytes of the arrays [`bench_dyn`] allocates with `dims`: N² elements, and the N² of the
+/// scratch array that makes `A` positive semi-definite in `init_array`, which is freed before the
+/// kernel runs but counts towards the peak.
+pub fn footprint(dims: [usize; 1]) -> u64 {
+    let [n] = dims;
+    crate::ndarray::footprint::<DataType>(&[&[n, n], &[n, n]])
+}
+
+crate::dataset::datasets! {
+    Mini: [40],
+    Small: [120],
+    Medium: [400],
+    Large: [2000],
+    ExtraLarge: [4000],
+}
+
+#[cfg(test)]
+crate::dataset::checksums! {
+    Mini: [("A", 573.0000000000001, 211541.55000000025)],
+    Small: [("A", 4919.666666666664, 16063824.883333359)],
+}
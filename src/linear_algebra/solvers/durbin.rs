@@ -1,29 +1,42 @@
-use crate::config::linear_algebra::solvers::durbin::DataType;
-use crate::ndarray::{Array1D, ArrayAlloc};
+pub use crate::config::linear_algebra::solvers::durbin::DataType;
+#[cfg(any(test, feature = "print-result"))]
+use crate::dump::Dump;
+use crate::ndarray::{AllocError, Array1, Array1D, ArrayAlloc, Contiguous, DynArray1D};
+use crate::pages::PageAllocator;
+use crate::timer::Timer;
 use crate::util;
-use core::mem::MaybeUninit;
+use core::alloc::Allocator;
 use core::time::Duration;
 
-unsafe fn init_array<const N: usize>(n: usize, r: &mut MaybeUninit<Array1D<DataType, N>>) {
-    let r = unsafe { r.assume_init_mut() };
-
+unsafe fn init_array(n: usize, r: &mut impl Array1<DataType>) {
     for i in 0..n {
         r[i] = (n + 1 - i) as DataType;
     }
 }
 
-unsafe fn kernel_durbin<const N: usize>(
+#[cfg(any(test, feature = "print-result"))]
+fn print_array(n: usize, y: &impl Array1<DataType>, dump: &mut impl Dump) {
+    dump.begin("y");
+    for i in 0..n {
+        if i.is_multiple_of(20) {
+            dump.newline();
+        }
+        dump.value(y[i]);
+    }
+    dump.end("y");
+}
+
+unsafe fn kernel_durbin(
     n: usize,
-    r: &Array1D<DataType, N>,
-    y: &mut Array1D<DataType, N>,
+    r: &impl Array1<DataType>,
+    y: &mut impl Array1<DataType>,
+    z: &mut impl Array1<DataType>,
 ) {
-    let mut z: [DataType; N] = MaybeUninit::uninit().assume_init();
-
     y[0] = -r[0];
     let mut beta = 1.0;
     let mut alpha = -r[0];
     for k in 1..n {
-        beta = (1.0 - alpha * alpha) * beta;
+        beta *= 1.0 - alpha * alpha;
         let mut sum = 0.0;
         for i in 0..k {
             sum += r[k - i - 1] * y[i];
@@ -40,23 +53,95 @@ unsafe fn kernel_durbin<const N: usize>(
     }
 }
 
-pub fn bench<const N: usize>(timing_function: &dyn Fn() -> u64) -> Duration {
+pub fn bench<const N: usize>(timer: &dyn Timer) -> Duration {
+    bench_in::<N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench`], but allocates the arrays with `alloc` instead of a [`PageAllocator`].
+pub fn bench_in<const N: usize, Alloc: Allocator>(timer: &dyn Timer, alloc: Alloc) -> Duration {
+    try_bench_in::<N, _>(timer, alloc).unwrap_or_else(|e| e.handle())
+}
+
+/// Like [`bench`], but returns an error instead of aborting if the arrays cannot be allocated.
+pub fn try_bench<const N: usize>(timer: &dyn Timer) -> Result<Duration, AllocError> {
+    try_bench_in::<N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench_in`], but returns an error instead of aborting if the arrays cannot be
+/// allocated.
+pub fn try_bench_in<const N: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Result<Duration, AllocError> {
     let n = N;
 
-    let mut r = Array1D::<DataType, N>::maybe_uninit();
-    let y = Array1D::<DataType, N>::maybe_uninit();
+    let mut r = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
+    let mut y = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
+    let mut z = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
 
     unsafe {
+        let r = r.assume_init_mut();
+        let y = y.assume_init_mut();
+        let z = z.assume_init_mut();
+        init_array(n, r);
+
+        let elapsed = util::benchmark_with_timer(
+            &[r.extent(), y.extent(), z.extent()],
+            || kernel_durbin(n, r, y, z),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, y, &mut crate::dump::Results::start());
+        util::consume(y);
+        Ok(elapsed)
+    }
+}
+
+/// Runs the same benchmark as [`bench`] with dimensions that are only known at runtime, given in
+/// the order of its const generics.
+pub fn bench_dyn(dims: [usize; 1], timer: &dyn Timer) -> Duration {
+    let [n] = dims;
+
+    unsafe {
+        let mut r = DynArray1D::<DataType>::uninit(n);
+        let mut y = DynArray1D::<DataType>::uninit(n);
+        let mut z = DynArray1D::<DataType>::uninit(n);
         init_array(n, &mut r);
-        let r = r.assume_init();
-        let mut y = y.assume_init();
 
-        let elapsed =
-            util::benchmark_with_timing_function(|| kernel_durbin(n, &r, &mut y), timing_function);
+        let elapsed = util::benchmark_with_timer(
+            &[r.extent(), y.extent(), z.extent()],
+            || kernel_durbin(n, &r, &mut y, &mut z),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, &y, &mut crate::dump::Results::start());
         util::consume(y);
         elapsed
     }
 }
 
-#[test]
-fn check() {}
+/// Floating-point operations of one run of [`bench`] with `dims`: (N - 1)·(2·N + 5).
+pub fn flops(dims: [usize; 1]) -> u64 {
+    let [n] = dims.map(|d| d as u64);
+    n.saturating_sub(1) * (2 * n + 5)
+}
+
+/// Bytes of the arrays [`bench_dyn`] allocates with `dims`: 3·N elements.
+pub fn footprint(dims: [usize; 1]) -> u64 {
+    let [n] = dims;
+    crate::ndarray::footprint::<DataType>(&[&[n], &[n], &[n]])
+}
+
+crate::dataset::datasets! {
+    Mini: [40],
+    Small: [120],
+    Medium: [400],
+    Large: [2000],
+    ExtraLarge: [4000],
+}
+
+#[cfg(test)]
+crate::dataset::checksums! {
+    Mini: [("y", -1.0398412758009044, -16.613994553905933)],
+    Small: [("y", -1.0090643901512604, -58.29564291414937)],
+}
@@ -1,22 +1,23 @@
 #![allow(non_snake_case)]
 
-use crate::config::linear_algebra::solvers::gramschmidt::DataType;
-use crate::ndarray::{Array2D, ArrayAlloc};
+pub use crate::config::linear_algebra::solvers::gramschmidt::DataType;
+#[cfg(any(test, feature = "print-result"))]
+use crate::dump::Dump;
+use crate::layout::{Layout, LayoutArray2D};
+use crate::ndarray::{AllocError, Array2, Array2D, ArrayAlloc, Contiguous, DynArray2D};
+use crate::pages::PageAllocator;
+use crate::timer::Timer;
 use crate::util;
-use core::mem::MaybeUninit;
+use core::alloc::Allocator;
 use core::time::Duration;
 
-unsafe fn init_array<const M: usize, const N: usize>(
+unsafe fn init_array(
     m: usize,
     n: usize,
-    A: &mut MaybeUninit<Array2D<DataType, M, N>>,
-    R: &mut MaybeUninit<Array2D<DataType, N, N>>,
-    Q: &mut MaybeUninit<Array2D<DataType, M, N>>,
+    A: &mut impl Array2<DataType>,
+    R: &mut impl Array2<DataType>,
+    Q: &mut impl Array2<DataType>,
 ) {
-    let A = unsafe { A.assume_init_mut() };
-    let R = unsafe { R.assume_init_mut() };
-    let Q = unsafe { Q.assume_init_mut() };
-
     for i in 0..m {
         for j in 0..n {
             A[i][j] = ((((i * j) % m) as DataType / m as DataType) * 100.0) + 10.0;
@@ -30,12 +31,42 @@ unsafe fn init_array<const M: usize, const N: usize>(
     }
 }
 
-unsafe fn kernel_gramschmidt<const M: usize, const N: usize>(
+#[cfg(any(test, feature = "print-result"))]
+fn print_array(
+    m: usize,
+    n: usize,
+    R: &impl Array2<DataType>,
+    Q: &impl Array2<DataType>,
+    dump: &mut impl Dump,
+) {
+    dump.begin("R");
+    for i in 0..n {
+        for j in 0..n {
+            if (i * n + j).is_multiple_of(20) {
+                dump.newline();
+            }
+            dump.value(R[i][j]);
+        }
+    }
+    dump.end("R");
+    dump.begin("Q");
+    for i in 0..m {
+        for j in 0..n {
+            if (i * n + j).is_multiple_of(20) {
+                dump.newline();
+            }
+            dump.value(Q[i][j]);
+        }
+    }
+    dump.end("Q");
+}
+
+unsafe fn kernel_gramschmidt(
     m: usize,
     n: usize,
-    A: &mut Array2D<DataType, M, N>,
-    R: &mut Array2D<DataType, N, N>,
-    Q: &mut Array2D<DataType, M, N>,
+    A: &mut impl Array2<DataType>,
+    R: &mut impl Array2<DataType>,
+    Q: &mut impl Array2<DataType>,
 ) {
     for k in 0..n {
         let mut nrm = 0.0;
@@ -52,30 +83,106 @@ unsafe fn kernel_gramschmidt<const M: usize, const N: usize>(
                 R[k][j] += Q[i][k] * A[i][j];
             }
             for i in 0..m {
-                A[i][j] = A[i][j] - Q[i][k] * R[k][j];
+                A[i][j] -= Q[i][k] * R[k][j];
             }
         }
     }
 }
 
-pub fn bench<const M: usize, const N: usize>(timing_function: &dyn Fn() -> u64) -> Duration {
+pub fn bench<const M: usize, const N: usize>(timer: &dyn Timer) -> Duration {
+    bench_in::<M, N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench`], but allocates the arrays with `alloc` instead of a [`PageAllocator`].
+pub fn bench_in<const M: usize, const N: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Duration {
+    try_bench_in::<M, N, _>(timer, alloc).unwrap_or_else(|e| e.handle())
+}
+
+/// Like [`bench`], but returns an error instead of aborting if the arrays cannot be allocated.
+pub fn try_bench<const M: usize, const N: usize>(
+    timer: &dyn Timer,
+) -> Result<Duration, AllocError> {
+    try_bench_in::<M, N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench_in`], but returns an error instead of aborting if the arrays cannot be
+/// allocated.
+pub fn try_bench_in<const M: usize, const N: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Result<Duration, AllocError> {
     let m = M;
     let n = N;
 
-    let mut A = Array2D::<DataType, M, N>::maybe_uninit();
-    let mut R = Array2D::<DataType, N, N>::maybe_uninit();
-    let mut Q = Array2D::<DataType, M, N>::maybe_uninit();
+    let mut A = Array2D::<DataType, M, N>::try_maybe_uninit_in(&alloc)?;
+    let mut R = Array2D::<DataType, N, N>::try_maybe_uninit_in(&alloc)?;
+    let mut Q = Array2D::<DataType, M, N>::try_maybe_uninit_in(&alloc)?;
+
+    unsafe {
+        let A = A.assume_init_mut();
+        let R = R.assume_init_mut();
+        let Q = Q.assume_init_mut();
+        init_array(m, n, A, R, Q);
+
+        let elapsed = util::benchmark_with_timer(
+            &[A.extent(), R.extent(), Q.extent()],
+            || kernel_gramschmidt(m, n, A, R, Q),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(m, n, R, Q, &mut crate::dump::Results::start());
+        util::consume(A);
+        util::consume(R);
+        util::consume(Q);
+        Ok(elapsed)
+    }
+}
+
+/// Runs the same benchmark as [`bench`] with dimensions that are only known at runtime, given in
+/// the order of its const generics.
+pub fn bench_dyn(dims: [usize; 2], timer: &dyn Timer) -> Duration {
+    let [m, n] = dims;
+
+    unsafe {
+        let mut A = DynArray2D::<DataType>::uninit(m, n);
+        let mut R = DynArray2D::<DataType>::uninit(n, n);
+        let mut Q = DynArray2D::<DataType>::uninit(m, n);
+        init_array(m, n, &mut A, &mut R, &mut Q);
+
+        let elapsed = util::benchmark_with_timer(
+            &[A.extent(), R.extent(), Q.extent()],
+            || kernel_gramschmidt(m, n, &mut A, &mut R, &mut Q),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(m, n, &R, &Q, &mut crate::dump::Results::start());
+        util::consume(A);
+        util::consume(R);
+        util::consume(Q);
+        elapsed
+    }
+}
+
+/// Like [`bench_dyn`], but stores the arrays with two or three dimensions in the layout `L`.
+pub fn bench_layout<L: Layout>(dims: [usize; 2], timer: &dyn Timer) -> Duration {
+    let [m, n] = dims;
 
     unsafe {
+        let mut A = LayoutArray2D::<DataType, L>::uninit(m, n);
+        let mut R = LayoutArray2D::<DataType, L>::uninit(n, n);
+        let mut Q = LayoutArray2D::<DataType, L>::uninit(m, n);
         init_array(m, n, &mut A, &mut R, &mut Q);
-        let mut A = A.assume_init();
-        let mut R = R.assume_init();
-        let mut Q = Q.assume_init();
 
-        let elapsed = util::benchmark_with_timing_function(
+        let elapsed = util::benchmark_with_timer(
+            &[A.extent(), R.extent(), Q.extent()],
             || kernel_gramschmidt(m, n, &mut A, &mut R, &mut Q),
-            timing_function,
+            timer,
         );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(m, n, &R, &Q, &mut crate::dump::Results::start());
         util::consume(A);
         util::consume(R);
         util::consume(Q);
@@ -83,5 +190,34 @@ pub fn bench<const M: usize, const N: usize>(timing_function: &dyn Fn() -> u64)
     }
 }
 
-#[test]
-fn check() {}
+/// Floating-point operations of one run of [`bench`] with `dims`: 2·M·N² + M·N + N.
+pub fn flops(dims: [usize; 2]) -> u64 {
+    let [m, n] = dims.map(|d| d as u64);
+    2 * m * n * n + m * n + n
+}
+
+/// Bytes of the arrays [`bench_dyn`] allocates with `dims`: 2·M·N + N² elements.
+pub fn footprint(dims: [usize; 2]) -> u64 {
+    let [m, n] = dims;
+    crate::ndarray::footprint::<DataType>(&[&[m, n], &[n, n], &[m, n]])
+}
+
+crate::dataset::datasets! {
+    Mini: [20, 30],
+    Small: [60, 80],
+    Medium: [200, 240],
+    Large: [1000, 1200],
+    ExtraLarge: [2000, 2600],
+}
+
+#[cfg(test)]
+crate::dataset::checksums! {
+    Mini: [
+        ("R", 11729.462218533072, 1050864.4811437041),
+        ("Q", 10.39010062572471, 4418.657714548869),
+    ],
+    Small: [
+        ("R", 59948.23847600215, 33944680.530469514),
+        ("Q", -0.5201454712078638, -290.2608547035212),
+    ],
+}
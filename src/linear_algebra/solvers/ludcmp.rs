@@ -1,23 +1,28 @@
 #![allow(non_snake_case)]
 
-use crate::config::linear_algebra::solvers::ludcmp::DataType;
-use crate::ndarray::{Array1D, Array2D, ArrayAlloc};
+pub use crate::config::linear_algebra::solvers::ludcmp::DataType;
+#[cfg(any(test, feature = "print-result"))]
+use crate::dump::Dump;
+use crate::layout::{Layout, LayoutArray2D};
+use crate::ndarray::{
+    AllocError, Array1, Array1D, Array2, Array2D, ArrayAlloc, Contiguous, DynArray1D, DynArray2D,
+    PositiveSemiDefinite,
+};
+use crate::pages::PageAllocator;
+use crate::timer::Timer;
 use crate::util;
-use core::mem::MaybeUninit;
+use alloc::alloc::Global;
+use core::alloc::Allocator;
 use core::time::Duration;
 
-unsafe fn init_array<const N: usize>(
+unsafe fn init_array(
     n: usize,
-    A: &mut MaybeUninit<Array2D<DataType, N, N>>,
-    b: &mut MaybeUninit<Array1D<DataType, N>>,
-    x: &mut MaybeUninit<Array1D<DataType, N>>,
-    y: &mut MaybeUninit<Array1D<DataType, N>>,
-) {
-    let A = unsafe { A.assume_init_mut() };
-    let b = unsafe { b.assume_init_mut() };
-    let x = unsafe { x.assume_init_mut() };
-    let y = unsafe { y.assume_init_mut() };
-
+    A: &mut (impl Array2<DataType> + PositiveSemiDefinite),
+    b: &mut impl Array1<DataType>,
+    x: &mut impl Array1<DataType>,
+    y: &mut impl Array1<DataType>,
+    alloc: impl Allocator,
+) -> Result<(), AllocError> {
     let float_n = n as DataType;
 
     for i in 0..n {
@@ -36,15 +41,27 @@ unsafe fn init_array<const N: usize>(
         A[i][i] = 1.0;
     }
 
-    A.make_positive_semi_definite();
+    A.try_make_positive_semi_definite_in(alloc)
+}
+
+#[cfg(any(test, feature = "print-result"))]
+fn print_array(n: usize, x: &impl Array1<DataType>, dump: &mut impl Dump) {
+    dump.begin("x");
+    for i in 0..n {
+        if i.is_multiple_of(20) {
+            dump.newline();
+        }
+        dump.value(x[i]);
+    }
+    dump.end("x");
 }
 
-unsafe fn kernel_ludcmp<const N: usize>(
+unsafe fn kernel_ludcmp(
     n: usize,
-    A: &mut Array2D<DataType, N, N>,
-    b: &Array1D<DataType, N>,
-    x: &mut Array1D<DataType, N>,
-    y: &mut Array1D<DataType, N>,
+    A: &mut impl Array2<DataType>,
+    b: &impl Array1<DataType>,
+    x: &mut impl Array1<DataType>,
+    y: &mut impl Array1<DataType>,
 ) {
     let mut w;
     for i in 0..n {
@@ -81,29 +98,124 @@ unsafe fn kernel_ludcmp<const N: usize>(
     }
 }
 
-pub fn bench<const N: usize>(timing_function: &dyn Fn() -> u64) -> Duration {
+pub fn bench<const N: usize>(timer: &dyn Timer) -> Duration {
+    bench_in::<N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench`], but allocates the arrays with `alloc` instead of a [`PageAllocator`].
+pub fn bench_in<const N: usize, Alloc: Allocator>(timer: &dyn Timer, alloc: Alloc) -> Duration {
+    try_bench_in::<N, _>(timer, alloc).unwrap_or_else(|e| e.handle())
+}
+
+/// Like [`bench`], but returns an error instead of aborting if the arrays cannot be allocated.
+pub fn try_bench<const N: usize>(timer: &dyn Timer) -> Result<Duration, AllocError> {
+    try_bench_in::<N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench_in`], but returns an error instead of aborting if the arrays cannot be
+/// allocated.
+pub fn try_bench_in<const N: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Result<Duration, AllocError> {
     let n = N;
 
-    let mut A = Array2D::<DataType, N, N>::maybe_uninit();
-    let mut b = Array1D::<DataType, N>::maybe_uninit();
-    let mut x = Array1D::<DataType, N>::maybe_uninit();
-    let mut y = Array1D::<DataType, N>::maybe_uninit();
+    let mut A = Array2D::<DataType, N, N>::try_maybe_uninit_in(&alloc)?;
+    let mut b = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
+    let mut x = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
+    let mut y = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
+
+    unsafe {
+        let A = A.assume_init_mut();
+        let b = b.assume_init_mut();
+        let x = x.assume_init_mut();
+        let y = y.assume_init_mut();
+        init_array(n, A, b, x, y, &alloc)?;
+
+        let elapsed = util::benchmark_with_timer(
+            &[A.extent(), b.extent(), x.extent(), y.extent()],
+            || kernel_ludcmp(n, A, b, x, y),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, x, &mut crate::dump::Results::start());
+        util::consume(x);
+        Ok(elapsed)
+    }
+}
+
+/// Runs the same benchmark as [`bench`] with dimensions that are only known at runtime, given in
+/// the order of its const generics.
+pub fn bench_dyn(dims: [usize; 1], timer: &dyn Timer) -> Duration {
+    let [n] = dims;
 
     unsafe {
-        init_array(n, &mut A, &mut b, &mut x, &mut y);
-        let b = b.assume_init();
-        let mut A = A.assume_init();
-        let mut x = x.assume_init();
-        let mut y = y.assume_init();
+        let mut A = DynArray2D::<DataType>::uninit(n, n);
+        let mut b = DynArray1D::<DataType>::uninit(n);
+        let mut x = DynArray1D::<DataType>::uninit(n);
+        let mut y = DynArray1D::<DataType>::uninit(n);
+        init_array(n, &mut A, &mut b, &mut x, &mut y, Global).unwrap_or_else(|e| e.handle());
 
-        let elapsed = util::benchmark_with_timing_function(
+        let elapsed = util::benchmark_with_timer(
+            &[A.extent(), b.extent(), x.extent(), y.extent()],
             || kernel_ludcmp(n, &mut A, &b, &mut x, &mut y),
-            timing_function,
+            timer,
         );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, &x, &mut crate::dump::Results::start());
         util::consume(x);
         elapsed
     }
 }
 
-#[test]
-fn check() {}
+/// Like [`bench_dyn`], but stores the arrays with two or three dimensions in the layout `L`.
+pub fn bench_layout<L: Layout>(dims: [usize; 1], timer: &dyn Timer) -> Duration {
+    let [n] = dims;
+
+    unsafe {
+        let mut A = LayoutArray2D::<DataType, L>::uninit(n, n);
+        let mut b = DynArray1D::<DataType>::uninit(n);
+        let mut x = DynArray1D::<DataType>::uninit(n);
+        let mut y = DynArray1D::<DataType>::uninit(n);
+        init_array(n, &mut A, &mut b, &mut x, &mut y, Global).unwrap_or_else(|e| e.handle());
+
+        let elapsed = util::benchmark_with_timer(
+            &[A.extent(), b.extent(), x.extent(), y.extent()],
+            || kernel_ludcmp(n, &mut A, &b, &mut x, &mut y),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, &x, &mut crate::dump::Results::start());
+        util::consume(x);
+        elapsed
+    }
+}
+
+/// Floating-point operations of one run of [`bench`] with `dims`: N·(N - 1)·(4·N + 1) / 6 for the
+/// decomposition and 2·N·(N - 1) + N for the two substitutions.
+pub fn flops(dims: [usize; 1]) -> u64 {
+    let [n] = dims.map(|d| d as u64);
+    n * n.saturating_sub(1) * (4 * n + 1) / 6 + 2 * n * n.saturating_sub(1) + n
+}
+
+/// Bytes of the arrays [`bench_dyn`] allocates with `dims`: N² + 3·N elements, and the N² of the
+/// scratch array that makes `A` positive semi-definite in `init_array`, which is freed before the
+/// kernel runs but counts towards the peak.
+pub fn footprint(dims: [usize; 1]) -> u64 {
+    let [n] = dims;
+    crate::ndarray::footprint::<DataType>(&[&[n, n], &[n], &[n], &[n], &[n, n]])
+}
+
+crate::dataset::datasets! {
+    Mini: [40],
+    Small: [120],
+    Medium: [400],
+    Large: [2000],
+    ExtraLarge: [4000],
+}
+
+#[cfg(test)]
+crate::dataset::checksums! {
+    Mini: [("x", 4.012499999999999, 7.935858368083045)],
+    Small: [("x", 4.00416666666667, 11.521492762084488)],
+}
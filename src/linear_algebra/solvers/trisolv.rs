@@ -1,21 +1,24 @@
 #![allow(non_snake_case)]
 
-use crate::config::linear_algebra::solvers::trisolv::DataType;
-use crate::ndarray::{Array1D, Array2D, ArrayAlloc};
+pub use crate::config::linear_algebra::solvers::trisolv::DataType;
+#[cfg(any(test, feature = "print-result"))]
+use crate::dump::Dump;
+use crate::layout::{Layout, LayoutArray2D};
+use crate::ndarray::{
+    AllocError, Array1, Array1D, Array2, Array2D, ArrayAlloc, Contiguous, DynArray1D, DynArray2D,
+};
+use crate::pages::PageAllocator;
+use crate::timer::Timer;
 use crate::util;
-use core::mem::MaybeUninit;
+use core::alloc::Allocator;
 use core::time::Duration;
 
-unsafe fn init_array<const N: usize>(
+unsafe fn init_array(
     n: usize,
-    L: &mut MaybeUninit<Array2D<DataType, N, N>>,
-    x: &mut MaybeUninit<Array1D<DataType, N>>,
-    b: &mut MaybeUninit<Array1D<DataType, N>>,
+    L: &mut impl Array2<DataType>,
+    x: &mut impl Array1<DataType>,
+    b: &mut impl Array1<DataType>,
 ) {
-    let L = unsafe { L.assume_init_mut() };
-    let x = unsafe { x.assume_init_mut() };
-    let b = unsafe { b.assume_init_mut() };
-
     for i in 0..n {
         x[i] = -999.0;
         b[i] = i as DataType;
@@ -25,42 +28,144 @@ unsafe fn init_array<const N: usize>(
     }
 }
 
-unsafe fn kernel_trisolv<const N: usize>(
+#[cfg(any(test, feature = "print-result"))]
+fn print_array(n: usize, x: &impl Array1<DataType>, dump: &mut impl Dump) {
+    dump.begin("x");
+    for i in 0..n {
+        if i.is_multiple_of(20) {
+            dump.newline();
+        }
+        dump.value(x[i]);
+    }
+    dump.end("x");
+}
+
+unsafe fn kernel_trisolv(
     n: usize,
-    L: &Array2D<DataType, N, N>,
-    x: &mut Array1D<DataType, N>,
-    b: &Array1D<DataType, N>,
+    L: &impl Array2<DataType>,
+    x: &mut impl Array1<DataType>,
+    b: &impl Array1<DataType>,
 ) {
     for i in 0..n {
         x[i] = b[i];
         for j in 0..i {
             x[i] -= L[i][j] * x[j];
         }
-        x[i] = x[i] / L[i][i];
+        x[i] /= L[i][i];
     }
 }
 
-pub fn bench<const N: usize>(timing_function: &dyn Fn() -> u64) -> Duration {
+pub fn bench<const N: usize>(timer: &dyn Timer) -> Duration {
+    bench_in::<N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench`], but allocates the arrays with `alloc` instead of a [`PageAllocator`].
+pub fn bench_in<const N: usize, Alloc: Allocator>(timer: &dyn Timer, alloc: Alloc) -> Duration {
+    try_bench_in::<N, _>(timer, alloc).unwrap_or_else(|e| e.handle())
+}
+
+/// Like [`bench`], but returns an error instead of aborting if the arrays cannot be allocated.
+pub fn try_bench<const N: usize>(timer: &dyn Timer) -> Result<Duration, AllocError> {
+    try_bench_in::<N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench_in`], but returns an error instead of aborting if the arrays cannot be
+/// allocated.
+pub fn try_bench_in<const N: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Result<Duration, AllocError> {
     let n = N;
 
-    let mut L = Array2D::<DataType, N, N>::maybe_uninit();
-    let mut x = Array1D::<DataType, N>::maybe_uninit();
-    let mut b = Array1D::<DataType, N>::maybe_uninit();
+    let mut L = Array2D::<DataType, N, N>::try_maybe_uninit_in(&alloc)?;
+    let mut x = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
+    let mut b = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
+
+    unsafe {
+        let L = L.assume_init_mut();
+        let x = x.assume_init_mut();
+        let b = b.assume_init_mut();
+        init_array(n, L, x, b);
+
+        let elapsed = util::benchmark_with_timer(
+            &[L.extent(), x.extent(), b.extent()],
+            || kernel_trisolv(n, L, x, b),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, x, &mut crate::dump::Results::start());
+        util::consume(x);
+        Ok(elapsed)
+    }
+}
+
+/// Runs the same benchmark as [`bench`] with dimensions that are only known at runtime, given in
+/// the order of its const generics.
+pub fn bench_dyn(dims: [usize; 1], timer: &dyn Timer) -> Duration {
+    let [n] = dims;
 
     unsafe {
+        let mut L = DynArray2D::<DataType>::uninit(n, n);
+        let mut x = DynArray1D::<DataType>::uninit(n);
+        let mut b = DynArray1D::<DataType>::uninit(n);
         init_array(n, &mut L, &mut x, &mut b);
-        let L = L.assume_init();
-        let b = b.assume_init();
-        let mut x = x.assume_init();
 
-        let elapsed = util::benchmark_with_timing_function(
+        let elapsed = util::benchmark_with_timer(
+            &[L.extent(), x.extent(), b.extent()],
             || kernel_trisolv(n, &L, &mut x, &b),
-            timing_function,
+            timer,
         );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, &x, &mut crate::dump::Results::start());
         util::consume(x);
         elapsed
     }
 }
 
-#[test]
-fn check() {}
+/// Like [`bench_dyn`], but stores the arrays with two or three dimensions in the layout `L`.
+pub fn bench_layout<L: Layout>(dims: [usize; 1], timer: &dyn Timer) -> Duration {
+    let [n] = dims;
+
+    unsafe {
+        let mut L = LayoutArray2D::<DataType, L>::uninit(n, n);
+        let mut x = DynArray1D::<DataType>::uninit(n);
+        let mut b = DynArray1D::<DataType>::uninit(n);
+        init_array(n, &mut L, &mut x, &mut b);
+
+        let elapsed = util::benchmark_with_timer(
+            &[L.extent(), x.extent(), b.extent()],
+            || kernel_trisolv(n, &L, &mut x, &b),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, &x, &mut crate::dump::Results::start());
+        util::consume(x);
+        elapsed
+    }
+}
+
+/// Floating-point operations of one run of [`bench`] with `dims`: N².
+pub fn flops(dims: [usize; 1]) -> u64 {
+    let [n] = dims.map(|d| d as u64);
+    n * n
+}
+
+/// Bytes of the arrays [`bench_dyn`] allocates with `dims`: N² + 2·N elements.
+pub fn footprint(dims: [usize; 1]) -> u64 {
+    let [n] = dims;
+    crate::ndarray::footprint::<DataType>(&[&[n, n], &[n], &[n]])
+}
+
+crate::dataset::datasets! {
+    Mini: [40],
+    Small: [120],
+    Medium: [400],
+    Large: [2000],
+    ExtraLarge: [4000],
+}
+
+#[cfg(test)]
+crate::dataset::checksums! {
+    Mini: [("x", 12.365172214194995, 221.5789493497946)],
+    Small: [("x", 37.65087819886746, 1933.861645927054)],
+}
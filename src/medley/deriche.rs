@@ -1,17 +1,15 @@
-use crate::config::medley::deriche::DataType;
-use crate::ndarray::{Array2D, ArrayAlloc};
+pub use crate::config::medley::deriche::DataType;
+#[cfg(any(test, feature = "print-result"))]
+use crate::dump::Dump;
+use crate::layout::{Layout, LayoutArray2D};
+use crate::ndarray::{AllocError, Array2, Array2D, ArrayAlloc, Contiguous, DynArray2D};
+use crate::pages::PageAllocator;
+use crate::timer::Timer;
 use crate::util;
-use core::mem::MaybeUninit;
+use core::alloc::Allocator;
 use core::time::Duration;
 
-unsafe fn init_array<const H: usize, const W: usize>(
-    w: usize,
-    h: usize,
-    alpha: &mut DataType,
-    img_in: &mut MaybeUninit<Array2D<DataType, W, H>>,
-) {
-    let img_in = unsafe { img_in.assume_init_mut() };
-
+unsafe fn init_array(w: usize, h: usize, alpha: &mut DataType, img_in: &mut impl Array2<DataType>) {
     *alpha = 0.25;
     for i in 0..w {
         for j in 0..h {
@@ -20,14 +18,28 @@ unsafe fn init_array<const H: usize, const W: usize>(
     }
 }
 
-unsafe fn kernel_deriche<const H: usize, const W: usize>(
+#[cfg(any(test, feature = "print-result"))]
+fn print_array(w: usize, h: usize, img_out: &impl Array2<DataType>, dump: &mut impl Dump) {
+    dump.begin("imgOut");
+    for i in 0..w {
+        for j in 0..h {
+            if (i * h + j).is_multiple_of(20) {
+                dump.newline();
+            }
+            dump.value(img_out[i][j]);
+        }
+    }
+    dump.end("imgOut");
+}
+
+unsafe fn kernel_deriche(
     w: usize,
     h: usize,
     alpha: DataType,
-    img_in: &Array2D<DataType, W, H>,
-    img_out: &mut Array2D<DataType, W, H>,
-    y1: &mut Array2D<DataType, W, H>,
-    y2: &mut Array2D<DataType, W, H>,
+    img_in: &impl Array2<DataType>,
+    img_out: &mut impl Array2<DataType>,
+    y1: &mut impl Array2<DataType>,
+    y2: &mut impl Array2<DataType>,
 ) {
     let mut xm1;
     let mut tm1;
@@ -120,31 +132,133 @@ unsafe fn kernel_deriche<const H: usize, const W: usize>(
     }
 }
 
-pub fn bench<const H: usize, const W: usize>(timing_function: &dyn Fn() -> u64) -> Duration {
+pub fn bench<const H: usize, const W: usize>(timer: &dyn Timer) -> Duration {
+    bench_in::<H, W, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench`], but allocates the arrays with `alloc` instead of a [`PageAllocator`].
+pub fn bench_in<const H: usize, const W: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Duration {
+    try_bench_in::<H, W, _>(timer, alloc).unwrap_or_else(|e| e.handle())
+}
+
+/// Like [`bench`], but returns an error instead of aborting if the arrays cannot be allocated.
+pub fn try_bench<const H: usize, const W: usize>(
+    timer: &dyn Timer,
+) -> Result<Duration, AllocError> {
+    try_bench_in::<H, W, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench_in`], but returns an error instead of aborting if the arrays cannot be
+/// allocated.
+pub fn try_bench_in<const H: usize, const W: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Result<Duration, AllocError> {
     let w = W;
     let h = H;
 
     let mut alpha = 0.0;
-    let mut img_in = Array2D::<DataType, W, H>::maybe_uninit();
-    let img_out = Array2D::<DataType, W, H>::maybe_uninit();
-    let y1 = Array2D::<DataType, W, H>::maybe_uninit();
-    let y2 = Array2D::<DataType, W, H>::maybe_uninit();
+    let mut img_in = Array2D::<DataType, W, H>::try_maybe_uninit_in(&alloc)?;
+    let mut img_out = Array2D::<DataType, W, H>::try_maybe_uninit_in(&alloc)?;
+    let mut y1 = Array2D::<DataType, W, H>::try_maybe_uninit_in(&alloc)?;
+    let mut y2 = Array2D::<DataType, W, H>::try_maybe_uninit_in(&alloc)?;
+
+    unsafe {
+        let img_in = img_in.assume_init_mut();
+        let img_out = img_out.assume_init_mut();
+        let y1 = y1.assume_init_mut();
+        let y2 = y2.assume_init_mut();
+        init_array(w, h, &mut alpha, img_in);
+
+        let elapsed = util::benchmark_with_timer(
+            &[img_in.extent(), img_out.extent(), y1.extent(), y2.extent()],
+            || kernel_deriche(w, h, alpha, img_in, img_out, y1, y2),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(w, h, img_out, &mut crate::dump::Results::start());
+        util::consume(img_out);
+        Ok(elapsed)
+    }
+}
+
+/// Runs the same benchmark as [`bench`] with dimensions that are only known at runtime, given in
+/// the order of its const generics.
+pub fn bench_dyn(dims: [usize; 2], timer: &dyn Timer) -> Duration {
+    let [h, w] = dims;
+
+    let mut alpha = 0.0;
 
     unsafe {
+        let mut img_in = DynArray2D::<DataType>::uninit(w, h);
+        let mut img_out = DynArray2D::<DataType>::uninit(w, h);
+        let mut y1 = DynArray2D::<DataType>::uninit(w, h);
+        let mut y2 = DynArray2D::<DataType>::uninit(w, h);
         init_array(w, h, &mut alpha, &mut img_in);
-        let img_in = img_in.assume_init();
-        let mut img_out = img_out.assume_init();
-        let mut y1 = y1.assume_init();
-        let mut y2 = y2.assume_init();
 
-        let elapsed = util::benchmark_with_timing_function(
+        let elapsed = util::benchmark_with_timer(
+            &[img_in.extent(), img_out.extent(), y1.extent(), y2.extent()],
             || kernel_deriche(w, h, alpha, &img_in, &mut img_out, &mut y1, &mut y2),
-            timing_function,
+            timer,
         );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(w, h, &img_out, &mut crate::dump::Results::start());
         util::consume(img_out);
         elapsed
     }
 }
 
-#[test]
-fn check() {}
+/// Like [`bench_dyn`], but stores the arrays with two or three dimensions in the layout `L`.
+pub fn bench_layout<L: Layout>(dims: [usize; 2], timer: &dyn Timer) -> Duration {
+    let [h, w] = dims;
+
+    let mut alpha = 0.0;
+
+    unsafe {
+        let mut img_in = LayoutArray2D::<DataType, L>::uninit(w, h);
+        let mut img_out = LayoutArray2D::<DataType, L>::uninit(w, h);
+        let mut y1 = LayoutArray2D::<DataType, L>::uninit(w, h);
+        let mut y2 = LayoutArray2D::<DataType, L>::uninit(w, h);
+        init_array(w, h, &mut alpha, &mut img_in);
+
+        let elapsed = util::benchmark_with_timer(
+            &[img_in.extent(), img_out.extent(), y1.extent(), y2.extent()],
+            || kernel_deriche(w, h, alpha, &img_in, &mut img_out, &mut y1, &mut y2),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(w, h, &img_out, &mut crate::dump::Results::start());
+        util::consume(img_out);
+        elapsed
+    }
+}
+
+/// Floating-point operations of one run of [`bench`] with `dims`: 32·W·H, i.e. four recursive
+/// filters with 7 operations and two sums with 2 operations per pixel.
+pub fn flops(dims: [usize; 2]) -> u64 {
+    let [h, w] = dims.map(|d| d as u64);
+    32 * w * h
+}
+
+/// Bytes of the arrays [`bench_dyn`] allocates with `dims`: 4·W·H elements.
+pub fn footprint(dims: [usize; 2]) -> u64 {
+    let [h, w] = dims;
+    crate::ndarray::footprint::<DataType>(&[&[w, h], &[w, h], &[w, h], &[w, h]])
+}
+
+crate::dataset::datasets! {
+    Mini: [64, 64],
+    Small: [128, 192],
+    Medium: [480, 720],
+    Large: [2160, 4096],
+    ExtraLarge: [4320, 7680],
+}
+
+#[cfg(test)]
+crate::dataset::checksums! {
+    Mini: [("imgOut", 80.4248349439149, 163877.626624275)],
+    Small: [("imgOut", 467.1604945785075, 5699191.900714705)],
+}
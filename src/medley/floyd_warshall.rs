@@ -1,12 +1,15 @@
-use crate::config::medley::floyd_warshall::DataType;
-use crate::ndarray::{Array2D, ArrayAlloc};
+pub use crate::config::medley::floyd_warshall::DataType;
+#[cfg(any(test, feature = "print-result"))]
+use crate::dump::Dump;
+use crate::layout::{Layout, LayoutArray2D};
+use crate::ndarray::{AllocError, Array2, Array2D, ArrayAlloc, Contiguous, DynArray2D};
+use crate::pages::PageAllocator;
+use crate::timer::Timer;
 use crate::util;
-use core::mem::MaybeUninit;
+use core::alloc::Allocator;
 use core::time::Duration;
 
-unsafe fn init_array<const N: usize>(n: usize, path: &mut MaybeUninit<Array2D<DataType, N, N>>) {
-    let path = unsafe { path.assume_init_mut() };
-
+unsafe fn init_array(n: usize, path: &mut impl Array2<DataType>) {
     for i in 0..n {
         for j in 0..n {
             path[i][j] = (i * j % 7 + 1) as DataType;
@@ -17,7 +20,21 @@ unsafe fn init_array<const N: usize>(n: usize, path: &mut MaybeUninit<Array2D<Da
     }
 }
 
-unsafe fn kernel_floyd_warshall<const N: usize>(n: usize, path: &mut Array2D<DataType, N, N>) {
+#[cfg(any(test, feature = "print-result"))]
+fn print_array(n: usize, path: &impl Array2<DataType>, dump: &mut impl Dump) {
+    dump.begin("path");
+    for i in 0..n {
+        for j in 0..n {
+            if (i * n + j).is_multiple_of(20) {
+                dump.newline();
+            }
+            dump.value(path[i][j]);
+        }
+    }
+    dump.end("path");
+}
+
+unsafe fn kernel_floyd_warshall(n: usize, path: &mut impl Array2<DataType>) {
     for k in 0..n {
         for i in 0..n {
             for j in 0..n {
@@ -31,23 +48,107 @@ unsafe fn kernel_floyd_warshall<const N: usize>(n: usize, path: &mut Array2D<Dat
     }
 }
 
-pub fn bench<const N: usize>(timing_function: &dyn Fn() -> u64) -> Duration {
+pub fn bench<const N: usize>(timer: &dyn Timer) -> Duration {
+    bench_in::<N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench`], but allocates the arrays with `alloc` instead of a [`PageAllocator`].
+pub fn bench_in<const N: usize, Alloc: Allocator>(timer: &dyn Timer, alloc: Alloc) -> Duration {
+    try_bench_in::<N, _>(timer, alloc).unwrap_or_else(|e| e.handle())
+}
+
+/// Like [`bench`], but returns an error instead of aborting if the arrays cannot be allocated.
+pub fn try_bench<const N: usize>(timer: &dyn Timer) -> Result<Duration, AllocError> {
+    try_bench_in::<N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench_in`], but returns an error instead of aborting if the arrays cannot be
+/// allocated.
+pub fn try_bench_in<const N: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Result<Duration, AllocError> {
     let n = N;
 
-    let mut path = Array2D::<DataType, N, N>::maybe_uninit();
+    let mut path = Array2D::<DataType, N, N>::try_maybe_uninit_in(&alloc)?;
+
+    unsafe {
+        let path = path.assume_init_mut();
+        init_array(n, path);
+
+        let elapsed =
+            util::benchmark_with_timer(&[path.extent()], || kernel_floyd_warshall(n, path), timer);
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, path, &mut crate::dump::Results::start());
+        util::consume(path);
+        Ok(elapsed)
+    }
+}
+
+/// Runs the same benchmark as [`bench`] with dimensions that are only known at runtime, given in
+/// the order of its const generics.
+pub fn bench_dyn(dims: [usize; 1], timer: &dyn Timer) -> Duration {
+    let [n] = dims;
+
+    unsafe {
+        let mut path = DynArray2D::<DataType>::uninit(n, n);
+        init_array(n, &mut path);
+
+        let elapsed = util::benchmark_with_timer(
+            &[path.extent()],
+            || kernel_floyd_warshall(n, &mut path),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, &path, &mut crate::dump::Results::start());
+        util::consume(path);
+        elapsed
+    }
+}
+
+/// Like [`bench_dyn`], but stores the arrays with two or three dimensions in the layout `L`.
+pub fn bench_layout<L: Layout>(dims: [usize; 1], timer: &dyn Timer) -> Duration {
+    let [n] = dims;
 
     unsafe {
+        let mut path = LayoutArray2D::<DataType, L>::uninit(n, n);
         init_array(n, &mut path);
-        let mut path = path.assume_init();
-        
-        let elapsed = util::benchmark_with_timing_function(
+
+        let elapsed = util::benchmark_with_timer(
+            &[path.extent()],
             || kernel_floyd_warshall(n, &mut path),
-            timing_function,
+            timer,
         );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, &path, &mut crate::dump::Results::start());
         util::consume(path);
         elapsed
     }
 }
 
-#[test]
-fn check() {}
+/// Integer operations of one run of [`bench`] with `dims`: 2·N³, an addition and a comparison per
+/// step.
+pub fn ops(dims: [usize; 1]) -> u64 {
+    let [n] = dims.map(|d| d as u64);
+    2 * n * n * n
+}
+
+/// Bytes of the arrays [`bench_dyn`] allocates with `dims`: N² elements.
+pub fn footprint(dims: [usize; 1]) -> u64 {
+    let [n] = dims;
+    crate::ndarray::footprint::<DataType>(&[&[n, n]])
+}
+
+crate::dataset::datasets! {
+    Mini: [60],
+    Small: [180],
+    Medium: [500],
+    Large: [2800],
+    ExtraLarge: [5600],
+}
+
+#[cfg(test)]
+crate::dataset::checksums! {
+    Mini: [("path", 6594.0, 11868532.0)],
+    Small: [("path", 59360.0, 962320882.0)],
+}
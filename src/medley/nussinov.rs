@@ -1,19 +1,19 @@
-use crate::config::medley::nussinov::DataType;
-use crate::ndarray::{Array1D, Array2D, ArrayAlloc};
+pub use crate::config::medley::nussinov::DataType;
+#[cfg(any(test, feature = "print-result"))]
+use crate::dump::Dump;
+use crate::layout::{Layout, LayoutArray2D};
+use crate::ndarray::{
+    AllocError, Array1, Array1D, Array2, Array2D, ArrayAlloc, Contiguous, DynArray1D, DynArray2D,
+};
+use crate::pages::PageAllocator;
+use crate::timer::Timer;
 use crate::util;
-use core::mem::MaybeUninit;
+use core::alloc::Allocator;
 use core::time::Duration;
 
 type Base = i8;
 
-unsafe fn init_array<const N: usize>(
-    n: usize,
-    seq: &mut MaybeUninit<Array1D<Base, N>>,
-    table: &mut MaybeUninit<Array2D<DataType, N, N>>,
-) {
-    let seq = unsafe { seq.assume_init_mut() };
-    let table = unsafe { table.assume_init_mut() };
-
+unsafe fn init_array(n: usize, seq: &mut impl Array1<Base>, table: &mut impl Array2<DataType>) {
     for i in 0..n {
         seq[i] = ((i + 1) % 4) as Base;
     }
@@ -25,11 +25,23 @@ unsafe fn init_array<const N: usize>(
     }
 }
 
-unsafe fn kernel_nussinov<const N: usize>(
-    n: usize,
-    seq: &Array1D<Base, N>,
-    table: &mut Array2D<DataType, N, N>,
-) {
+#[cfg(any(test, feature = "print-result"))]
+fn print_array(n: usize, table: &impl Array2<DataType>, dump: &mut impl Dump) {
+    let mut t = 0usize;
+    dump.begin("table");
+    for i in 0..n {
+        for j in i..n {
+            if t.is_multiple_of(20) {
+                dump.newline();
+            }
+            dump.value(table[i][j]);
+            t += 1;
+        }
+    }
+    dump.end("table");
+}
+
+unsafe fn kernel_nussinov(n: usize, seq: &impl Array1<Base>, table: &mut impl Array2<DataType>) {
     let match_base = |b1, b2| {
         if b1 + b2 == 3 {
             1
@@ -47,7 +59,7 @@ unsafe fn kernel_nussinov<const N: usize>(
 
     for i in (0..n).rev() {
         for j in (i as isize + 1)..(n as isize) {
-            if j - 1 >= 0 {
+            if j > 0 {
                 let j = j as usize;
                 table[i][j] = max_score(table[i][j], table[i][j - 1]);
             }
@@ -56,7 +68,7 @@ unsafe fn kernel_nussinov<const N: usize>(
                 table[i][j] = max_score(table[i][j], table[i + 1][j]);
             }
 
-            if j - 1 >= 0 && i + 1 < n {
+            if j > 0 && i + 1 < n {
                 let j = j as usize;
                 if i < j - 1 {
                     table[i][j] = max_score(
@@ -76,25 +88,115 @@ unsafe fn kernel_nussinov<const N: usize>(
     }
 }
 
-pub fn bench<const N: usize>(timing_function: &dyn Fn() -> u64) -> Duration {
+pub fn bench<const N: usize>(timer: &dyn Timer) -> Duration {
+    bench_in::<N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench`], but allocates the arrays with `alloc` instead of a [`PageAllocator`].
+pub fn bench_in<const N: usize, Alloc: Allocator>(timer: &dyn Timer, alloc: Alloc) -> Duration {
+    try_bench_in::<N, _>(timer, alloc).unwrap_or_else(|e| e.handle())
+}
+
+/// Like [`bench`], but returns an error instead of aborting if the arrays cannot be allocated.
+pub fn try_bench<const N: usize>(timer: &dyn Timer) -> Result<Duration, AllocError> {
+    try_bench_in::<N, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench_in`], but returns an error instead of aborting if the arrays cannot be
+/// allocated.
+pub fn try_bench_in<const N: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Result<Duration, AllocError> {
     let n = N;
 
-    let mut seq = Array1D::maybe_uninit();
-    let mut table = Array2D::<DataType, N, N>::maybe_uninit();
+    let mut seq = Array1D::<Base, N>::try_maybe_uninit_in(&alloc)?;
+    let mut table = Array2D::<DataType, N, N>::try_maybe_uninit_in(&alloc)?;
 
     unsafe {
+        let seq = seq.assume_init_mut();
+        let table = table.assume_init_mut();
+        init_array(n, seq, table);
+
+        let elapsed = util::benchmark_with_timer(
+            &[seq.extent(), table.extent()],
+            || kernel_nussinov(n, seq, table),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, table, &mut crate::dump::Results::start());
+        util::consume(table);
+        Ok(elapsed)
+    }
+}
+
+/// Runs the same benchmark as [`bench`] with dimensions that are only known at runtime, given in
+/// the order of its const generics.
+pub fn bench_dyn(dims: [usize; 1], timer: &dyn Timer) -> Duration {
+    let [n] = dims;
+
+    unsafe {
+        let mut seq = DynArray1D::<Base>::uninit(n);
+        let mut table = DynArray2D::<DataType>::uninit(n, n);
         init_array(n, &mut seq, &mut table);
-        let seq = seq.assume_init();
-        let mut table = table.assume_init();
 
-        let elapsed = util::benchmark_with_timing_function(
+        let elapsed = util::benchmark_with_timer(
+            &[seq.extent(), table.extent()],
             || kernel_nussinov(n, &seq, &mut table),
-            timing_function,
+            timer,
         );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, &table, &mut crate::dump::Results::start());
         util::consume(table);
         elapsed
     }
 }
 
-#[test]
-fn check() {}
+/// Like [`bench_dyn`], but stores the arrays with two or three dimensions in the layout `L`.
+pub fn bench_layout<L: Layout>(dims: [usize; 1], timer: &dyn Timer) -> Duration {
+    let [n] = dims;
+
+    unsafe {
+        let mut seq = DynArray1D::<Base>::uninit(n);
+        let mut table = LayoutArray2D::<DataType, L>::uninit(n, n);
+        init_array(n, &mut seq, &mut table);
+
+        let elapsed = util::benchmark_with_timer(
+            &[seq.extent(), table.extent()],
+            || kernel_nussinov(n, &seq, &mut table),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, &table, &mut crate::dump::Results::start());
+        util::consume(table);
+        elapsed
+    }
+}
+
+/// Integer operations of one run of [`bench`] with `dims`: (N - 1)·(N² + 4·N - 3) / 3 additions and
+/// comparisons.
+pub fn ops(dims: [usize; 1]) -> u64 {
+    let [n] = dims.map(|d| d as u64);
+    (n - 1) * (n * n + 4 * n - 3) / 3
+}
+
+/// Bytes of the arrays [`bench_dyn`] allocates with `dims`: N elements of `Base` and N² elements of
+/// `DataType`.
+pub fn footprint(dims: [usize; 1]) -> u64 {
+    let [n] = dims;
+    crate::ndarray::footprint::<Base>(&[&[n]]) + crate::ndarray::footprint::<DataType>(&[&[n, n]])
+}
+
+crate::dataset::datasets! {
+    Mini: [60],
+    Small: [180],
+    Medium: [500],
+    Large: [2500],
+    ExtraLarge: [5500],
+}
+
+#[cfg(test)]
+crate::dataset::checksums! {
+    Mini: [("table", 16254.0, 11670498.0)],
+    Small: [("table", 469964.0, 3041608196.0)],
+}
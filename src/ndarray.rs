@@ -1,20 +1,76 @@
-use alloc::alloc::{alloc, alloc_zeroed};
+use crate::pages::PageAllocator;
+use crate::util::{self, Prefault};
+use alloc::alloc::{alloc, alloc_zeroed, handle_alloc_error, Global};
 use alloc::boxed::Box;
-use core::alloc::Layout;
+use core::alloc::{Allocator, Layout};
 use core::fmt;
-use core::mem::MaybeUninit;
+use core::mem::{self, MaybeUninit};
 use core::ops::{self, Index, IndexMut};
+use core::ptr::NonNull;
+use core::slice;
+use core::sync::atomic::{AtomicUsize, Ordering};
 
+macro_rules! alignments {
+    ($($name:ident = $bytes:literal),*) => {$(
+        #[doc = concat!("Aligns a fixed-size array to ", stringify!($bytes), " bytes.")]
+        #[repr(align($bytes))]
+        #[derive(Clone, Copy, Debug)]
+        pub struct $name;
+    )*};
+}
+
+// 32 bytes suit AVX loads, 64 bytes align the arrays to cache lines and 4096 bytes to pages.
+alignments!(Align32 = 32, Align64 = 64, Align4096 = 4096);
+
+/// `M` elements aligned to `A`, one of [`Align32`], [`Align64`] and [`Align4096`].
 #[repr(C, align(32))]
-pub struct Array1D<T, const M: usize>(pub [T; M]);
+pub struct Array1D<T, const M: usize, A = Align32>(pub [T; M], [A; 0]);
 
+/// `M` rows of `N` elements aligned to `A`. Every row is stored with `LD >= N` elements, its
+/// leading dimension, so that padding can break up the cache-set conflicts of power-of-two sizes
+/// like PolyBench/C's `POLYBENCH_PADDING_FACTOR`. Kernels only index the first `N` of them.
 #[repr(C, align(32))]
-pub struct Array2D<T, const M: usize, const N: usize>(pub [Array1D<T, N>; M]);
+pub struct Array2D<T, const M: usize, const N: usize, A = Align32, const LD: usize = N>(
+    pub [Array1D<T, LD>; M],
+    [A; 0],
+);
 
+/// `M` planes of `N` rows of `P` elements aligned to `A`, with every row stored with `LD >= P`
+/// elements like in [`Array2D`].
 #[repr(C, align(32))]
-pub struct Array3D<T, const M: usize, const N: usize, const P: usize>(pub [Array2D<T, N, P>; M]);
+pub struct Array3D<
+    T,
+    const M: usize,
+    const N: usize,
+    const P: usize,
+    A = Align32,
+    const LD: usize = P,
+>(pub [Array2D<T, N, P, Align32, LD>; M], [A; 0]);
+
+// The alignment is a private field, so the arrays are built with `new` instead of their tuple
+// constructors.
+
+impl<T, const M: usize, A> Array1D<T, M, A> {
+    pub const fn new(elements: [T; M]) -> Self {
+        Array1D(elements, [])
+    }
+}
+
+impl<T, const M: usize, const N: usize, A, const LD: usize> Array2D<T, M, N, A, LD> {
+    pub const fn new(rows: [Array1D<T, LD>; M]) -> Self {
+        Array2D(rows, [])
+    }
+}
+
+impl<T, const M: usize, const N: usize, const P: usize, A, const LD: usize>
+    Array3D<T, M, N, P, A, LD>
+{
+    pub const fn new(planes: [Array2D<T, N, P, Align32, LD>; M]) -> Self {
+        Array3D(planes, [])
+    }
+}
 
-impl<T, const M: usize> Index<usize> for Array1D<T, M> {
+impl<T, const M: usize, A> Index<usize> for Array1D<T, M, A> {
     type Output = T;
 
     #[inline(always)]
@@ -24,7 +80,7 @@ impl<T, const M: usize> Index<usize> for Array1D<T, M> {
     }
 }
 
-impl<T, const M: usize> IndexMut<usize> for Array1D<T, M> {
+impl<T, const M: usize, A> IndexMut<usize> for Array1D<T, M, A> {
     #[inline(always)]
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
         debug_assert!(index < M);
@@ -32,8 +88,10 @@ impl<T, const M: usize> IndexMut<usize> for Array1D<T, M> {
     }
 }
 
-impl<T, const M: usize, const N: usize> Index<usize> for Array2D<T, M, N> {
-    type Output = Array1D<T, N>;
+impl<T, const M: usize, const N: usize, A, const LD: usize> Index<usize>
+    for Array2D<T, M, N, A, LD>
+{
+    type Output = Array1D<T, LD>;
 
     #[inline(always)]
     fn index(&self, index: usize) -> &Self::Output {
@@ -42,7 +100,9 @@ impl<T, const M: usize, const N: usize> Index<usize> for Array2D<T, M, N> {
     }
 }
 
-impl<T, const M: usize, const N: usize> IndexMut<usize> for Array2D<T, M, N> {
+impl<T, const M: usize, const N: usize, A, const LD: usize> IndexMut<usize>
+    for Array2D<T, M, N, A, LD>
+{
     #[inline(always)]
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
         debug_assert!(index < M);
@@ -50,8 +110,10 @@ impl<T, const M: usize, const N: usize> IndexMut<usize> for Array2D<T, M, N> {
     }
 }
 
-impl<T, const M: usize, const N: usize, const P: usize> Index<usize> for Array3D<T, M, N, P> {
-    type Output = Array2D<T, N, P>;
+impl<T, const M: usize, const N: usize, const P: usize, A, const LD: usize> Index<usize>
+    for Array3D<T, M, N, P, A, LD>
+{
+    type Output = Array2D<T, N, P, Align32, LD>;
 
     #[inline(always)]
     fn index(&self, index: usize) -> &Self::Output {
@@ -60,7 +122,9 @@ impl<T, const M: usize, const N: usize, const P: usize> Index<usize> for Array3D
     }
 }
 
-impl<T, const M: usize, const N: usize, const P: usize> IndexMut<usize> for Array3D<T, M, N, P> {
+impl<T, const M: usize, const N: usize, const P: usize, A, const LD: usize> IndexMut<usize>
+    for Array3D<T, M, N, P, A, LD>
+{
     #[inline(always)]
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
         debug_assert!(index < M);
@@ -68,42 +132,302 @@ impl<T, const M: usize, const N: usize, const P: usize> IndexMut<usize> for Arra
     }
 }
 
-impl<T, const N: usize> Array2D<T, N, N>
-where
-    T: Copy + ops::Mul<Output = T> + ops::AddAssign<T>,
+/// Row-major indexing shared by the fixed-size and the runtime-sized arrays, so that a kernel
+/// can be written once and run with dimensions known at compile time or only at runtime.
+pub trait Array1<T>: Index<usize, Output = T> + IndexMut<usize> {}
+
+impl<T, A> Array1<T> for A where A: Index<usize, Output = T> + IndexMut<usize> + ?Sized {}
+
+/// A two-dimensional array indexed as `a[i][j]`.
+pub trait Array2<T>: Index<usize, Output = <Self as Array2<T>>::Row> + IndexMut<usize> {
+    type Row: Array1<T> + ?Sized;
+}
+
+/// A three-dimensional array indexed as `a[i][j][k]`.
+pub trait Array3<T>: Index<usize, Output = <Self as Array3<T>>::Plane> + IndexMut<usize> {
+    type Plane: Array2<T> + ?Sized;
+}
+
+impl<T, const M: usize, const N: usize, A, const LD: usize> Array2<T> for Array2D<T, M, N, A, LD> {
+    type Row = Array1D<T, LD>;
+}
+
+impl<T, const M: usize, const N: usize, const P: usize, A, const LD: usize> Array3<T>
+    for Array3D<T, M, N, P, A, LD>
 {
-    pub fn make_positive_semi_definite(&mut self) {
-        let mut b = Array2D::<T, N, N>::maybe_uninit_zeroed();
-        let n = N;
-
-        for t in 0..n {
-            for r in 0..n {
-                for s in 0..n {
-                    b[r][s] += self[r][t] * self[s][t];
+    type Plane = Array2D<T, N, P, Align32, LD>;
+}
+
+/// The memory an array occupies, given as a pointer to its first byte and its length in bytes.
+///
+/// [`crate::util::CacheFlush::Clflush`] evicts exactly these bytes from the caches.
+#[derive(Clone, Copy, Debug)]
+pub struct Extent {
+    pub ptr: *const u8,
+    pub len: usize,
+}
+
+/// Arrays that are stored in a single allocation.
+pub trait Contiguous {
+    fn extent(&self) -> Extent;
+}
+
+macro_rules! contiguous_static {
+    ($($array:ident<$($dim:ident),+>),*) => {$(
+        impl<T, $(const $dim: usize,)+ A> Contiguous for $array<T, $($dim,)+ A> {
+            fn extent(&self) -> Extent {
+                Extent {
+                    ptr: self as *const Self as *const u8,
+                    len: core::mem::size_of::<Self>(),
                 }
             }
         }
+    )*};
+}
+
+contiguous_static!(Array1D<M>);
+
+impl<T, const M: usize, const N: usize, A, const LD: usize> Contiguous for Array2D<T, M, N, A, LD> {
+    fn extent(&self) -> Extent {
+        Extent {
+            ptr: self as *const Self as *const u8,
+            len: core::mem::size_of::<Self>(),
+        }
+    }
+}
+
+impl<T, const M: usize, const N: usize, const P: usize, A, const LD: usize> Contiguous
+    for Array3D<T, M, N, P, A, LD>
+{
+    fn extent(&self) -> Extent {
+        Extent {
+            ptr: self as *const Self as *const u8,
+            len: core::mem::size_of::<Self>(),
+        }
+    }
+}
+
+impl<T> Contiguous for DynArray1D<T> {
+    fn extent(&self) -> Extent {
+        Extent {
+            ptr: self.0.as_ptr() as *const u8,
+            len: core::mem::size_of_val(&*self.0),
+        }
+    }
+}
+
+impl<T> Contiguous for DynArray2D<T> {
+    fn extent(&self) -> Extent {
+        Extent {
+            ptr: self.data.as_ptr() as *const u8,
+            len: core::mem::size_of_val(&*self.data),
+        }
+    }
+}
+
+impl<T> Contiguous for DynArray3D<T> {
+    fn extent(&self) -> Extent {
+        Extent {
+            ptr: self.data.as_ptr() as *const u8,
+            len: self.data.len() * core::mem::size_of::<T>(),
+        }
+    }
+}
+
+/// Square arrays that can be overwritten with the product of themselves and their transpose.
+/// The scratch array for the product comes from the global allocator unless the `_in` variant
+/// is given another one.
+pub trait PositiveSemiDefinite {
+    /// Like [`PositiveSemiDefinite::try_make_positive_semi_definite`], but allocates the scratch
+    /// array with `alloc`.
+    fn try_make_positive_semi_definite_in<Alloc: Allocator>(
+        &mut self,
+        alloc: Alloc,
+    ) -> Result<(), AllocError>;
+
+    /// Like [`PositiveSemiDefinite::make_positive_semi_definite`], but returns an error if the
+    /// scratch array cannot be allocated.
+    fn try_make_positive_semi_definite(&mut self) -> Result<(), AllocError> {
+        self.try_make_positive_semi_definite_in(Global)
+    }
+
+    fn make_positive_semi_definite(&mut self) {
+        self.try_make_positive_semi_definite()
+            .unwrap_or_else(|e| e.handle())
+    }
+}
+
+impl<T, const N: usize, A, const LD: usize> PositiveSemiDefinite for Array2D<T, N, N, A, LD>
+where
+    T: Copy + ops::Mul<Output = T> + ops::AddAssign<T>,
+{
+    fn try_make_positive_semi_definite_in<Alloc: Allocator>(
+        &mut self,
+        alloc: Alloc,
+    ) -> Result<(), AllocError> {
+        let mut b = Array2D::<T, N, N, A, LD>::try_zeroed_in(alloc)?;
+        make_positive_semi_definite(N, self, &mut *b);
+        Ok(())
+    }
+}
+
+impl<T> PositiveSemiDefinite for DynArray2D<T>
+where
+    T: Copy + ops::Mul<Output = T> + ops::AddAssign<T>,
+{
+    fn try_make_positive_semi_definite_in<Alloc: Allocator>(
+        &mut self,
+        alloc: Alloc,
+    ) -> Result<(), AllocError> {
+        debug_assert_eq!(self.rows, self.cols);
+        let mut b = Scratch::try_new_in(self.rows, alloc)?;
+        make_positive_semi_definite(self.rows, self, &mut b);
+        Ok(())
+    }
+}
+
+/// A zeroed `n` × `n` array without padding, the scratch array of [`PositiveSemiDefinite`] for
+/// the runtime-sized arrays.
+pub(crate) struct Scratch<T, Alloc: Allocator> {
+    data: Box<[T], Alloc>,
+    n: usize,
+}
+
+impl<T, Alloc: Allocator> Scratch<T, Alloc> {
+    pub(crate) fn try_new_in(n: usize, alloc: Alloc) -> Result<Self, AllocError> {
+        let len = n * n;
+        let data =
+            Box::try_new_zeroed_slice_in(len, alloc).map_err(|_| AllocError::array::<T>(len))?;
+        Ok(Scratch {
+            data: unsafe { data.assume_init() },
+            n,
+        })
+    }
+}
+
+impl<T, Alloc: Allocator> Index<usize> for Scratch<T, Alloc> {
+    type Output = [T];
+
+    #[inline(always)]
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.data[index * self.n..][..self.n]
+    }
+}
+
+impl<T, Alloc: Allocator> IndexMut<usize> for Scratch<T, Alloc> {
+    #[inline(always)]
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.data[index * self.n..][..self.n]
+    }
+}
+
+impl<T, Alloc: Allocator> Array2<T> for Scratch<T, Alloc> {
+    type Row = [T];
+}
+
+/// `b` must be zeroed.
+pub(crate) fn make_positive_semi_definite<T, A, B>(n: usize, a: &mut A, b: &mut B)
+where
+    T: Copy + ops::Mul<Output = T> + ops::AddAssign<T>,
+    A: Array2<T> + ?Sized,
+    B: Array2<T> + ?Sized,
+{
+    for t in 0..n {
         for r in 0..n {
             for s in 0..n {
-                self[r][s] = b[r][s];
+                b[r][s] += a[r][t] * a[s][t];
             }
         }
     }
+    for r in 0..n {
+        for s in 0..n {
+            a[r][s] = b[r][s];
+        }
+    }
+}
+
+/// The allocator could not provide the memory of an array.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AllocError {
+    /// The layout of the allocation that failed.
+    pub layout: Layout,
+}
+
+impl AllocError {
+    fn array<T>(len: usize) -> AllocError {
+        AllocError {
+            layout: Layout::array::<T>(len).unwrap_or_else(|_| Layout::new::<T>()),
+        }
+    }
+
+    /// Aborts like a failed infallible allocation, with [`handle_alloc_error`].
+    pub fn handle(self) -> ! {
+        handle_alloc_error(self.layout)
+    }
+}
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cannot allocate {} bytes aligned to {}",
+            self.layout.size(),
+            self.layout.align()
+        )
+    }
 }
 
 /// All these allocators are controlled by SGX allocators.
 /// If they are managed by `alloc`, they would point to invalid memory and the encalve will crash.
+///
+/// The `_in` variants take the [`Allocator`] to use instead of the global one, e.g. an arena or
+/// the enclave heap.
 pub trait ArrayAlloc: Sized {
-    /// Returns an uninitialized array given the size of `Self`.
-    /// This will use `MaybeUninit` to allocate a free memory with garbage data.
-    /// Do not directly use this area because it is UB.
+    /// Returns an array for `init_array` to fill in, given the size of `Self`.
+    /// Its memory is zeroed and faulted in according to [`util::prefault`], so it is sound to
+    /// `assume_init` it and no page faults are left for the timed kernel.
     fn maybe_uninit() -> Box<MaybeUninit<Self>> {
-        Box::new_uninit()
+        Self::maybe_uninit_in(Global)
+    }
+
+    fn maybe_uninit_in<A: Allocator>(alloc: A) -> Box<MaybeUninit<Self>, A> {
+        Self::try_maybe_uninit_in(alloc).unwrap_or_else(|e| e.handle())
+    }
+
+    /// Like [`ArrayAlloc::maybe_uninit`], but returns an error instead of aborting if the memory
+    /// cannot be allocated, which is likely in an enclave.
+    fn try_maybe_uninit() -> Result<Box<MaybeUninit<Self>>, AllocError> {
+        Self::try_maybe_uninit_in(Global)
+    }
+
+    fn try_maybe_uninit_in<A: Allocator>(
+        alloc: A,
+    ) -> Result<Box<MaybeUninit<Self>, A>, AllocError> {
+        try_prefaulted_in(alloc, util::prefault())
     }
 
     /// Returns an zeroed array after `uninit`. This method ensures that the memory is valid.
     fn maybe_uninit_zeroed() -> Box<Self> {
-        unsafe { Box::new_zeroed().assume_init() }
+        Self::zeroed_in(Global)
+    }
+
+    fn zeroed_in<A: Allocator>(alloc: A) -> Box<Self, A> {
+        Self::try_zeroed_in(alloc).unwrap_or_else(|e| e.handle())
+    }
+
+    /// Like [`ArrayAlloc::maybe_uninit_zeroed`], but returns an error instead of aborting if the
+    /// memory cannot be allocated.
+    fn try_zeroed() -> Result<Box<Self>, AllocError> {
+        Self::try_zeroed_in(Global)
+    }
+
+    fn try_zeroed_in<A: Allocator>(alloc: A) -> Result<Box<Self, A>, AllocError> {
+        match Box::try_new_zeroed_in(alloc) {
+            Ok(array) => Ok(unsafe { array.assume_init() }),
+            Err(_) => Err(AllocError {
+                layout: Layout::new::<Self>(),
+            }),
+        }
     }
 
     #[deprecated]
@@ -111,6 +435,9 @@ pub trait ArrayAlloc: Sized {
         let layout = Layout::new::<Self>();
         unsafe {
             let raw = alloc(layout) as *mut Self;
+            if raw.is_null() {
+                handle_alloc_error(layout);
+            }
             Box::from_raw(raw)
         }
     }
@@ -120,66 +447,502 @@ pub trait ArrayAlloc: Sized {
         let layout = Layout::new::<Self>();
         unsafe {
             let raw = alloc_zeroed(layout) as *mut Self;
+            if raw.is_null() {
+                handle_alloc_error(layout);
+            }
             Box::from_raw(raw)
         }
     }
 }
 
-impl<T, const N: usize> ArrayAlloc for Array1D<T, N> {}
-impl<T, const M: usize, const N: usize> ArrayAlloc for Array2D<T, M, N> {}
-impl<T, const M: usize, const N: usize, const P: usize> ArrayAlloc for Array3D<T, M, N, P> {}
+/// The zeroed memory of [`ArrayAlloc::try_maybe_uninit_in`], faulted in according to `policy`.
+fn try_prefaulted_in<S, A: Allocator>(
+    alloc: A,
+    policy: Prefault,
+) -> Result<Box<MaybeUninit<S>, A>, AllocError> {
+    let mut array = match policy {
+        Prefault::Zero => Box::try_new_uninit_in(alloc),
+        _ => Box::try_new_zeroed_in(alloc),
+    }
+    .map_err(|_| AllocError {
+        layout: Layout::new::<S>(),
+    })?;
+    unsafe { util::prefault_memory(array.as_mut_ptr() as *mut u8, mem::size_of::<S>(), policy) };
+    Ok(array)
+}
+
+impl<T, const N: usize, A> ArrayAlloc for Array1D<T, N, A> {}
+impl<T, const M: usize, const N: usize, A, const LD: usize> ArrayAlloc for Array2D<T, M, N, A, LD> {}
+impl<T, const M: usize, const N: usize, const P: usize, A, const LD: usize> ArrayAlloc
+    for Array3D<T, M, N, P, A, LD>
+{
+}
 
-impl<T, const N: usize> fmt::Display for Array1D<T, N>
+/// Writes `items` as `[a, b, c]`.
+fn write_list<I: IntoIterator>(
+    f: &mut fmt::Formatter<'_>,
+    items: I,
+    mut write: impl FnMut(&mut fmt::Formatter<'_>, I::Item) -> fmt::Result,
+) -> fmt::Result {
+    write!(f, "[")?;
+    for (i, item) in items.into_iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write(f, item)?;
+    }
+    write!(f, "]")
+}
+
+impl<T, const N: usize, A> fmt::Display for Array1D<T, N, A>
 where
     T: fmt::Display,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "[")?;
-        for x in &self.0[..(self.0.len() - 1)] {
-            write!(f, "{}, ", x)?;
-        }
-        if let Some(last) = self.0.last() {
-            write!(f, "{}]", last)?;
-        }
-        Ok(())
+        write_list(f, &self.0, |f, x| write!(f, "{}", x))
     }
 }
 
-impl<T, const M: usize, const N: usize> fmt::Display for Array2D<T, M, N>
+/// Only the first `N` elements of every row are written, not the padding.
+impl<T, const M: usize, const N: usize, A, const LD: usize> fmt::Display for Array2D<T, M, N, A, LD>
 where
     T: fmt::Display,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "[")?;
-        for x in &self.0[..(self.0.len() - 1)] {
-            write!(f, "{}, ", x)?;
-        }
-        if let Some(last) = self.0.last() {
-            write!(f, "{}]", last)?;
-        }
-        Ok(())
+        write_list(f, self.rows(), |f, row| {
+            write_list(f, row, |f, x| write!(f, "{}", x))
+        })
     }
 }
 
-impl<T, const M: usize, const N: usize, const P: usize> fmt::Display for Array3D<T, M, N, P>
+/// Only the first `P` elements of every row are written, not the padding.
+impl<T, const M: usize, const N: usize, const P: usize, A, const LD: usize> fmt::Display
+    for Array3D<T, M, N, P, A, LD>
 where
     T: fmt::Display,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "[")?;
-        for x in &self.0[..(self.0.len() - 1)] {
-            write!(f, "{}, ", x)?;
+        write_list(f, &self.0, |f, plane| write!(f, "{}", plane))
+    }
+}
+
+static DYN_ALIGNMENT: AtomicUsize = AtomicUsize::new(32);
+static DYN_PADDING: AtomicUsize = AtomicUsize::new(0);
+
+/// Sets the alignment in bytes of the runtime-sized arrays that are allocated later. The default
+/// is 32, like [`Align32`].
+///
+/// # Panics
+///
+/// If `bytes` is not a power of two.
+pub fn set_alignment(bytes: usize) {
+    assert!(bytes.is_power_of_two(), "{} is not a power of two", bytes);
+    DYN_ALIGNMENT.store(bytes, Ordering::Relaxed);
+}
+
+/// Returns the alignment set by [`set_alignment`].
+pub fn alignment() -> usize {
+    DYN_ALIGNMENT.load(Ordering::Relaxed)
+}
+
+/// Sets the elements of padding after every row of the runtime-sized arrays with two or three
+/// dimensions that are allocated later, the counterpart of the `LD` of [`Array2D`] and
+/// [`Array3D`]. The default is none.
+pub fn set_padding(elements: usize) {
+    DYN_PADDING.store(elements, Ordering::Relaxed);
+}
+
+/// Returns the padding set by [`set_padding`].
+pub fn padding() -> usize {
+    DYN_PADDING.load(Ordering::Relaxed)
+}
+
+/// Returns the bytes that runtime-sized arrays of `T` with the dimensions in `arrays` take when
+/// they are allocated now: with the [`padding`] of their rows, and each rounded up to the
+/// [`crate::pages::Pages::granularity`] of [`crate::pages::pages`], a whole GiB for
+/// [`crate::pages::Pages::Huge1G`].
+///
+/// The `footprint` of every kernel counts its arrays with this, so that what it reports, and
+/// what the runner checks against the memory budget, is what the allocator hands out rather than
+/// the bare elements the kernel's doc comment lists.
+pub fn footprint<T>(arrays: &[&[usize]]) -> u64 {
+    footprint_with::<T>(
+        arrays,
+        padding(),
+        crate::pages::pages().granularity() as u64,
+    )
+}
+
+fn footprint_with<T>(arrays: &[&[usize]], padding: usize, granularity: u64) -> u64 {
+    arrays
+        .iter()
+        .map(|dims| {
+            let (cols, rows) = dims.split_last().map_or((0, &[][..]), |(&c, r)| (c, r));
+            let ld = match rows.is_empty() {
+                true => cols,
+                false => cols + padding,
+            };
+            let elements = rows.iter().map(|&d| d as u64).product::<u64>() * ld as u64;
+            (elements * mem::size_of::<T>() as u64).next_multiple_of(granularity)
+        })
+        .sum()
+}
+
+/// Raises the alignment of every allocation of a [`PageAllocator`] to `align` bytes.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Aligned {
+    alloc: PageAllocator,
+    align: usize,
+}
+
+impl Aligned {
+    /// Returns the allocator for the pages and the alignment of the runtime-sized arrays.
+    pub(crate) fn current() -> Aligned {
+        Aligned {
+            alloc: PageAllocator::default(),
+            align: alignment(),
         }
-        if let Some(last) = self.0.last() {
-            write!(f, "{}]", last)?;
+    }
+
+    fn layout(&self, layout: Layout) -> Result<Layout, core::alloc::AllocError> {
+        layout
+            .align_to(self.align)
+            .map_err(|_| core::alloc::AllocError)
+    }
+}
+
+unsafe impl Allocator for Aligned {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, core::alloc::AllocError> {
+        self.alloc.allocate(self.layout(layout)?)
+    }
+
+    fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, core::alloc::AllocError> {
+        self.alloc.allocate_zeroed(self.layout(layout)?)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        // The layout was raised the same way when the memory was allocated.
+        unsafe {
+            self.alloc
+                .deallocate(ptr, self.layout(layout).unwrap_or(layout))
         }
-        Ok(())
     }
 }
 
+/// A runtime-sized counterpart of [`Array1D`].
+///
+/// The runtime-sized arrays are allocated with the [`PageAllocator`] for [`crate::pages::pages`]
+/// and aligned to [`alignment`].
+pub struct DynArray1D<T>(Box<[T], Aligned>);
+
+/// A row of a [`DynArray2D`] or a [`DynArray3D`].
+#[repr(transparent)]
+pub struct DynRow<T>([T]);
+
+/// A runtime-sized counterpart of [`Array2D`] with the same row-major layout, whose rows are
+/// stored with `ld` elements.
+pub struct DynArray2D<T> {
+    data: Box<[T], Aligned>,
+    rows: usize,
+    cols: usize,
+    ld: usize,
+}
+
+/// A plane of a [`DynArray3D`].
+pub struct DynPlane<T> {
+    ptr: NonNull<T>,
+    rows: usize,
+    cols: usize,
+    ld: usize,
+}
+
+/// A runtime-sized counterpart of [`Array3D`] with the same row-major layout.
+pub struct DynArray3D<T> {
+    data: NonNull<[T]>,
+    alloc: Aligned,
+    planes: Box<[DynPlane<T>]>,
+}
+
+/// Allocates `len` zeroed elements for a runtime-sized array, faulted in according to
+/// [`util::prefault`].
+pub(crate) fn prefaulted_slice<T>(len: usize) -> Box<[T], Aligned> {
+    prefaulted_slice_with(len, util::prefault())
+}
+
+fn prefaulted_slice_with<T>(len: usize, policy: Prefault) -> Box<[T], Aligned> {
+    let alloc = Aligned::current();
+    let mut data = match policy {
+        Prefault::Zero => Box::new_uninit_slice_in(len, alloc),
+        _ => Box::new_zeroed_slice_in(len, alloc),
+    };
+    unsafe {
+        util::prefault_memory(
+            data.as_mut_ptr() as *mut u8,
+            len * mem::size_of::<T>(),
+            policy,
+        );
+        data.assume_init()
+    }
+}
+
+/// Allocates `len` elements for a runtime-sized array whose bytes are all zero.
+pub(crate) fn zeroed_slice<T>(len: usize) -> Box<[T], Aligned> {
+    unsafe { Box::new_zeroed_slice_in(len, Aligned::current()).assume_init() }
+}
+
+impl<T> DynArray1D<T> {
+    /// Returns an array of `m` elements for `init_array` to fill in, faulted in according to
+    /// [`util::prefault`].
+    ///
+    /// # Safety
+    ///
+    /// Every element must be written before it is read. The elements are zero, but that is not
+    /// part of the contract.
+    pub unsafe fn uninit(m: usize) -> Self {
+        DynArray1D(prefaulted_slice(m))
+    }
+
+    /// Returns an array of `m` elements whose bytes are all zero.
+    pub fn zeroed(m: usize) -> Self {
+        DynArray1D(zeroed_slice(m))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<T> DynRow<T> {
+    fn from_slice(slice: &[T]) -> &Self {
+        // SAFETY: `DynRow<T>` is a transparent wrapper around `[T]`.
+        unsafe { &*(slice as *const [T] as *const Self) }
+    }
+
+    fn from_slice_mut(slice: &mut [T]) -> &mut Self {
+        // SAFETY: `DynRow<T>` is a transparent wrapper around `[T]`.
+        unsafe { &mut *(slice as *mut [T] as *mut Self) }
+    }
+}
+
+impl<T> DynArray2D<T> {
+    /// Returns an array of `m` rows and `n` columns for `init_array` to fill in, faulted in
+    /// according to [`util::prefault`].
+    ///
+    /// # Safety
+    ///
+    /// Every element must be written before it is read. The elements are zero, but that is not
+    /// part of the contract.
+    pub unsafe fn uninit(m: usize, n: usize) -> Self {
+        Self::with_slice(prefaulted_slice, m, n, padding())
+    }
+
+    /// Returns an array of `m` rows and `n` columns whose bytes are all zero.
+    pub fn zeroed(m: usize, n: usize) -> Self {
+        Self::with_slice(zeroed_slice, m, n, padding())
+    }
+
+    fn with_slice(
+        slice: fn(usize) -> Box<[T], Aligned>,
+        m: usize,
+        n: usize,
+        padding: usize,
+    ) -> Self {
+        let ld = n + padding;
+        DynArray2D {
+            data: slice(m * ld),
+            rows: m,
+            cols: n,
+            ld,
+        }
+    }
+
+    /// Returns the dimensions as `(rows, columns)`.
+    pub fn dims(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
+}
+
+impl<T> DynArray3D<T> {
+    /// Returns an array of `m` planes, `n` rows and `p` columns for `init_array` to fill in,
+    /// faulted in according to [`util::prefault`].
+    ///
+    /// # Safety
+    ///
+    /// Every element must be written before it is read. The elements are zero, but that is not
+    /// part of the contract.
+    pub unsafe fn uninit(m: usize, n: usize, p: usize) -> Self {
+        Self::with_slice(prefaulted_slice, m, n, p, padding())
+    }
+
+    /// Returns an array of `m` planes, `n` rows and `p` columns whose bytes are all zero.
+    pub fn zeroed(m: usize, n: usize, p: usize) -> Self {
+        Self::with_slice(zeroed_slice, m, n, p, padding())
+    }
+
+    fn with_slice(
+        slice: fn(usize) -> Box<[T], Aligned>,
+        m: usize,
+        n: usize,
+        p: usize,
+        padding: usize,
+    ) -> Self {
+        let ld = p + padding;
+        let (data, alloc) = Box::into_raw_with_allocator(slice(m * n * ld));
+        // SAFETY: `Box` never holds a null pointer.
+        let data = unsafe { NonNull::new_unchecked(data) };
+        let base = data.cast::<T>();
+        let planes = (0..m)
+            .map(|i| DynPlane {
+                // SAFETY: `i * n * ld` is within the allocation of `m * n * ld` elements.
+                ptr: unsafe { base.add(i * n * ld) },
+                rows: n,
+                cols: p,
+                ld,
+            })
+            .collect();
+        DynArray3D {
+            data,
+            alloc,
+            planes,
+        }
+    }
+
+    /// Returns the dimensions as `(planes, rows, columns)`.
+    pub fn dims(&self) -> (usize, usize, usize) {
+        let (rows, cols) = self.planes.first().map_or((0, 0), |p| (p.rows, p.cols));
+        (self.planes.len(), rows, cols)
+    }
+}
+
+impl<T> Drop for DynArray3D<T> {
+    fn drop(&mut self) {
+        // SAFETY: `data` was taken from a `Box` with `alloc` in `with_slice`.
+        drop(unsafe { Box::from_raw_in(self.data.as_ptr(), self.alloc) });
+    }
+}
+
+impl<T> Index<usize> for DynArray1D<T> {
+    type Output = T;
+
+    #[inline(always)]
+    fn index(&self, index: usize) -> &Self::Output {
+        debug_assert!(index < self.0.len());
+        unsafe { self.0.get_unchecked(index) }
+    }
+}
+
+impl<T> IndexMut<usize> for DynArray1D<T> {
+    #[inline(always)]
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        debug_assert!(index < self.0.len());
+        unsafe { self.0.get_unchecked_mut(index) }
+    }
+}
+
+impl<T> Index<usize> for DynRow<T> {
+    type Output = T;
+
+    #[inline(always)]
+    fn index(&self, index: usize) -> &Self::Output {
+        debug_assert!(index < self.0.len());
+        unsafe { self.0.get_unchecked(index) }
+    }
+}
+
+impl<T> IndexMut<usize> for DynRow<T> {
+    #[inline(always)]
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        debug_assert!(index < self.0.len());
+        unsafe { self.0.get_unchecked_mut(index) }
+    }
+}
+
+impl<T> Index<usize> for DynArray2D<T> {
+    type Output = DynRow<T>;
+
+    #[inline(always)]
+    fn index(&self, index: usize) -> &Self::Output {
+        debug_assert!(index < self.rows);
+        let start = index * self.ld;
+        DynRow::from_slice(unsafe { self.data.get_unchecked(start..start + self.cols) })
+    }
+}
+
+impl<T> IndexMut<usize> for DynArray2D<T> {
+    #[inline(always)]
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        debug_assert!(index < self.rows);
+        let start = index * self.ld;
+        DynRow::from_slice_mut(unsafe { self.data.get_unchecked_mut(start..start + self.cols) })
+    }
+}
+
+impl<T> Index<usize> for DynPlane<T> {
+    type Output = DynRow<T>;
+
+    #[inline(always)]
+    fn index(&self, index: usize) -> &Self::Output {
+        debug_assert!(index < self.rows);
+        // SAFETY: the row lies within the plane, which is owned by the borrowed `DynArray3D`.
+        let row =
+            unsafe { slice::from_raw_parts(self.ptr.add(index * self.ld).as_ptr(), self.cols) };
+        DynRow::from_slice(row)
+    }
+}
+
+impl<T> IndexMut<usize> for DynPlane<T> {
+    #[inline(always)]
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        debug_assert!(index < self.rows);
+        // SAFETY: as above, and planes never overlap so the row is not aliased.
+        let row =
+            unsafe { slice::from_raw_parts_mut(self.ptr.add(index * self.ld).as_ptr(), self.cols) };
+        DynRow::from_slice_mut(row)
+    }
+}
+
+impl<T> Index<usize> for DynArray3D<T> {
+    type Output = DynPlane<T>;
+
+    #[inline(always)]
+    fn index(&self, index: usize) -> &Self::Output {
+        debug_assert!(index < self.planes.len());
+        unsafe { self.planes.get_unchecked(index) }
+    }
+}
+
+impl<T> IndexMut<usize> for DynArray3D<T> {
+    #[inline(always)]
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        debug_assert!(index < self.planes.len());
+        unsafe { self.planes.get_unchecked_mut(index) }
+    }
+}
+
+impl<T> Array2<T> for DynArray2D<T> {
+    type Row = DynRow<T>;
+}
+
+impl<T> Array2<T> for DynPlane<T> {
+    type Row = DynRow<T>;
+}
+
+impl<T> Array3<T> for DynArray3D<T> {
+    type Plane = DynPlane<T>;
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::linear_algebra::blas::gemm;
+    use crate::linear_algebra::solvers::lu;
+    use crate::pages::Pages;
+    use crate::timer::FnTimer;
+    use core::cell::Cell;
     use core::mem::size_of;
 
     #[test]
@@ -187,5 +950,187 @@ mod tests {
         assert_eq!(1024, size_of::<Array1D<u8, 1024>>());
         assert_eq!(8388608, size_of::<Array2D<f64, 1024, 1024>>());
         assert_eq!(67108864, size_of::<Array3D<f32, 256, 256, 256>>());
+
+        assert_eq!(64, mem::align_of::<Array1D<u8, 1, Align64>>());
+        assert_eq!(4096, size_of::<Array1D<u8, 1, Align4096>>());
+        assert_eq!(
+            1024 * 1032 * 8,
+            size_of::<Array2D<f64, 1024, 1024, Align32, 1032>>()
+        );
+        assert_eq!(
+            2 * 3 * 8 * 4,
+            size_of::<Array3D<f32, 2, 3, 5, Align32, 8>>()
+        );
+        assert_eq!(4096, size_of::<Array3D<f32, 2, 3, 5, Align4096, 8>>());
+    }
+
+    #[test]
+    fn prefaulted_arrays_are_zeroed() {
+        let mut policies = alloc::vec![Prefault::None, Prefault::Zero];
+        #[cfg(target_os = "linux")]
+        policies.extend([Prefault::WillNeed, Prefault::Populate]);
+        // The policy is passed in rather than set, which would change it for the tests that run in
+        // parallel.
+        for policy in policies {
+            let data = prefaulted_slice_with::<f64>(300 * 700, policy);
+            assert!(data.iter().all(|&x| x == 0.0), "{}", policy);
+            let array = try_prefaulted_in::<Array2D<i32, 30, 70>, _>(Global, policy).unwrap();
+            let array = unsafe { array.assume_init() };
+            assert!(array.0.iter().all(|row| row.0.iter().all(|&x| x == 0)));
+        }
+    }
+
+    #[test]
+    fn fallible_allocation() {
+        let array = Array2D::<f64, 30, 70>::try_zeroed().unwrap();
+        assert!(array.0.iter().all(|row| row.0.iter().all(|&x| x == 0.0)));
+        let mut a = Array2D::<f64, 3, 3>::try_zeroed().unwrap();
+        a[1][1] = 2.0;
+        a.try_make_positive_semi_definite().unwrap();
+        assert_eq!(4.0, a[1][1]);
+
+        // 64 TiB exceed what Linux overcommits by default, and the address space elsewhere.
+        let error = Array1D::<u8, { 1 << 46 }>::try_zeroed().err().unwrap();
+        assert_eq!(1 << 46, error.layout.size());
+    }
+
+    /// Counts the bytes allocated through it.
+    struct Counting(Cell<usize>);
+
+    unsafe impl Allocator for Counting {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, alloc::alloc::AllocError> {
+            self.0.set(self.0.get() + layout.size());
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            Global.deallocate(ptr, layout)
+        }
+    }
+
+    #[test]
+    fn custom_allocators() {
+        let counting = Counting(Cell::new(0));
+        let array = Array2D::<f64, 30, 70>::zeroed_in(&counting);
+        assert!(array.0.iter().all(|row| row.0.iter().all(|&x| x == 0.0)));
+        Array1D::<u8, 100>::maybe_uninit_in(&counting);
+        assert_eq!(
+            size_of::<Array2D<f64, 30, 70>>() + size_of::<Array1D<u8, 100>>(),
+            counting.0.get()
+        );
+
+        counting.0.set(0);
+        let timer = FnTimer::nanos(|| 0);
+        // Rows of 32 elements need no padding, so all of the footprint is allocated through it.
+        gemm::bench_in::<32, 32, 32, _>(&timer, &counting);
+        assert_eq!(gemm::footprint([32, 32, 32]), counting.0.get() as u64);
+
+        // The scratch array that makes the input positive semi-definite comes from it as well.
+        counting.0.set(0);
+        lu::bench_in::<32, _>(&timer, &counting);
+        assert_eq!(lu::footprint([32]), counting.0.get() as u64);
+    }
+
+    #[test]
+    fn constructors() {
+        let a = Array2D::<u8, 2, 2, Align64, 3>::new([
+            Array1D::new([1, 2, 0]),
+            Array1D::new([3, 4, 0]),
+        ]);
+        assert_eq!(4, a[1][1]);
+        assert!((&a as *const _ as usize).is_multiple_of(64));
+        let b = Array3D::<u8, 1, 2, 2>::new([Array2D::new([
+            Array1D::new([1, 2]),
+            Array1D::new([3, 4]),
+        ])]);
+        assert_eq!(4, b[0][1][1]);
+    }
+
+    #[test]
+    fn dyn_arrays_are_row_major() {
+        let mut a = DynArray2D::<usize>::zeroed(3, 4);
+        let mut b = DynArray3D::<usize>::zeroed(2, 3, 4);
+        for i in 0..3 {
+            for j in 0..4 {
+                a[i][j] = i * 4 + j;
+                for k in 0..2 {
+                    b[k][i][j] = (k * 3 + i) * 4 + j;
+                }
+            }
+        }
+        assert!(a.data.iter().copied().eq(0..12));
+        assert!(unsafe { b.data.as_ref() }.iter().copied().eq(0..24));
+        assert_eq!((2, 3, 4), b.dims());
+        assert_eq!(23, b[1][2][3]);
+    }
+
+    #[test]
+    fn padded_arrays() {
+        let mut a = Array2D::<usize, 3, 4, Align64, 6>::maybe_uninit_zeroed();
+        let mut b = DynArray2D::<usize>::with_slice(zeroed_slice, 3, 4, 2);
+        let mut c = DynArray3D::<usize>::with_slice(zeroed_slice, 2, 3, 4, 2);
+        for i in 0..3 {
+            for j in 0..4 {
+                a[i][j] = i * 4 + j + 1;
+                b[i][j] = i * 4 + j + 1;
+                for k in 0..2 {
+                    c[k][i][j] = (k * 3 + i) * 4 + j + 1;
+                }
+            }
+        }
+        // Every row is followed by two elements of padding that are never written.
+        assert!(a.0.iter().flat_map(|row| &row.0[..4]).copied().eq(1..=12));
+        assert!(a.0.iter().all(|row| row.0[4..] == [0, 0]));
+        for (data, len) in [(&*b.data, 12), (unsafe { c.data.as_ref() }, 24)] {
+            assert!(data
+                .chunks(6)
+                .flat_map(|row| &row[..4])
+                .copied()
+                .eq(1..=len));
+            assert!(data.chunks(6).all(|row| row[4..] == [0, 0]));
+        }
+
+        let alloc = Aligned {
+            alloc: PageAllocator::new(Pages::Default),
+            align: 4096,
+        };
+        let aligned = Box::<[u8], _>::new_zeroed_slice_in(100, alloc);
+        assert!((aligned.as_ptr() as usize).is_multiple_of(4096));
+    }
+
+    #[test]
+    fn footprints() {
+        let arrays: &[&[usize]] = &[&[3, 4], &[2, 3, 4], &[5], &[0, 7]];
+        assert_eq!((12 + 24 + 5) * 8, footprint_with::<f64>(arrays, 0, 1));
+        // Only the rows of arrays with two or three dimensions are padded.
+        assert_eq!((18 + 36 + 5) * 8, footprint_with::<f64>(arrays, 2, 1));
+        // Every array that is not empty takes at least one page.
+        assert_eq!(3 << 30, footprint_with::<f64>(arrays, 2, 1 << 30));
+    }
+
+    #[test]
+    fn display_skips_padding() {
+        let mut a = Array2D::<i32, 2, 3>::maybe_uninit_zeroed();
+        let mut b = Array2D::<i32, 2, 3, Align64, 5>::maybe_uninit_zeroed();
+        let mut c = Array3D::<i32, 2, 2, 3, Align32, 4>::maybe_uninit_zeroed();
+        for (x, value) in a.iter_mut().zip(1..) {
+            *x = value;
+        }
+        for (x, value) in b.iter_mut().zip(1..) {
+            *x = value;
+        }
+        for (x, value) in c.iter_mut().zip(1..) {
+            *x = value;
+        }
+        assert_eq!("[[1, 2, 3], [4, 5, 6]]", alloc::format!("{}", a));
+        assert_eq!(alloc::format!("{}", a), alloc::format!("{}", b));
+        assert_eq!(
+            "[[[1, 2, 3], [4, 5, 6]], [[7, 8, 9], [10, 11, 12]]]",
+            alloc::format!("{}", c)
+        );
+        assert_eq!(
+            "[]",
+            alloc::format!("{}", Array1D::<i32, 0>::maybe_uninit_zeroed())
+        );
     }
 }
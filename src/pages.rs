@@ -0,0 +1,250 @@
+//! The pages that back the arrays of the kernels, which decide how much of them the TLB covers.
+
+use alloc::alloc::Global;
+use core::alloc::{AllocError, Allocator, Layout};
+use core::ptr::{self, NonNull};
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+/// How the memory of the arrays is mapped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pages {
+    /// The global allocator provides the memory and the system-wide transparent huge page policy
+    /// decides about its pages.
+    Default,
+    /// Explicit 2 MiB pages from hugetlbfs, mapped with `MAP_HUGETLB`.
+    #[cfg(target_os = "linux")]
+    Huge2M,
+    /// Explicit 1 GiB pages from hugetlbfs, mapped with `MAP_HUGETLB`.
+    #[cfg(target_os = "linux")]
+    Huge1G,
+    /// Transparent huge pages, requested with `madvise(MADV_HUGEPAGE)` on mappings aligned to
+    /// 2 MiB.
+    #[cfg(target_os = "linux")]
+    Transparent,
+    /// Base pages only, with transparent huge pages disabled by `madvise(MADV_NOHUGEPAGE)`.
+    #[cfg(target_os = "linux")]
+    Small,
+}
+
+impl core::fmt::Display for Pages {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            Pages::Default => "default",
+            #[cfg(target_os = "linux")]
+            Pages::Huge2M => "hugetlb-2m",
+            #[cfg(target_os = "linux")]
+            Pages::Huge1G => "hugetlb-1g",
+            #[cfg(target_os = "linux")]
+            Pages::Transparent => "thp",
+            #[cfg(target_os = "linux")]
+            Pages::Small => "4k",
+        })
+    }
+}
+
+impl Pages {
+    /// Returns the size that the [`PageAllocator`] rounds allocations up to with these pages, or 1
+    /// for [`Pages::Default`], whose allocations it leaves to the global allocator.
+    pub fn granularity(self) -> usize {
+        match self {
+            Pages::Default => 1,
+            #[cfg(target_os = "linux")]
+            Pages::Huge1G => 1 << 30,
+            #[cfg(target_os = "linux")]
+            Pages::Small => unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize },
+            #[cfg(target_os = "linux")]
+            Pages::Huge2M | Pages::Transparent => THP_SIZE,
+        }
+    }
+}
+
+static PAGES: AtomicU8 = AtomicU8::new(Pages::Default as u8);
+static FELL_BACK: AtomicBool = AtomicBool::new(false);
+
+/// Sets the pages of the arrays that are allocated later. The default is [`Pages::Default`].
+pub fn set_pages(pages: Pages) {
+    PAGES.store(pages as u8, Ordering::Relaxed);
+}
+
+/// Returns the mode set by [`set_pages`].
+pub fn pages() -> Pages {
+    match PAGES.load(Ordering::Relaxed) {
+        #[cfg(target_os = "linux")]
+        1 => Pages::Huge2M,
+        #[cfg(target_os = "linux")]
+        2 => Pages::Huge1G,
+        #[cfg(target_os = "linux")]
+        3 => Pages::Transparent,
+        #[cfg(target_os = "linux")]
+        4 => Pages::Small,
+        _ => Pages::Default,
+    }
+}
+
+/// Returns whether an allocation fell back from hugetlbfs pages to transparent huge pages since
+/// the last call.
+pub fn take_fallback() -> bool {
+    FELL_BACK.swap(false, Ordering::Relaxed)
+}
+
+/// An [`Allocator`] that maps every allocation with the pages of a [`Pages`] mode, or forwards it
+/// to the global allocator for [`Pages::Default`].
+///
+/// If hugetlbfs has no free pages of the requested size, the memory is backed with transparent
+/// huge pages instead and [`take_fallback`] reports it.
+#[derive(Clone, Copy, Debug)]
+pub struct PageAllocator {
+    pages: Pages,
+}
+
+impl PageAllocator {
+    pub const fn new(pages: Pages) -> PageAllocator {
+        PageAllocator { pages }
+    }
+}
+
+impl Default for PageAllocator {
+    /// Returns an allocator for the mode set by [`set_pages`].
+    fn default() -> PageAllocator {
+        PageAllocator::new(pages())
+    }
+}
+
+#[cfg(target_os = "linux")]
+const THP_SIZE: usize = 2 << 20;
+
+#[cfg(target_os = "linux")]
+fn mmap(len: usize, flags: libc::c_int) -> Option<*mut u8> {
+    let ptr = unsafe {
+        libc::mmap(
+            ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | flags,
+            -1,
+            0,
+        )
+    };
+    (ptr != libc::MAP_FAILED).then_some(ptr as *mut u8)
+}
+
+/// Maps `len` bytes, a multiple of [`THP_SIZE`], at an address aligned to [`THP_SIZE`], so that
+/// all of them can be backed with transparent huge pages.
+#[cfg(target_os = "linux")]
+fn map_transparent(len: usize) -> Option<*mut u8> {
+    let raw = mmap(len + THP_SIZE, 0)? as usize;
+    let start = raw.next_multiple_of(THP_SIZE);
+    unsafe {
+        if start > raw {
+            libc::munmap(raw as *mut libc::c_void, start - raw);
+        }
+        libc::munmap((start + len) as *mut libc::c_void, raw + THP_SIZE - start);
+        // Fails only if the kernel lacks transparent huge pages, which leaves base pages.
+        libc::madvise(start as *mut libc::c_void, len, libc::MADV_HUGEPAGE);
+    }
+    Some(start as *mut u8)
+}
+
+#[cfg(target_os = "linux")]
+impl PageAllocator {
+    /// Maps fresh, and therefore zeroed, memory for `layout`.
+    fn map(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        if layout.size() == 0 {
+            let dangling = ptr::without_provenance_mut(layout.align());
+            return Ok(NonNull::slice_from_raw_parts(
+                NonNull::new(dangling).ok_or(AllocError)?,
+                0,
+            ));
+        }
+        // Mappings are aligned to their granularity too.
+        let granularity = self.pages.granularity();
+        if layout.align() > granularity {
+            return Err(AllocError);
+        }
+        let len = layout
+            .size()
+            .checked_next_multiple_of(granularity)
+            .ok_or(AllocError)?;
+        let ptr = match self.pages {
+            Pages::Huge2M | Pages::Huge1G => {
+                let size = match self.pages {
+                    Pages::Huge2M => libc::MAP_HUGE_2MB,
+                    _ => libc::MAP_HUGE_1GB,
+                };
+                mmap(len, libc::MAP_HUGETLB | size).or_else(|| {
+                    FELL_BACK.store(true, Ordering::Relaxed);
+                    map_transparent(len)
+                })
+            }
+            Pages::Small => mmap(len, 0).inspect(|&ptr| unsafe {
+                libc::madvise(ptr as *mut libc::c_void, len, libc::MADV_NOHUGEPAGE);
+            }),
+            _ => map_transparent(len),
+        };
+        let ptr = NonNull::new(ptr.ok_or(AllocError)?).ok_or(AllocError)?;
+        Ok(NonNull::slice_from_raw_parts(ptr, len))
+    }
+
+    /// Unmaps memory returned by [`PageAllocator::map`] for `layout`.
+    unsafe fn unmap(&self, ptr: NonNull<u8>, layout: Layout) {
+        if layout.size() != 0 {
+            let len = layout.size().next_multiple_of(self.pages.granularity());
+            unsafe { libc::munmap(ptr.as_ptr() as *mut libc::c_void, len) };
+        }
+    }
+}
+
+unsafe impl Allocator for PageAllocator {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        #[cfg(target_os = "linux")]
+        if self.pages != Pages::Default {
+            return self.map(layout);
+        }
+        Global.allocate(layout)
+    }
+
+    fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        #[cfg(target_os = "linux")]
+        if self.pages != Pages::Default {
+            return self.map(layout);
+        }
+        Global.allocate_zeroed(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        #[cfg(target_os = "linux")]
+        if self.pages != Pages::Default {
+            return unsafe { self.unmap(ptr, layout) };
+        }
+        unsafe { Global.deallocate(ptr, layout) }
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::ndarray::{Array2D, ArrayAlloc};
+
+    #[test]
+    fn page_modes() {
+        for pages in [
+            Pages::Default,
+            Pages::Huge2M,
+            Pages::Huge1G,
+            Pages::Transparent,
+            Pages::Small,
+        ] {
+            let alloc = PageAllocator::new(pages);
+            let mut array = Array2D::<f64, 300, 700>::zeroed_in(alloc);
+            assert!(array.0.iter().all(|row| row.0.iter().all(|&x| x == 0.0)));
+            array[299][699] = 1.0;
+            if pages != Pages::Default {
+                // Transparent huge pages, which 1 GiB pages fall back to, are aligned to 2 MiB.
+                let align = pages.granularity().min(THP_SIZE);
+                assert!((&*array as *const _ as usize).is_multiple_of(align));
+            }
+            let empty = alloc.allocate(Layout::new::<()>()).unwrap();
+            unsafe { alloc.deallocate(empty.cast(), Layout::new::<()>()) };
+        }
+    }
+}
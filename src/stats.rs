@@ -0,0 +1,466 @@
+use alloc::vec::Vec;
+use core::time::Duration;
+
+/// Which samples are discarded before the summary statistics are computed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outliers {
+    /// Every sample is kept.
+    Keep,
+    /// The fastest and the slowest sample are discarded, as PolyBench/C's `time_benchmark.sh`
+    /// does with its five runs.
+    TrimMinMax,
+    /// Samples outside `[Q1 - k * IQR, Q3 + k * IQR]` are discarded (Tukey's fences, usually with
+    /// `k = 1.5`).
+    Tukey(f64),
+}
+
+/// When adaptive sampling stops taking samples.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Adaptive {
+    /// The confidence level of the interval of the median, e.g. 0.95.
+    pub confidence: f64,
+    /// The width of the interval relative to the median below which the median is precise
+    /// enough, e.g. 0.01.
+    pub relative_width: f64,
+    pub max_samples: usize,
+    /// The budget for the sum of the samples. The time spent initializing the arrays is not
+    /// counted.
+    pub max_time: Duration,
+}
+
+/// Why sampling stopped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stop {
+    /// The fixed number of samples was taken.
+    Count,
+    /// The interval of the median became narrow enough.
+    Converged,
+    MaxSamples,
+    MaxTime,
+}
+
+impl core::fmt::Display for Stop {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            Stop::Count => "count",
+            Stop::Converged => "converged",
+            Stop::MaxSamples => "max-samples",
+            Stop::MaxTime => "max-time",
+        })
+    }
+}
+
+/// How often a benchmark is executed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sampling {
+    /// Executions whose results are thrown away before the first sample.
+    pub warmup: usize,
+    /// The number of samples, or the minimum number of samples if `adaptive` is set.
+    pub samples: usize,
+    pub outliers: Outliers,
+    /// Keeps taking samples until the median is precise enough or a budget runs out.
+    pub adaptive: Option<Adaptive>,
+}
+
+impl Sampling {
+    /// A single execution, which is what `bench` measures on its own.
+    pub const SINGLE: Sampling = Sampling {
+        warmup: 0,
+        samples: 1,
+        outliers: Outliers::Keep,
+        adaptive: None,
+    };
+
+    /// Five executions with the minimum and the maximum discarded, as in PolyBench/C.
+    pub const POLYBENCH: Sampling = Sampling {
+        warmup: 0,
+        samples: 5,
+        outliers: Outliers::TrimMinMax,
+        adaptive: None,
+    };
+
+    /// Calls `sample` for every warmup run and sample and summarizes the returned durations.
+    ///
+    /// Every call must set up fresh inputs because many kernels update their inputs in place.
+    /// Each kernel's `bench` does this already since it allocates and initializes its arrays
+    /// before it starts the timer.
+    pub fn run<F>(&self, mut sample: F) -> Summary
+    where
+        F: FnMut() -> Duration,
+    {
+        for _ in 0..self.warmup {
+            sample();
+        }
+        let samples: Vec<Duration> = (0..self.samples).map(|_| sample()).collect();
+        let adaptive = match self.adaptive {
+            Some(adaptive) => adaptive,
+            None => return Summary::new(samples, self.outliers),
+        };
+
+        let mut summary = Summary::new(samples, self.outliers);
+        let mut total: Duration = summary.samples.iter().sum();
+        summary.stop = loop {
+            let converged =
+                summary
+                    .median_interval(adaptive.confidence)
+                    .is_some_and(|(low, high)| {
+                        nanos(high - low) <= adaptive.relative_width * nanos(summary.median)
+                    });
+            if converged {
+                break Stop::Converged;
+            } else if summary.samples.len() >= adaptive.max_samples {
+                break Stop::MaxSamples;
+            } else if total >= adaptive.max_time {
+                break Stop::MaxTime;
+            }
+            let duration = sample();
+            total += duration;
+            let mut samples = summary.samples;
+            samples.push(duration);
+            summary = Summary::new(samples, self.outliers);
+        };
+        summary
+    }
+}
+
+/// Summary statistics of the samples that were not discarded as outliers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Summary {
+    /// Every sample in the order it was measured, including outliers.
+    pub samples: Vec<Duration>,
+    /// Whether the sample at the same index was discarded as an outlier.
+    pub discarded: Vec<bool>,
+    pub min: Duration,
+    pub max: Duration,
+    pub median: Duration,
+    pub mean: Duration,
+    /// The sample standard deviation.
+    pub stddev: Duration,
+    pub stop: Stop,
+}
+
+impl Summary {
+    pub fn new(samples: Vec<Duration>, outliers: Outliers) -> Summary {
+        let mut order: Vec<usize> = (0..samples.len()).collect();
+        order.sort_unstable_by_key(|&i| samples[i]);
+
+        let mut discarded = alloc::vec![false; samples.len()];
+        match outliers {
+            Outliers::Keep => {}
+            Outliers::TrimMinMax => {
+                if samples.len() >= 3 {
+                    discarded[order[0]] = true;
+                    discarded[order[order.len() - 1]] = true;
+                }
+            }
+            Outliers::Tukey(k) => {
+                let sorted: Vec<f64> = order.iter().map(|&i| nanos(samples[i])).collect();
+                let q1 = quantile(&sorted, 0.25);
+                let q3 = quantile(&sorted, 0.75);
+                let (low, high) = (q1 - k * (q3 - q1), q3 + k * (q3 - q1));
+                for (i, sample) in samples.iter().enumerate() {
+                    discarded[i] = !(low..=high).contains(&nanos(*sample));
+                }
+            }
+        }
+
+        let kept: Vec<f64> = order
+            .iter()
+            .filter(|&&i| !discarded[i])
+            .map(|&i| nanos(samples[i]))
+            .collect();
+        let n = kept.len() as f64;
+        let (min, max, median, mean, stddev) = if kept.is_empty() {
+            (0.0, 0.0, 0.0, 0.0, 0.0)
+        } else {
+            let mean = kept.iter().sum::<f64>() / n;
+            let variance = if kept.len() > 1 {
+                kept.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / (n - 1.0)
+            } else {
+                0.0
+            };
+            let median = quantile(&kept, 0.5);
+            (kept[0], kept[kept.len() - 1], median, mean, variance.sqrt())
+        };
+
+        Summary {
+            samples,
+            discarded,
+            min: from_nanos(min),
+            max: from_nanos(max),
+            median: from_nanos(median),
+            mean: from_nanos(mean),
+            stddev: from_nanos(stddev),
+            stop: Stop::Count,
+        }
+    }
+
+    /// Returns the number of samples that were not discarded.
+    pub fn kept(&self) -> usize {
+        self.discarded.iter().filter(|&&d| !d).count()
+    }
+
+    /// Returns the distribution-free confidence interval of the median of the kept samples, or
+    /// `None` if there are too few samples to bound it.
+    ///
+    /// The bounds are the order statistics whose ranks the normal approximation of the binomial
+    /// distribution gives.
+    pub fn median_interval(&self, confidence: f64) -> Option<(Duration, Duration)> {
+        let mut kept: Vec<Duration> = self
+            .samples
+            .iter()
+            .zip(&self.discarded)
+            .filter(|(_, &discarded)| !discarded)
+            .map(|(&sample, _)| sample)
+            .collect();
+        kept.sort_unstable();
+
+        let n = kept.len() as f64;
+        let spread = normal_quantile(0.5 + confidence / 2.0) * n.sqrt();
+        let low = ((n - spread) / 2.0).floor();
+        let high = (1.0 + (n + spread) / 2.0).ceil();
+        if low < 1.0 || high > n {
+            return None;
+        }
+        Some((kept[low as usize - 1], kept[high as usize - 1]))
+    }
+}
+
+/// The speedup of a candidate over a baseline, with a confidence interval.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Speedup {
+    /// How many times faster the candidate is; below 1 if it is slower.
+    pub estimate: f64,
+    /// The bounds of the confidence interval, `0` and infinity if there are too few samples to
+    /// bound it.
+    pub low: f64,
+    pub high: f64,
+}
+
+impl Speedup {
+    /// Compares two non-empty sets of samples.
+    ///
+    /// The estimate is the median of the ratios of all pairs of a baseline and a candidate sample,
+    /// i.e. the Hodges-Lehmann estimate of the shift between the logarithms of the samples. The
+    /// interval is the distribution-free one that is derived from the Mann-Whitney U test, using
+    /// the normal approximation of U.
+    ///
+    /// Samples of 0 ns, which [`crate::timer::Calibrated`] reports for runs shorter than its
+    /// overhead, count as 1 ns so that their logarithms are finite.
+    pub fn new(baseline: &[Duration], candidate: &[Duration], confidence: f64) -> Speedup {
+        let mut ratios: Vec<f64> = baseline
+            .iter()
+            .flat_map(|&b| candidate.iter().map(move |&c| log_nanos(b) - log_nanos(c)))
+            .collect();
+        ratios.sort_unstable_by(f64::total_cmp);
+
+        let (n, m) = (baseline.len() as f64, candidate.len() as f64);
+        let z = normal_quantile(0.5 + confidence / 2.0);
+        let rank = (n * m / 2.0 - z * (n * m * (n + m + 1.0) / 12.0).sqrt()).floor();
+        let (low, high) = if rank >= 1.0 {
+            let rank = rank as usize;
+            (ratios[rank - 1].exp(), ratios[ratios.len() - rank].exp())
+        } else {
+            (0.0, f64::INFINITY)
+        };
+        Speedup {
+            estimate: quantile(&ratios, 0.5).exp(),
+            low,
+            high,
+        }
+    }
+
+    /// Returns `true` if the confidence interval excludes 1, i.e. the change is larger than the
+    /// noise.
+    pub fn is_significant(&self) -> bool {
+        self.low > 1.0 || self.high < 1.0
+    }
+}
+
+/// The quantile function of the standard normal distribution, after Peter Acklam's rational
+/// approximation with a relative error below 1.15e-9.
+fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e+01,
+        2.209460984245205e+02,
+        -2.759285104469687e+02,
+        1.38357751867269e+02,
+        -3.066479806614716e+01,
+        2.506628277459239e+00,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e+01,
+        1.615858368580409e+02,
+        -1.556989798598866e+02,
+        6.680131188771972e+01,
+        -1.328068155288572e+01,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-03,
+        -3.223964580411365e-01,
+        -2.400758277161838e+00,
+        -2.549732539343734e+00,
+        4.374664141464968e+00,
+        2.938163982698783e+00,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-03,
+        3.224671290700398e-01,
+        2.445134137142996e+00,
+        3.754408661907416e+00,
+    ];
+    const LOW: f64 = 0.02425;
+
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+    if p < LOW {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - LOW {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+fn nanos(duration: Duration) -> f64 {
+    duration.as_nanos() as f64
+}
+
+/// Returns the logarithm of `duration` in nanoseconds, taking at least 1 ns.
+fn log_nanos(duration: Duration) -> f64 {
+    nanos(duration).max(1.0).ln()
+}
+
+fn from_nanos(nanos: f64) -> Duration {
+    Duration::from_nanos(nanos.round() as u64)
+}
+
+/// Linearly interpolated quantile of an ascending, non-empty slice.
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let pos = q * (sorted.len() - 1) as f64;
+    let lower = pos as usize;
+    let upper = (lower + 1).min(sorted.len() - 1);
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (pos - lower as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn ms(values: &[u64]) -> Vec<Duration> {
+        values.iter().map(|&v| Duration::from_millis(v)).collect()
+    }
+
+    #[test]
+    fn trim_min_max() {
+        let summary = Summary::new(ms(&[30, 10, 20, 50, 40]), Outliers::TrimMinMax);
+        assert_eq!(vec![false, true, false, true, false], summary.discarded);
+        assert_eq!(Duration::from_millis(20), summary.min);
+        assert_eq!(Duration::from_millis(40), summary.max);
+        assert_eq!(Duration::from_millis(30), summary.median);
+        assert_eq!(Duration::from_millis(30), summary.mean);
+        assert_eq!(Duration::from_millis(10), summary.stddev);
+    }
+
+    #[test]
+    fn tukey_fences() {
+        let summary = Summary::new(ms(&[10, 11, 12, 11, 10, 100]), Outliers::Tukey(1.5));
+        assert_eq!(5, summary.kept());
+        assert!(summary.discarded[5]);
+        assert_eq!(Duration::from_millis(12), summary.max);
+    }
+
+    #[test]
+    fn median_intervals() {
+        let summary = Summary::new(ms(&[5, 1, 4, 2, 3]), Outliers::Keep);
+        assert_eq!(None, summary.median_interval(0.95));
+
+        let samples: Vec<u64> = (1..=100).collect();
+        let summary = Summary::new(ms(&samples), Outliers::Keep);
+        let (low, high) = summary.median_interval(0.95).unwrap();
+        assert_eq!((40, 61), (low.as_millis(), high.as_millis()));
+    }
+
+    #[test]
+    fn adaptive_sampling() {
+        let adaptive = Adaptive {
+            confidence: 0.95,
+            relative_width: 0.01,
+            max_samples: 1000,
+            max_time: Duration::from_secs(10),
+        };
+        let sampling = Sampling {
+            adaptive: Some(adaptive),
+            ..Sampling::SINGLE
+        };
+        let summary = sampling.run(|| Duration::from_millis(10));
+        assert_eq!(Stop::Converged, summary.stop);
+        assert_eq!(8, summary.samples.len());
+
+        let mut next = 0;
+        let mut noisy = || {
+            next = (next + 7) % 10;
+            Duration::from_millis(10 + next)
+        };
+        let summary = Sampling {
+            adaptive: Some(Adaptive {
+                max_samples: 50,
+                ..adaptive
+            }),
+            ..sampling
+        }
+        .run(&mut noisy);
+        assert_eq!(
+            (Stop::MaxSamples, 50),
+            (summary.stop, summary.samples.len())
+        );
+
+        let summary = Sampling {
+            adaptive: Some(Adaptive {
+                max_time: Duration::from_millis(100),
+                ..adaptive
+            }),
+            ..sampling
+        }
+        .run(noisy);
+        assert_eq!(Stop::MaxTime, summary.stop);
+        assert!(summary.samples.len() < 10);
+    }
+
+    #[test]
+    fn normal_quantiles() {
+        assert!((normal_quantile(0.975) - 1.959964).abs() < 1e-6);
+        assert!((normal_quantile(0.005) + 2.575829).abs() < 1e-6);
+        assert_eq!(0.0, normal_quantile(0.5));
+    }
+
+    #[test]
+    fn speedup_intervals() {
+        let baseline = ms(&[20, 22, 24, 21, 23, 20, 22, 24]);
+        let faster = ms(&[10, 11, 12, 10, 11, 12, 11, 10]);
+        let speedup = Speedup::new(&baseline, &faster, 0.95);
+        assert!((speedup.estimate - 2.0).abs() < 0.1);
+        assert!(speedup.is_significant() && speedup.low > 1.5);
+
+        let speedup = Speedup::new(&baseline, &baseline, 0.95);
+        assert_eq!(1.0, speedup.estimate);
+        assert!(!speedup.is_significant());
+
+        let speedup = Speedup::new(&baseline[..1], &faster[..1], 0.95);
+        assert_eq!((0.0, f64::INFINITY), (speedup.low, speedup.high));
+
+        // Runs shorter than the timer's overhead are clamped to zero.
+        let zeros = [Duration::ZERO; 8];
+        let speedup = Speedup::new(&zeros, &zeros, 0.95);
+        assert_eq!(1.0, speedup.estimate);
+        assert!(!speedup.is_significant());
+        let speedup = Speedup::new(&zeros, &faster, 0.95);
+        assert!(speedup.estimate.is_finite() && speedup.high < 1.0);
+    }
+}
@@ -1,17 +1,17 @@
 #![allow(non_snake_case)]
 
-use crate::config::stencils::adi::DataType;
-use crate::ndarray::{Array2D, ArrayAlloc};
+pub use crate::config::stencils::adi::DataType;
+#[cfg(any(test, feature = "print-result"))]
+use crate::dump::Dump;
+use crate::layout::{Layout, LayoutArray2D};
+use crate::ndarray::{AllocError, Array2, Array2D, ArrayAlloc, Contiguous, DynArray2D};
+use crate::pages::PageAllocator;
+use crate::timer::Timer;
 use crate::util;
-use core::mem::MaybeUninit;
+use core::alloc::Allocator;
 use core::time::Duration;
 
-unsafe fn init_array<const N: usize, const TSTEPS: usize>(
-    n: usize,
-    u: &mut MaybeUninit<Array2D<DataType, N, N>>,
-) {
-    let u = unsafe { u.assume_init_mut() };
-
+unsafe fn init_array(n: usize, u: &mut impl Array2<DataType>) {
     for i in 0..n {
         for j in 0..n {
             u[i][j] = (i + n - j) as DataType / n as DataType;
@@ -19,13 +19,27 @@ unsafe fn init_array<const N: usize, const TSTEPS: usize>(
     }
 }
 
-unsafe fn kernel_adi<const N: usize, const TSTEPS: usize>(
+#[cfg(any(test, feature = "print-result"))]
+fn print_array(n: usize, u: &impl Array2<DataType>, dump: &mut impl Dump) {
+    dump.begin("u");
+    for i in 0..n {
+        for j in 0..n {
+            if (i * n + j).is_multiple_of(20) {
+                dump.newline();
+            }
+            dump.value(u[i][j]);
+        }
+    }
+    dump.end("u");
+}
+
+unsafe fn kernel_adi(
     tsteps: usize,
     n: usize,
-    u: &mut Array2D<DataType, N, N>,
-    v: &mut Array2D<DataType, N, N>,
-    p: &mut Array2D<DataType, N, N>,
-    q: &mut Array2D<DataType, N, N>,
+    u: &mut impl Array2<DataType>,
+    v: &mut impl Array2<DataType>,
+    p: &mut impl Array2<DataType>,
+    q: &mut impl Array2<DataType>,
 ) {
     let DX = 1.0 / n as DataType;
     let DY = 1.0 / n as DataType;
@@ -42,7 +56,7 @@ unsafe fn kernel_adi<const N: usize, const TSTEPS: usize>(
     let e = 1.0 + mul2;
     let f = d;
 
-    for _ in 1..tsteps {
+    for _ in 1..=tsteps {
         for i in 1..(n - 1) {
             v[0][i] = 1.0;
             p[i][0] = 0.0;
@@ -80,30 +94,129 @@ unsafe fn kernel_adi<const N: usize, const TSTEPS: usize>(
     }
 }
 
-pub fn bench<const N: usize, const TSTEPS: usize>(timing_function: &dyn Fn() -> u64) -> Duration {
+pub fn bench<const N: usize, const TSTEPS: usize>(timer: &dyn Timer) -> Duration {
+    bench_in::<N, TSTEPS, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench`], but allocates the arrays with `alloc` instead of a [`PageAllocator`].
+pub fn bench_in<const N: usize, const TSTEPS: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Duration {
+    try_bench_in::<N, TSTEPS, _>(timer, alloc).unwrap_or_else(|e| e.handle())
+}
+
+/// Like [`bench`], but returns an error instead of aborting if the arrays cannot be allocated.
+pub fn try_bench<const N: usize, const TSTEPS: usize>(
+    timer: &dyn Timer,
+) -> Result<Duration, AllocError> {
+    try_bench_in::<N, TSTEPS, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench_in`], but returns an error instead of aborting if the arrays cannot be
+/// allocated.
+pub fn try_bench_in<const N: usize, const TSTEPS: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Result<Duration, AllocError> {
     let n = N;
     let tsteps = TSTEPS;
 
-    let mut u = Array2D::<DataType, N, N>::maybe_uninit();
-    let v = Array2D::<DataType, N, N>::maybe_uninit();
-    let p = Array2D::<DataType, N, N>::maybe_uninit();
-    let q = Array2D::<DataType, N, N>::maybe_uninit();
+    let mut u = Array2D::<DataType, N, N>::try_maybe_uninit_in(&alloc)?;
+    let mut v = Array2D::<DataType, N, N>::try_maybe_uninit_in(&alloc)?;
+    let mut p = Array2D::<DataType, N, N>::try_maybe_uninit_in(&alloc)?;
+    let mut q = Array2D::<DataType, N, N>::try_maybe_uninit_in(&alloc)?;
 
     unsafe {
-        init_array::<N, TSTEPS>(n, &mut u);
-        let mut u = u.assume_init();
-        let mut v = v.assume_init();
-        let mut p = p.assume_init();
-        let mut q = q.assume_init();
-
-        let elapsed = util::benchmark_with_timing_function(
-            || kernel_adi::<N, TSTEPS>(tsteps, n, &mut u, &mut v, &mut p, &mut q),
-            timing_function,
+        let u = u.assume_init_mut();
+        let v = v.assume_init_mut();
+        let p = p.assume_init_mut();
+        let q = q.assume_init_mut();
+        init_array(n, u);
+
+        let elapsed = util::benchmark_with_timer(
+            &[u.extent(), v.extent(), p.extent(), q.extent()],
+            || kernel_adi(tsteps, n, u, v, p, q),
+            timer,
         );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, u, &mut crate::dump::Results::start());
+        util::consume(u);
+        Ok(elapsed)
+    }
+}
+
+/// Runs the same benchmark as [`bench`] with dimensions that are only known at runtime, given in
+/// the order of its const generics.
+pub fn bench_dyn(dims: [usize; 2], timer: &dyn Timer) -> Duration {
+    let [n, tsteps] = dims;
+
+    unsafe {
+        let mut u = DynArray2D::<DataType>::uninit(n, n);
+        let mut v = DynArray2D::<DataType>::uninit(n, n);
+        let mut p = DynArray2D::<DataType>::uninit(n, n);
+        let mut q = DynArray2D::<DataType>::uninit(n, n);
+        init_array(n, &mut u);
+
+        let elapsed = util::benchmark_with_timer(
+            &[u.extent(), v.extent(), p.extent(), q.extent()],
+            || kernel_adi(tsteps, n, &mut u, &mut v, &mut p, &mut q),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, &u, &mut crate::dump::Results::start());
+        util::consume(u);
+        elapsed
+    }
+}
+
+/// Like [`bench_dyn`], but stores the arrays with two or three dimensions in the layout `L`.
+pub fn bench_layout<L: Layout>(dims: [usize; 2], timer: &dyn Timer) -> Duration {
+    let [n, tsteps] = dims;
+
+    unsafe {
+        let mut u = LayoutArray2D::<DataType, L>::uninit(n, n);
+        let mut v = LayoutArray2D::<DataType, L>::uninit(n, n);
+        let mut p = LayoutArray2D::<DataType, L>::uninit(n, n);
+        let mut q = LayoutArray2D::<DataType, L>::uninit(n, n);
+        init_array(n, &mut u);
+
+        let elapsed = util::benchmark_with_timer(
+            &[u.extent(), v.extent(), p.extent(), q.extent()],
+            || kernel_adi(tsteps, n, &mut u, &mut v, &mut p, &mut q),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, &u, &mut crate::dump::Results::start());
         util::consume(u);
         elapsed
     }
 }
 
-#[test]
-fn check() {}
+/// Floating-point operations of one run of [`bench`] with `dims`: 30·TSTEPS·(N - 2)²: 15 operations
+/// per inner point in each of the two sweeps.
+pub fn flops(dims: [usize; 2]) -> u64 {
+    let [n, tsteps] = dims.map(|d| d as u64);
+    let inner = n.saturating_sub(2);
+    30 * tsteps * inner * inner
+}
+
+/// Bytes of the arrays [`bench_dyn`] allocates with `dims`: 4·N² elements.
+pub fn footprint(dims: [usize; 2]) -> u64 {
+    let [n, _] = dims;
+    crate::ndarray::footprint::<DataType>(&[&[n, n], &[n, n], &[n, n], &[n, n]])
+}
+
+crate::dataset::datasets! {
+    Mini: [20, 20],
+    Small: [60, 40],
+    Medium: [200, 100],
+    Large: [1000, 500],
+    ExtraLarge: [2000, 1000],
+}
+
+#[cfg(test)]
+crate::dataset::checksums! {
+    Mini: [("u", 400.0000000000004, 83743.46514191404)],
+    Small: [("u", 3600.0000000000073, 6585629.575693584)],
+}
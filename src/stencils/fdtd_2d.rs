@@ -1,23 +1,25 @@
-use crate::config::stencils::fdtd_2d::DataType;
-use crate::ndarray::{Array1D, Array2D, ArrayAlloc};
+pub use crate::config::stencils::fdtd_2d::DataType;
+#[cfg(any(test, feature = "print-result"))]
+use crate::dump::Dump;
+use crate::layout::{Layout, LayoutArray2D};
+use crate::ndarray::{
+    AllocError, Array1, Array1D, Array2, Array2D, ArrayAlloc, Contiguous, DynArray1D, DynArray2D,
+};
+use crate::pages::PageAllocator;
+use crate::timer::Timer;
 use crate::util;
-use core::mem::MaybeUninit;
+use core::alloc::Allocator;
 use core::time::Duration;
 
-unsafe fn init_array<const NX: usize, const NY: usize, const TMAX: usize>(
+unsafe fn init_array(
     tmax: usize,
     nx: usize,
     ny: usize,
-    ex: &mut MaybeUninit<Array2D<DataType, NX, NY>>,
-    ey: &mut MaybeUninit<Array2D<DataType, NX, NY>>,
-    hz: &mut MaybeUninit<Array2D<DataType, NX, NY>>,
-    fict: &mut MaybeUninit<Array1D<DataType, TMAX>>,
+    ex: &mut impl Array2<DataType>,
+    ey: &mut impl Array2<DataType>,
+    hz: &mut impl Array2<DataType>,
+    fict: &mut impl Array1<DataType>,
 ) {
-    let ex = unsafe { ex.assume_init_mut() };
-    let ey = unsafe { ey.assume_init_mut() };
-    let hz = unsafe { hz.assume_init_mut() };
-    let fict = unsafe { fict.assume_init_mut() };
-
     for i in 0..tmax {
         fict[i] = i as DataType;
     }
@@ -30,14 +32,55 @@ unsafe fn init_array<const NX: usize, const NY: usize, const TMAX: usize>(
     }
 }
 
-unsafe fn kernel_fdtd_2d<const NX: usize, const NY: usize, const TMAX: usize>(
+#[cfg(any(test, feature = "print-result"))]
+fn print_array(
+    nx: usize,
+    ny: usize,
+    ex: &impl Array2<DataType>,
+    ey: &impl Array2<DataType>,
+    hz: &impl Array2<DataType>,
+    dump: &mut impl Dump,
+) {
+    dump.begin("ex");
+    for i in 0..nx {
+        for j in 0..ny {
+            if (i * nx + j).is_multiple_of(20) {
+                dump.newline();
+            }
+            dump.value(ex[i][j]);
+        }
+    }
+    dump.end("ex");
+    dump.begin("ey");
+    for i in 0..nx {
+        for j in 0..ny {
+            if (i * nx + j).is_multiple_of(20) {
+                dump.newline();
+            }
+            dump.value(ey[i][j]);
+        }
+    }
+    dump.end("ey");
+    dump.begin("hz");
+    for i in 0..nx {
+        for j in 0..ny {
+            if (i * nx + j).is_multiple_of(20) {
+                dump.newline();
+            }
+            dump.value(hz[i][j]);
+        }
+    }
+    dump.end("hz");
+}
+
+unsafe fn kernel_fdtd_2d(
     tmax: usize,
     nx: usize,
     ny: usize,
-    ex: &mut Array2D<DataType, NX, NY>,
-    ey: &mut Array2D<DataType, NX, NY>,
-    hz: &mut Array2D<DataType, NX, NY>,
-    fict: &Array1D<DataType, TMAX>,
+    ex: &mut impl Array2<DataType>,
+    ey: &mut impl Array2<DataType>,
+    hz: &mut impl Array2<DataType>,
+    fict: &impl Array1<DataType>,
 ) {
     for t in 0..tmax {
         for j in 0..ny {
@@ -45,45 +88,121 @@ unsafe fn kernel_fdtd_2d<const NX: usize, const NY: usize, const TMAX: usize>(
         }
         for i in 1..nx {
             for j in 0..ny {
-                ey[i][j] = ey[i][j] - 0.5 * (hz[i][j] - hz[i - 1][j]);
+                ey[i][j] -= 0.5 * (hz[i][j] - hz[i - 1][j]);
             }
         }
         for i in 0..nx {
             for j in 1..ny {
-                ex[i][j] = ex[i][j] - 0.5 * (hz[i][j] - hz[i][j - 1]);
+                ex[i][j] -= 0.5 * (hz[i][j] - hz[i][j - 1]);
             }
         }
         for i in 0..(nx - 1) {
             for j in 0..(ny - 1) {
-                hz[i][j] = hz[i][j] - 0.7 * (ex[i][j + 1] - ex[i][j] + ey[i + 1][j] - ey[i][j]);
+                hz[i][j] -= 0.7 * (ex[i][j + 1] - ex[i][j] + ey[i + 1][j] - ey[i][j]);
             }
         }
     }
 }
 
-pub fn bench<const NX: usize, const NY: usize, const TMAX: usize>(
-    timing_function: &dyn Fn() -> u64,
+pub fn bench<const NX: usize, const NY: usize, const TMAX: usize>(timer: &dyn Timer) -> Duration {
+    bench_in::<NX, NY, TMAX, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench`], but allocates the arrays with `alloc` instead of a [`PageAllocator`].
+pub fn bench_in<const NX: usize, const NY: usize, const TMAX: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
 ) -> Duration {
+    try_bench_in::<NX, NY, TMAX, _>(timer, alloc).unwrap_or_else(|e| e.handle())
+}
+
+/// Like [`bench`], but returns an error instead of aborting if the arrays cannot be allocated.
+pub fn try_bench<const NX: usize, const NY: usize, const TMAX: usize>(
+    timer: &dyn Timer,
+) -> Result<Duration, AllocError> {
+    try_bench_in::<NX, NY, TMAX, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench_in`], but returns an error instead of aborting if the arrays cannot be
+/// allocated.
+pub fn try_bench_in<const NX: usize, const NY: usize, const TMAX: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Result<Duration, AllocError> {
     let tmax = TMAX;
     let nx = NX;
     let ny = NY;
 
-    let mut ex = Array2D::<DataType, NX, NY>::maybe_uninit();
-    let mut ey = Array2D::<DataType, NX, NY>::maybe_uninit();
-    let mut hz = Array2D::<DataType, NX, NY>::maybe_uninit();
-    let mut fict = Array1D::<DataType, TMAX>::maybe_uninit();
+    let mut ex = Array2D::<DataType, NX, NY>::try_maybe_uninit_in(&alloc)?;
+    let mut ey = Array2D::<DataType, NX, NY>::try_maybe_uninit_in(&alloc)?;
+    let mut hz = Array2D::<DataType, NX, NY>::try_maybe_uninit_in(&alloc)?;
+    let mut fict = Array1D::<DataType, TMAX>::try_maybe_uninit_in(&alloc)?;
 
     unsafe {
+        let ex = ex.assume_init_mut();
+        let ey = ey.assume_init_mut();
+        let hz = hz.assume_init_mut();
+        let fict = fict.assume_init_mut();
+        init_array(tmax, nx, ny, ex, ey, hz, fict);
+
+        let elapsed = util::benchmark_with_timer(
+            &[ex.extent(), ey.extent(), hz.extent(), fict.extent()],
+            || kernel_fdtd_2d(tmax, nx, ny, ex, ey, hz, fict),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(nx, ny, ex, ey, hz, &mut crate::dump::Results::start());
+        util::consume(ex);
+        util::consume(ey);
+        util::consume(hz);
+        Ok(elapsed)
+    }
+}
+
+/// Runs the same benchmark as [`bench`] with dimensions that are only known at runtime, given in
+/// the order of its const generics.
+pub fn bench_dyn(dims: [usize; 3], timer: &dyn Timer) -> Duration {
+    let [nx, ny, tmax] = dims;
+
+    unsafe {
+        let mut ex = DynArray2D::<DataType>::uninit(nx, ny);
+        let mut ey = DynArray2D::<DataType>::uninit(nx, ny);
+        let mut hz = DynArray2D::<DataType>::uninit(nx, ny);
+        let mut fict = DynArray1D::<DataType>::uninit(tmax);
+        init_array(tmax, nx, ny, &mut ex, &mut ey, &mut hz, &mut fict);
+
+        let elapsed = util::benchmark_with_timer(
+            &[ex.extent(), ey.extent(), hz.extent(), fict.extent()],
+            || kernel_fdtd_2d(tmax, nx, ny, &mut ex, &mut ey, &mut hz, &fict),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(nx, ny, &ex, &ey, &hz, &mut crate::dump::Results::start());
+        util::consume(ex);
+        util::consume(ey);
+        util::consume(hz);
+        elapsed
+    }
+}
+
+/// Like [`bench_dyn`], but stores the arrays with two or three dimensions in the layout `L`.
+pub fn bench_layout<L: Layout>(dims: [usize; 3], timer: &dyn Timer) -> Duration {
+    let [nx, ny, tmax] = dims;
+
+    unsafe {
+        let mut ex = LayoutArray2D::<DataType, L>::uninit(nx, ny);
+        let mut ey = LayoutArray2D::<DataType, L>::uninit(nx, ny);
+        let mut hz = LayoutArray2D::<DataType, L>::uninit(nx, ny);
+        let mut fict = DynArray1D::<DataType>::uninit(tmax);
         init_array(tmax, nx, ny, &mut ex, &mut ey, &mut hz, &mut fict);
-        let fict = fict.assume_init();
-        let mut ex = ex.assume_init();
-        let mut ey = ey.assume_init();
-        let mut hz = hz.assume_init();
 
-        let elapsed = util::benchmark_with_timing_function(
+        let elapsed = util::benchmark_with_timer(
+            &[ex.extent(), ey.extent(), hz.extent(), fict.extent()],
             || kernel_fdtd_2d(tmax, nx, ny, &mut ex, &mut ey, &mut hz, &fict),
-            timing_function,
+            timer,
         );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(nx, ny, &ex, &ey, &hz, &mut crate::dump::Results::start());
         util::consume(ex);
         util::consume(ey);
         util::consume(hz);
@@ -91,5 +210,38 @@ pub fn bench<const NX: usize, const NY: usize, const TMAX: usize>(
     }
 }
 
-#[test]
-fn check() {}
+/// Floating-point operations of one run of [`bench`] with `dims`:
+/// TMAX·(3·(NX - 1)·NY + 3·NX·(NY - 1) + 5·(NX - 1)·(NY - 1)).
+pub fn flops(dims: [usize; 3]) -> u64 {
+    let [nx, ny, tmax] = dims.map(|d| d as u64);
+    let (nx1, ny1) = (nx.saturating_sub(1), ny.saturating_sub(1));
+    tmax * (3 * nx1 * ny + 3 * nx * ny1 + 5 * nx1 * ny1)
+}
+
+/// Bytes of the arrays [`bench_dyn`] allocates with `dims`: 3·NX·NY + TMAX elements.
+pub fn footprint(dims: [usize; 3]) -> u64 {
+    let [nx, ny, tmax] = dims;
+    crate::ndarray::footprint::<DataType>(&[&[nx, ny], &[nx, ny], &[nx, ny], &[tmax]])
+}
+
+crate::dataset::datasets! {
+    Mini: [20, 30, 20],
+    Small: [60, 80, 40],
+    Medium: [200, 240, 100],
+    Large: [1000, 1200, 500],
+    ExtraLarge: [2000, 2600, 1000],
+}
+
+#[cfg(test)]
+crate::dataset::checksums! {
+    Mini: [
+        ("ex", 1910.9611519827547, 682934.1777162349),
+        ("ey", 1417.0544378320187, -435357.6573425882),
+        ("hz", 6839.525179632956, 2006634.2267438849),
+    ],
+    Small: [
+        ("ex", 50508.47542023923, 155834757.60506034),
+        ("ey", 41484.03708850691, 32964561.09201144),
+        ("hz", 83737.83342234396, 213521808.12568584),
+    ],
+}
@@ -1,19 +1,17 @@
 #![allow(non_snake_case)]
 
-use crate::config::stencils::heat_3d::DataType;
-use crate::ndarray::{Array3D, ArrayAlloc};
+pub use crate::config::stencils::heat_3d::DataType;
+#[cfg(any(test, feature = "print-result"))]
+use crate::dump::Dump;
+use crate::layout::{Layout, LayoutArray3D};
+use crate::ndarray::{AllocError, Array3, Array3D, ArrayAlloc, Contiguous, DynArray3D};
+use crate::pages::PageAllocator;
+use crate::timer::Timer;
 use crate::util;
-use core::mem::MaybeUninit;
+use core::alloc::Allocator;
 use core::time::Duration;
 
-unsafe fn init_array<const N: usize, const TSTEPS: usize>(
-    n: usize,
-    A: &mut MaybeUninit<Array3D<DataType, N, N, N>>,
-    B: &mut MaybeUninit<Array3D<DataType, N, N, N>>,
-) {
-    let A = unsafe { A.assume_init_mut() };
-    let B = unsafe { B.assume_init_mut() };
-
+unsafe fn init_array(n: usize, A: &mut impl Array3<DataType>, B: &mut impl Array3<DataType>) {
     for i in 0..n {
         for j in 0..n {
             for k in 0..n {
@@ -24,11 +22,27 @@ unsafe fn init_array<const N: usize, const TSTEPS: usize>(
     }
 }
 
-unsafe fn kernel_heat_3d<const N: usize, const TSTEPS: usize>(
+#[cfg(any(test, feature = "print-result"))]
+fn print_array(n: usize, A: &impl Array3<DataType>, dump: &mut impl Dump) {
+    dump.begin("A");
+    for i in 0..n {
+        for j in 0..n {
+            for k in 0..n {
+                if (i * n * n + j * n + k).is_multiple_of(20) {
+                    dump.newline();
+                }
+                dump.value(A[i][j][k]);
+            }
+        }
+    }
+    dump.end("A");
+}
+
+unsafe fn kernel_heat_3d(
     tsteps: usize,
     n: usize,
-    A: &mut Array3D<DataType, N, N, N>,
-    B: &mut Array3D<DataType, N, N, N>,
+    A: &mut impl Array3<DataType>,
+    B: &mut impl Array3<DataType>,
 ) {
     for _ in 1..tsteps {
         for i in 1..(n - 1) {
@@ -54,26 +68,121 @@ unsafe fn kernel_heat_3d<const N: usize, const TSTEPS: usize>(
     }
 }
 
-pub fn bench<const N: usize, const TSTEPS: usize>(timing_function: &dyn Fn() -> u64) -> Duration {
+pub fn bench<const N: usize, const TSTEPS: usize>(timer: &dyn Timer) -> Duration {
+    bench_in::<N, TSTEPS, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench`], but allocates the arrays with `alloc` instead of a [`PageAllocator`].
+pub fn bench_in<const N: usize, const TSTEPS: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Duration {
+    try_bench_in::<N, TSTEPS, _>(timer, alloc).unwrap_or_else(|e| e.handle())
+}
+
+/// Like [`bench`], but returns an error instead of aborting if the arrays cannot be allocated.
+pub fn try_bench<const N: usize, const TSTEPS: usize>(
+    timer: &dyn Timer,
+) -> Result<Duration, AllocError> {
+    try_bench_in::<N, TSTEPS, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench_in`], but returns an error instead of aborting if the arrays cannot be
+/// allocated.
+pub fn try_bench_in<const N: usize, const TSTEPS: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Result<Duration, AllocError> {
     let n = N;
     let tsteps = TSTEPS;
 
-    let mut A = Array3D::<DataType, N, N, N>::maybe_uninit();
-    let mut B = Array3D::<DataType, N, N, N>::maybe_uninit();
+    let mut A = Array3D::<DataType, N, N, N>::try_maybe_uninit_in(&alloc)?;
+    let mut B = Array3D::<DataType, N, N, N>::try_maybe_uninit_in(&alloc)?;
 
     unsafe {
-        init_array::<N, TSTEPS>(n, &mut A, &mut B);
-        let mut A = A.assume_init();
-        let mut B = B.assume_init();
-        
-        let elapsed = util::benchmark_with_timing_function(
-            || kernel_heat_3d::<N, TSTEPS>(tsteps, n, &mut A, &mut B),
-            timing_function,
+        let A = A.assume_init_mut();
+        let B = B.assume_init_mut();
+        init_array(n, A, B);
+
+        let elapsed = util::benchmark_with_timer(
+            &[A.extent(), B.extent()],
+            || kernel_heat_3d(tsteps, n, A, B),
+            timer,
         );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, A, &mut crate::dump::Results::start());
+        util::consume(A);
+        Ok(elapsed)
+    }
+}
+
+/// Runs the same benchmark as [`bench`] with dimensions that are only known at runtime, given in
+/// the order of its const generics.
+pub fn bench_dyn(dims: [usize; 2], timer: &dyn Timer) -> Duration {
+    let [n, tsteps] = dims;
+
+    unsafe {
+        let mut A = DynArray3D::<DataType>::uninit(n, n, n);
+        let mut B = DynArray3D::<DataType>::uninit(n, n, n);
+        init_array(n, &mut A, &mut B);
+
+        let elapsed = util::benchmark_with_timer(
+            &[A.extent(), B.extent()],
+            || kernel_heat_3d(tsteps, n, &mut A, &mut B),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, &A, &mut crate::dump::Results::start());
         util::consume(A);
         elapsed
     }
 }
 
-#[test]
-fn check() {}
+/// Like [`bench_dyn`], but stores the arrays with two or three dimensions in the layout `L`.
+pub fn bench_layout<L: Layout>(dims: [usize; 2], timer: &dyn Timer) -> Duration {
+    let [n, tsteps] = dims;
+
+    unsafe {
+        let mut A = LayoutArray3D::<DataType, L>::uninit(n, n, n);
+        let mut B = LayoutArray3D::<DataType, L>::uninit(n, n, n);
+        init_array(n, &mut A, &mut B);
+
+        let elapsed = util::benchmark_with_timer(
+            &[A.extent(), B.extent()],
+            || kernel_heat_3d(tsteps, n, &mut A, &mut B),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, &A, &mut crate::dump::Results::start());
+        util::consume(A);
+        elapsed
+    }
+}
+
+/// Floating-point operations of one run of [`bench`] with `dims`: 30·TSTEPS·(N - 2)³: 15 operations
+/// per inner point in each of the two sweeps.
+pub fn flops(dims: [usize; 2]) -> u64 {
+    let [n, tsteps] = dims.map(|d| d as u64);
+    let inner = n.saturating_sub(2);
+    30 * tsteps * inner * inner * inner
+}
+
+/// Bytes of the arrays [`bench_dyn`] allocates with `dims`: 2·N³ elements.
+pub fn footprint(dims: [usize; 2]) -> u64 {
+    let [n, _] = dims;
+    crate::ndarray::footprint::<DataType>(&[&[n, n, n], &[n, n, n]])
+}
+
+crate::dataset::datasets! {
+    Mini: [10, 20],
+    Small: [20, 40],
+    Medium: [40, 100],
+    Large: [120, 500],
+    ExtraLarge: [200, 1000],
+}
+
+#[cfg(test)]
+crate::dataset::checksums! {
+    Mini: [("A", 14500.0, 8156500.0)],
+    Small: [("A", 118000.0, 527786000.0)],
+}
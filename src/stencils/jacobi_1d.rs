@@ -1,30 +1,39 @@
 #![allow(non_snake_case)]
 
-use crate::config::stencils::jacobi_1d::DataType;
-use crate::ndarray::{Array1D, ArrayAlloc};
+pub use crate::config::stencils::jacobi_1d::DataType;
+#[cfg(any(test, feature = "print-result"))]
+use crate::dump::Dump;
+use crate::ndarray::{AllocError, Array1, Array1D, ArrayAlloc, Contiguous, DynArray1D};
+use crate::pages::PageAllocator;
+use crate::timer::Timer;
 use crate::util;
-use core::mem::MaybeUninit;
+use core::alloc::Allocator;
 use core::time::Duration;
 
-unsafe fn init_array<const N: usize, const TSTEPS: usize>(
-    n: usize,
-    A: &mut MaybeUninit<Array1D<DataType, N>>,
-    B: &mut MaybeUninit<Array1D<DataType, N>>,
-) {
-    let A = unsafe { A.assume_init_mut() };
-    let B = unsafe { B.assume_init_mut() };
-
+unsafe fn init_array(n: usize, A: &mut impl Array1<DataType>, B: &mut impl Array1<DataType>) {
     for i in 0..n {
         A[i] = (i + 2) as DataType / n as DataType;
         B[i] = (i + 3) as DataType / n as DataType;
     }
 }
 
-unsafe fn kernel_jacobi_1d<const N: usize, const TSTEPS: usize>(
+#[cfg(any(test, feature = "print-result"))]
+fn print_array(n: usize, A: &impl Array1<DataType>, dump: &mut impl Dump) {
+    dump.begin("A");
+    for i in 0..n {
+        if i.is_multiple_of(20) {
+            dump.newline();
+        }
+        dump.value(A[i]);
+    }
+    dump.end("A");
+}
+
+unsafe fn kernel_jacobi_1d(
     tsteps: usize,
     n: usize,
-    A: &mut Array1D<DataType, N>,
-    B: &mut Array1D<DataType, N>,
+    A: &mut impl Array1<DataType>,
+    B: &mut impl Array1<DataType>,
 ) {
     for _ in 0..tsteps {
         for i in 1..(n - 1) {
@@ -36,26 +45,98 @@ unsafe fn kernel_jacobi_1d<const N: usize, const TSTEPS: usize>(
     }
 }
 
-pub fn bench<const N: usize, const TSTEPS: usize>(timing_function: &dyn Fn() -> u64) -> Duration {
+pub fn bench<const N: usize, const TSTEPS: usize>(timer: &dyn Timer) -> Duration {
+    bench_in::<N, TSTEPS, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench`], but allocates the arrays with `alloc` instead of a [`PageAllocator`].
+pub fn bench_in<const N: usize, const TSTEPS: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Duration {
+    try_bench_in::<N, TSTEPS, _>(timer, alloc).unwrap_or_else(|e| e.handle())
+}
+
+/// Like [`bench`], but returns an error instead of aborting if the arrays cannot be allocated.
+pub fn try_bench<const N: usize, const TSTEPS: usize>(
+    timer: &dyn Timer,
+) -> Result<Duration, AllocError> {
+    try_bench_in::<N, TSTEPS, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench_in`], but returns an error instead of aborting if the arrays cannot be
+/// allocated.
+pub fn try_bench_in<const N: usize, const TSTEPS: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Result<Duration, AllocError> {
     let n = N;
     let tsteps = TSTEPS;
 
-    let mut A = Array1D::<DataType, N>::maybe_uninit();
-    let mut B = Array1D::<DataType, N>::maybe_uninit();
+    let mut A = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
+    let mut B = Array1D::<DataType, N>::try_maybe_uninit_in(&alloc)?;
 
     unsafe {
-        init_array::<N, TSTEPS>(n, &mut A, &mut B);
-        let mut A = A.assume_init();
-        let mut B = B.assume_init();
+        let A = A.assume_init_mut();
+        let B = B.assume_init_mut();
+        init_array(n, A, B);
 
-        let elapsed = util::benchmark_with_timing_function(
-            || kernel_jacobi_1d::<N, TSTEPS>(tsteps, n, &mut A, &mut B),
-            timing_function,
+        let elapsed = util::benchmark_with_timer(
+            &[A.extent(), B.extent()],
+            || kernel_jacobi_1d(tsteps, n, A, B),
+            timer,
         );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, A, &mut crate::dump::Results::start());
+        util::consume(A);
+        Ok(elapsed)
+    }
+}
+
+/// Runs the same benchmark as [`bench`] with dimensions that are only known at runtime, given in
+/// the order of its const generics.
+pub fn bench_dyn(dims: [usize; 2], timer: &dyn Timer) -> Duration {
+    let [n, tsteps] = dims;
+
+    unsafe {
+        let mut A = DynArray1D::<DataType>::uninit(n);
+        let mut B = DynArray1D::<DataType>::uninit(n);
+        init_array(n, &mut A, &mut B);
+
+        let elapsed = util::benchmark_with_timer(
+            &[A.extent(), B.extent()],
+            || kernel_jacobi_1d(tsteps, n, &mut A, &mut B),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, &A, &mut crate::dump::Results::start());
         util::consume(A);
         elapsed
     }
 }
 
-#[test]
-fn check() {}
+/// Floating-point operations of one run of [`bench`] with `dims`: 6·TSTEPS·(N - 2).
+pub fn flops(dims: [usize; 2]) -> u64 {
+    let [n, tsteps] = dims.map(|d| d as u64);
+    6 * tsteps * n.saturating_sub(2)
+}
+
+/// Bytes of the arrays [`bench_dyn`] allocates with `dims`: 2·N elements.
+pub fn footprint(dims: [usize; 2]) -> u64 {
+    let [n, _] = dims;
+    crate::ndarray::footprint::<DataType>(&[&[n], &[n]])
+}
+
+crate::dataset::datasets! {
+    Mini: [30, 20],
+    Small: [120, 40],
+    Medium: [400, 100],
+    Large: [2000, 500],
+    ExtraLarge: [4000, 1000],
+}
+
+#[cfg(test)]
+crate::dataset::checksums! {
+    Mini: [("A", 16.622753795581627, 332.55514860572396)],
+    Small: [("A", 61.50059792711304, 4919.940147697805)],
+}
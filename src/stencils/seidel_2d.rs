@@ -1,17 +1,17 @@
 #![allow(non_snake_case)]
 
-use crate::config::stencils::seidel_2d::DataType;
-use crate::ndarray::{Array2D, ArrayAlloc};
+pub use crate::config::stencils::seidel_2d::DataType;
+#[cfg(any(test, feature = "print-result"))]
+use crate::dump::Dump;
+use crate::layout::{Layout, LayoutArray2D};
+use crate::ndarray::{AllocError, Array2, Array2D, ArrayAlloc, Contiguous, DynArray2D};
+use crate::pages::PageAllocator;
+use crate::timer::Timer;
 use crate::util;
-use core::mem::MaybeUninit;
+use core::alloc::Allocator;
 use core::time::Duration;
 
-unsafe fn init_array<const N: usize, const TSTEPS: usize>(
-    n: usize,
-    A: &mut MaybeUninit<Array2D<DataType, N, N>>,
-) {
-    let A = unsafe { A.assume_init_mut() };
-
+unsafe fn init_array(n: usize, A: &mut impl Array2<DataType>) {
     for i in 0..n {
         for j in 0..n {
             A[i][j] = (i * (j + 2) + 2) as DataType / n as DataType;
@@ -19,11 +19,21 @@ unsafe fn init_array<const N: usize, const TSTEPS: usize>(
     }
 }
 
-unsafe fn kernel_seidel_2d<const N: usize, const TSTEPS: usize>(
-    tsteps: usize,
-    n: usize,
-    A: &mut Array2D<DataType, N, N>,
-) {
+#[cfg(any(test, feature = "print-result"))]
+fn print_array(n: usize, A: &impl Array2<DataType>, dump: &mut impl Dump) {
+    dump.begin("A");
+    for i in 0..n {
+        for j in 0..n {
+            if (i * n + j).is_multiple_of(20) {
+                dump.newline();
+            }
+            dump.value(A[i][j]);
+        }
+    }
+    dump.end("A");
+}
+
+unsafe fn kernel_seidel_2d(tsteps: usize, n: usize, A: &mut impl Array2<DataType>) {
     for _ in 0..tsteps {
         for i in 1..(n - 1) {
             for j in 1..(n - 1) {
@@ -42,24 +52,113 @@ unsafe fn kernel_seidel_2d<const N: usize, const TSTEPS: usize>(
     }
 }
 
-pub fn bench<const N: usize, const TSTEPS: usize>(timing_function: &dyn Fn() -> u64) -> Duration {
+pub fn bench<const N: usize, const TSTEPS: usize>(timer: &dyn Timer) -> Duration {
+    bench_in::<N, TSTEPS, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench`], but allocates the arrays with `alloc` instead of a [`PageAllocator`].
+pub fn bench_in<const N: usize, const TSTEPS: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Duration {
+    try_bench_in::<N, TSTEPS, _>(timer, alloc).unwrap_or_else(|e| e.handle())
+}
+
+/// Like [`bench`], but returns an error instead of aborting if the arrays cannot be allocated.
+pub fn try_bench<const N: usize, const TSTEPS: usize>(
+    timer: &dyn Timer,
+) -> Result<Duration, AllocError> {
+    try_bench_in::<N, TSTEPS, _>(timer, PageAllocator::default())
+}
+
+/// Like [`bench_in`], but returns an error instead of aborting if the arrays cannot be
+/// allocated.
+pub fn try_bench_in<const N: usize, const TSTEPS: usize, Alloc: Allocator>(
+    timer: &dyn Timer,
+    alloc: Alloc,
+) -> Result<Duration, AllocError> {
     let n = N;
     let tsteps = TSTEPS;
 
-    let mut A = Array2D::<DataType, N, N>::maybe_uninit();
+    let mut A = Array2D::<DataType, N, N>::try_maybe_uninit_in(&alloc)?;
 
     unsafe {
-        init_array::<N, TSTEPS>(n, &mut A);
-        let mut A = A.assume_init();
+        let A = A.assume_init_mut();
+        init_array(n, A);
+
+        let elapsed =
+            util::benchmark_with_timer(&[A.extent()], || kernel_seidel_2d(tsteps, n, A), timer);
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, A, &mut crate::dump::Results::start());
+        util::consume(A);
+        Ok(elapsed)
+    }
+}
+
+/// Runs the same benchmark as [`bench`] with dimensions that are only known at runtime, given in
+/// the order of its const generics.
+pub fn bench_dyn(dims: [usize; 2], timer: &dyn Timer) -> Duration {
+    let [n, tsteps] = dims;
 
-        let elapsed = util::benchmark_with_timing_function(
-            || kernel_seidel_2d::<N, TSTEPS>(tsteps, n, &mut A),
-            timing_function,
+    unsafe {
+        let mut A = DynArray2D::<DataType>::uninit(n, n);
+        init_array(n, &mut A);
+
+        let elapsed = util::benchmark_with_timer(
+            &[A.extent()],
+            || kernel_seidel_2d(tsteps, n, &mut A),
+            timer,
+        );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, &A, &mut crate::dump::Results::start());
+        util::consume(A);
+        elapsed
+    }
+}
+
+/// Like [`bench_dyn`], but stores the arrays with two or three dimensions in the layout `L`.
+pub fn bench_layout<L: Layout>(dims: [usize; 2], timer: &dyn Timer) -> Duration {
+    let [n, tsteps] = dims;
+
+    unsafe {
+        let mut A = LayoutArray2D::<DataType, L>::uninit(n, n);
+        init_array(n, &mut A);
+
+        let elapsed = util::benchmark_with_timer(
+            &[A.extent()],
+            || kernel_seidel_2d(tsteps, n, &mut A),
+            timer,
         );
+        #[cfg(any(test, feature = "print-result"))]
+        print_array(n, &A, &mut crate::dump::Results::start());
         util::consume(A);
         elapsed
     }
 }
 
-#[test]
-fn check() {}
+/// Floating-point operations of one run of [`bench`] with `dims`: 9·TSTEPS·(N - 2)².
+pub fn flops(dims: [usize; 2]) -> u64 {
+    let [n, tsteps] = dims.map(|d| d as u64);
+    let inner = n.saturating_sub(2);
+    9 * tsteps * inner * inner
+}
+
+/// Bytes of the arrays [`bench_dyn`] allocates with `dims`: N² elements.
+pub fn footprint(dims: [usize; 2]) -> u64 {
+    let [n, _] = dims;
+    crate::ndarray::footprint::<DataType>(&[&[n, n]])
+}
+
+crate::dataset::datasets! {
+    Mini: [40, 20],
+    Small: [120, 40],
+    Medium: [400, 100],
+    Large: [2000, 500],
+    ExtraLarge: [4000, 1000],
+}
+
+#[cfg(test)]
+crate::dataset::checksums! {
+    Mini: [("A", 16849.999999999964, 18176159.99999997)],
+    Small: [("A", 439349.9999999932, 4234753279.9999814)],
+}
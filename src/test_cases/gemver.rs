@@ -1,14 +0,0 @@
-
-use polybench_rs::linear_algebra::blas::gemver::bench;
-
-fn bench_and_print<const N: usize>() {
-    let dims = format!("{:?}", (N));
-    let elapsed = bench::<N>(|| 0).as_secs_f64();
-    println!("{:<14} | {:<30} | {:.7} s", "gemver", dims, elapsed);
-}
-
-fn main() {
-    bench_and_print::<5000>();
-    bench_and_print::<10000>();
-    bench_and_print::<20000>();
-}
@@ -1,14 +0,0 @@
-
-use polybench_rs::linear_algebra::blas::syr2k::bench;
-
-fn bench_and_print<const M: usize, const N: usize>() {
-    let dims = format!("{:?}", (M, N));
-    let elapsed = bench::<M, N>(|| 0).as_secs_f64();
-    println!("{:<14} | {:<30} | {:.7} s", "syr2k", dims, elapsed);
-}
-
-fn main() {
-    bench_and_print::<250, 300>();
-    bench_and_print::<500, 600>();
-    bench_and_print::<1000, 1200>();
-}
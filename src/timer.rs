@@ -0,0 +1,357 @@
+use core::time::Duration;
+
+/// A monotonic counter that measures the kernels.
+///
+/// The counter may tick at any rate, e.g. in nanoseconds or in cycles; [`Timer::frequency`] tells
+/// how its ticks are converted to time.
+pub trait Timer {
+    /// Returns the current value of the counter.
+    fn ticks(&self) -> u64;
+
+    /// Returns the number of ticks per second.
+    fn frequency(&self) -> u64;
+
+    /// Converts the ticks between two readings of the counter to a [`Duration`].
+    ///
+    /// A counter that went backwards, e.g. a TSC that is not synchronized between cores, yields
+    /// zero instead of overflowing.
+    fn elapsed(&self, begin: u64, end: u64) -> Duration {
+        let ticks = u128::from(end.saturating_sub(begin));
+        let nanos = ticks * 1_000_000_000 / u128::from(self.frequency().max(1));
+        Duration::from_nanos(nanos as u64)
+    }
+
+    /// Called right before the first reading of a measurement, outside the timed region.
+    fn start(&self) {}
+
+    /// Called right after the last reading of a measurement, outside the timed region.
+    fn stop(&self) {}
+}
+
+/// A timer made of a user-supplied function, which is how a timing source is registered on
+/// targets without `std`.
+pub struct FnTimer<F> {
+    ticks: F,
+    frequency: u64,
+}
+
+impl<F: Fn() -> u64> FnTimer<F> {
+    /// `ticks` returns a counter that ticks `frequency` times per second.
+    pub const fn new(ticks: F, frequency: u64) -> FnTimer<F> {
+        FnTimer { ticks, frequency }
+    }
+
+    /// `ticks` returns a counter in nanoseconds.
+    pub const fn nanos(ticks: F) -> FnTimer<F> {
+        FnTimer::new(ticks, 1_000_000_000)
+    }
+}
+
+impl<F: Fn() -> u64> Timer for FnTimer<F> {
+    fn ticks(&self) -> u64 {
+        (self.ticks)()
+    }
+
+    fn frequency(&self) -> u64 {
+        self.frequency
+    }
+}
+
+/// [`std::time::Instant`], in nanoseconds since the timer was created.
+#[cfg(feature = "std")]
+pub struct Instant {
+    start: std::time::Instant,
+}
+
+#[cfg(feature = "std")]
+impl Instant {
+    pub fn new() -> Instant {
+        Instant {
+            start: std::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for Instant {
+    fn default() -> Instant {
+        Instant::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Timer for Instant {
+    fn ticks(&self) -> u64 {
+        self.start.elapsed().as_nanos() as u64
+    }
+
+    fn frequency(&self) -> u64 {
+        1_000_000_000
+    }
+}
+
+/// The clocks of `clock_gettime` that are suitable for benchmarking.
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Clock {
+    /// `CLOCK_MONOTONIC`, which is slewed by NTP.
+    Monotonic,
+    /// `CLOCK_MONOTONIC_RAW`, which is not adjusted at all.
+    MonotonicRaw,
+    /// `CLOCK_THREAD_CPUTIME_ID`, the CPU time consumed by the calling thread.
+    ThreadCputime,
+}
+
+/// Linux's `clock_gettime`, in nanoseconds.
+#[cfg(target_os = "linux")]
+pub struct ClockGettime {
+    clock: Clock,
+}
+
+#[cfg(target_os = "linux")]
+impl ClockGettime {
+    pub const fn new(clock: Clock) -> ClockGettime {
+        ClockGettime { clock }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Timer for ClockGettime {
+    fn ticks(&self) -> u64 {
+        let clock = match self.clock {
+            Clock::Monotonic => libc::CLOCK_MONOTONIC,
+            Clock::MonotonicRaw => libc::CLOCK_MONOTONIC_RAW,
+            Clock::ThreadCputime => libc::CLOCK_THREAD_CPUTIME_ID,
+        };
+        let mut time = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        // The clocks above exist since Linux 2.6.28, so the call cannot fail.
+        unsafe { libc::clock_gettime(clock, &mut time) };
+        time.tv_sec as u64 * 1_000_000_000 + time.tv_nsec as u64
+    }
+
+    fn frequency(&self) -> u64 {
+        1_000_000_000
+    }
+}
+
+/// The time-stamp counter of x86, read with `rdtsc`.
+///
+/// Its frequency is not architecturally visible, so it is measured against another timer by
+/// [`Rdtsc::calibrate`]. The counter is only a clock on CPUs with an invariant TSC.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub struct Rdtsc {
+    frequency: u64,
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+impl Rdtsc {
+    /// Uses a known TSC frequency in Hz.
+    pub const fn with_frequency(frequency: u64) -> Rdtsc {
+        Rdtsc { frequency }
+    }
+
+    /// Measures the TSC frequency by spinning for `duration` of `reference`.
+    ///
+    /// Returns `None` if `reference` stops advancing before `duration` has passed, e.g. because
+    /// it is stuck at zero or runs backwards.
+    pub fn calibrate(reference: &dyn Timer, duration: Duration) -> Option<Rdtsc> {
+        // Readings in a row that may not advance `reference`, as in `Calibration::measure`.
+        const MAX_SPINS: usize = 1 << 24;
+
+        let rdtsc = Rdtsc::with_frequency(0);
+        let reference_begin = reference.ticks();
+        let begin = rdtsc.ticks();
+        let mut elapsed = Duration::ZERO;
+        let mut spins = 0;
+        while elapsed < duration {
+            let now = reference.elapsed(reference_begin, reference.ticks());
+            if now > elapsed {
+                elapsed = now;
+                spins = 0;
+            } else if spins == MAX_SPINS {
+                return None;
+            } else {
+                spins += 1;
+            }
+        }
+        let end = rdtsc.ticks();
+
+        Some(Rdtsc::with_frequency(
+            (end.saturating_sub(begin) as f64 / elapsed.as_secs_f64()) as u64,
+        ))
+    }
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+impl Timer for Rdtsc {
+    fn ticks(&self) -> u64 {
+        #[cfg(target_arch = "x86")]
+        use core::arch::x86::{_mm_lfence, _rdtsc};
+        #[cfg(target_arch = "x86_64")]
+        use core::arch::x86_64::{_mm_lfence, _rdtsc};
+
+        // The fences keep the surrounding instructions from being reordered across the read.
+        unsafe {
+            _mm_lfence();
+            let ticks = _rdtsc();
+            _mm_lfence();
+            ticks
+        }
+    }
+
+    fn frequency(&self) -> u64 {
+        self.frequency
+    }
+}
+
+/// The granularity and the cost of a timer, measured by [`Calibration::measure`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Calibration {
+    /// The smallest step between two readings that differ.
+    pub resolution: Duration,
+    /// The time a single reading takes, which every measurement includes once.
+    pub overhead: Duration,
+}
+
+impl Calibration {
+    /// How many times the resolution a duration must be to be measured reliably.
+    pub const RELIABLE_RESOLUTIONS: u32 = 100;
+
+    /// Measures the resolution as the smallest of 100 steps and the overhead as the mean of
+    /// 10000 back-to-back readings.
+    pub fn measure(timer: &dyn Timer) -> Calibration {
+        const STEPS: usize = 100;
+        const READINGS: u32 = 10_000;
+        // Gives up on timers that do not advance, e.g. one that is stuck at zero.
+        const MAX_SPINS: usize = 1 << 24;
+
+        let mut resolution = Duration::MAX;
+        for _ in 0..STEPS {
+            let begin = timer.ticks();
+            match (0..MAX_SPINS)
+                .map(|_| timer.ticks())
+                .find(|&end| end != begin)
+            {
+                Some(end) => resolution = resolution.min(timer.elapsed(begin, end)),
+                None => break,
+            }
+        }
+
+        let begin = timer.ticks();
+        for _ in 0..READINGS {
+            crate::util::consume(timer.ticks());
+        }
+        let end = timer.ticks();
+        Calibration {
+            resolution: if resolution == Duration::MAX {
+                Duration::ZERO
+            } else {
+                resolution
+            },
+            overhead: timer.elapsed(begin, end) / (READINGS + 1),
+        }
+    }
+
+    /// Returns `true` if `duration` is at least [`Calibration::RELIABLE_RESOLUTIONS`] times the
+    /// resolution.
+    pub fn is_reliable(&self, duration: Duration) -> bool {
+        duration >= self.resolution * Calibration::RELIABLE_RESOLUTIONS
+    }
+}
+
+/// A timer whose measurements do not include the overhead of reading it.
+pub struct Calibrated<'a> {
+    pub timer: &'a dyn Timer,
+    pub calibration: Calibration,
+}
+
+impl<'a> Calibrated<'a> {
+    /// Calibrates `timer`.
+    pub fn new(timer: &'a dyn Timer) -> Calibrated<'a> {
+        Calibrated {
+            timer,
+            calibration: Calibration::measure(timer),
+        }
+    }
+}
+
+impl Timer for Calibrated<'_> {
+    fn ticks(&self) -> u64 {
+        self.timer.ticks()
+    }
+
+    fn frequency(&self) -> u64 {
+        self.timer.frequency()
+    }
+
+    fn elapsed(&self, begin: u64, end: u64) -> Duration {
+        self.timer
+            .elapsed(begin, end)
+            .saturating_sub(self.calibration.overhead)
+    }
+
+    fn start(&self) {
+        self.timer.start();
+    }
+
+    fn stop(&self) {
+        self.timer.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn elapsed_converts_ticks() {
+        let timer = FnTimer::new(|| 0, 3_000_000_000);
+        assert_eq!(Duration::from_secs(2), timer.elapsed(1, 6_000_000_001));
+        assert_eq!(Duration::from_nanos(1), timer.elapsed(0, 3));
+    }
+
+    #[test]
+    fn elapsed_saturates_backwards() {
+        let timer = FnTimer::nanos(|| 0);
+        assert_eq!(Duration::from_secs(0), timer.elapsed(10, 5));
+    }
+
+    #[test]
+    fn calibration() {
+        let ticks = core::cell::Cell::new(0);
+        let timer = FnTimer::nanos(|| {
+            ticks.set(ticks.get() + 5);
+            ticks.get()
+        });
+        let calibrated = Calibrated::new(&timer);
+        assert_eq!(Duration::from_nanos(5), calibrated.calibration.resolution);
+        assert_eq!(Duration::from_nanos(5), calibrated.calibration.overhead);
+        assert_eq!(Duration::from_nanos(95), calibrated.elapsed(0, 100));
+        assert!(!calibrated
+            .calibration
+            .is_reliable(Duration::from_nanos(499)));
+        assert!(calibrated
+            .calibration
+            .is_reliable(Duration::from_nanos(500)));
+
+        let stuck = Calibration::measure(&FnTimer::nanos(|| 0));
+        assert_eq!(Duration::ZERO, stuck.resolution);
+    }
+
+    #[test]
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn rdtsc_calibration_gives_up_on_a_stuck_reference() {
+        let duration = Duration::from_millis(1);
+        assert!(Rdtsc::calibrate(&FnTimer::nanos(|| 0), duration).is_none());
+        let ticks = core::cell::Cell::new(0);
+        let advancing = FnTimer::nanos(|| {
+            ticks.set(ticks.get() + 1000);
+            ticks.get()
+        });
+        let rdtsc = Rdtsc::calibrate(&advancing, duration).unwrap();
+        assert!(rdtsc.frequency() > 0);
+    }
+}
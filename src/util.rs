@@ -1,4 +1,7 @@
+use crate::ndarray::Extent;
+use crate::timer::{self, Timer};
 use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
 use core::time::Duration;
 
 /// An identity function that *__hints__* to the compiler to be maximally pessimistic about what
@@ -19,41 +22,294 @@ pub fn consume<T>(dummy: T) -> T {
     core::hint::black_box(dummy)
 }
 
+/// The size of the buffer that is swept before every measurement by default, when the size of
+/// the last-level cache is not known.
+pub const LLC_CACHE_SIZE: usize = 32 * 1024 * 1024; // 32 MiB
+
+/// How the caches are prepared before every measurement.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CacheFlush {
+    /// The caches are left as they are, like with PolyBench/C's `POLYBENCH_NO_FLUSH_CACHE`.
+    None,
+    /// A buffer of the given number of bytes is written and read, like PolyBench/C's
+    /// `polybench_flush_cache` with `POLYBENCH_CACHE_SIZE_KB`.
+    Sweep(usize),
+    /// The cache lines of the kernel's arrays are flushed with `clflushopt`, or `clflush` on CPUs
+    /// without it.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    Clflush,
+}
+
+impl CacheFlush {
+    /// A sweep over the size of the last-level cache, or over [`LLC_CACHE_SIZE`] if that cannot
+    /// be found.
+    pub fn sweep_llc() -> CacheFlush {
+        #[cfg(all(feature = "std", target_os = "linux"))]
+        if let Some(size) = llc_size() {
+            return CacheFlush::Sweep(size);
+        }
+        CacheFlush::Sweep(LLC_CACHE_SIZE)
+    }
+}
+
+impl core::fmt::Display for CacheFlush {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            CacheFlush::None => f.write_str("none"),
+            CacheFlush::Sweep(size) => write!(f, "sweep {} KiB", size / 1024),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            CacheFlush::Clflush => f.write_str("clflush"),
+        }
+    }
+}
+
+/// The size of the largest cache of the first CPU according to
+/// `/sys/devices/system/cpu/cpu0/cache`.
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub fn llc_size() -> Option<usize> {
+    std::fs::read_dir("/sys/devices/system/cpu/cpu0/cache")
+        .ok()?
+        .filter_map(|entry| {
+            let size = std::fs::read_to_string(entry.ok()?.path().join("size")).ok()?;
+            let size = size.trim();
+            let (digits, unit) = match size.strip_suffix('K') {
+                Some(digits) => (digits, 1024),
+                None => match size.strip_suffix('M') {
+                    Some(digits) => (digits, 1024 * 1024),
+                    None => (size, 1),
+                },
+            };
+            Some(digits.parse::<usize>().ok()? * unit)
+        })
+        .max()
+}
+
+/// The memory that is available for new allocations without swapping, according to the
+/// `MemAvailable` line of `/proc/meminfo`.
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub fn available_memory() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let line = meminfo.lines().find(|l| l.starts_with("MemAvailable:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+/// The policy of every later measurement, encoded as `0` for [`CacheFlush::None`], `usize::MAX`
+/// for [`CacheFlush::Clflush`] and the size otherwise.
+static CACHE_FLUSH: AtomicUsize = AtomicUsize::new(LLC_CACHE_SIZE);
+
+/// Sets how the caches are prepared before every later measurement. The default is a sweep over
+/// [`LLC_CACHE_SIZE`].
+pub fn set_cache_flush(policy: CacheFlush) {
+    let encoded = match policy {
+        CacheFlush::None => 0,
+        CacheFlush::Sweep(size) => size.min(usize::MAX - 1),
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        CacheFlush::Clflush => usize::MAX,
+    };
+    CACHE_FLUSH.store(encoded, Ordering::Relaxed);
+}
+
+/// Returns the policy set by [`set_cache_flush`].
+pub fn cache_flush() -> CacheFlush {
+    match CACHE_FLUSH.load(Ordering::Relaxed) {
+        0 => CacheFlush::None,
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        usize::MAX => CacheFlush::Clflush,
+        size => CacheFlush::Sweep(size),
+    }
+}
+
+/// How the memory of the arrays a kernel allocates is faulted in before the measurement starts.
+///
+/// The arrays are zeroed with every policy, so no uninitialized memory is ever read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Prefault {
+    /// The memory is allocated zeroed but not touched, so that the first write to every page of
+    /// the kernel's outputs faults inside the measurement.
+    None,
+    /// Every byte is written with zero.
+    Zero,
+    /// The zeroed memory is advised with `madvise(MADV_WILLNEED)`.
+    #[cfg(target_os = "linux")]
+    WillNeed,
+    /// The pages are populated writable with `madvise(MADV_POPULATE_WRITE)`, which is what
+    /// `MAP_POPULATE` does for memory that is already mapped. Every byte is written with zero on
+    /// kernels older than Linux 5.14, which lack it.
+    #[cfg(target_os = "linux")]
+    Populate,
+}
+
+impl core::fmt::Display for Prefault {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            Prefault::None => "none",
+            Prefault::Zero => "zero",
+            #[cfg(target_os = "linux")]
+            Prefault::WillNeed => "willneed",
+            #[cfg(target_os = "linux")]
+            Prefault::Populate => "populate",
+        })
+    }
+}
+
+static PREFAULT: AtomicU8 = AtomicU8::new(Prefault::Zero as u8);
+
+/// Sets how the arrays that are allocated later are faulted in. The default is
+/// [`Prefault::Zero`].
+pub fn set_prefault(policy: Prefault) {
+    PREFAULT.store(policy as u8, Ordering::Relaxed);
+}
+
+/// Returns the policy set by [`set_prefault`].
+pub fn prefault() -> Prefault {
+    match PREFAULT.load(Ordering::Relaxed) {
+        0 => Prefault::None,
+        #[cfg(target_os = "linux")]
+        2 => Prefault::WillNeed,
+        #[cfg(target_os = "linux")]
+        3 => Prefault::Populate,
+        _ => Prefault::Zero,
+    }
+}
+
+/// Faults in `len` bytes at `ptr` according to `policy`, which the caller reads from [`prefault`]
+/// once for both the allocation and this call.
+///
+/// # Safety
+///
+/// `ptr` must be valid for writes of `len` bytes. The memory must already be zeroed unless the
+/// policy is [`Prefault::Zero`].
+#[inline(never)]
+pub(crate) unsafe fn prefault_memory(ptr: *mut u8, len: usize, policy: Prefault) {
+    #[cfg(target_os = "linux")]
+    let advise = |advice| {
+        let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        let start = ptr as usize & !(page - 1);
+        let end = ptr as usize + len;
+        unsafe { libc::madvise(start as *mut libc::c_void, end - start, advice) == 0 }
+    };
+
+    match policy {
+        Prefault::None => {}
+        Prefault::Zero => unsafe { core::ptr::write_bytes(ptr, 0, len) },
+        #[cfg(target_os = "linux")]
+        Prefault::WillNeed => {
+            advise(libc::MADV_WILLNEED);
+        }
+        #[cfg(target_os = "linux")]
+        Prefault::Populate => {
+            if !advise(libc::MADV_POPULATE_WRITE) {
+                unsafe { core::ptr::write_bytes(ptr, 0, len) };
+            }
+        }
+    }
+}
+
 /// Optimize the locality by flushing the Last level cache (LLC),
 /// which refers to the highest-numbered cache that is accessed by the cores prior to fetching from memory.
 /// Things get different when we are inside the enclave.
-fn flush_llc_cache() {
+fn flush_llc_cache(size: usize) {
     // Note that when we are in the range of enclave, the memory space must be
     // sufficient to prevent illegitimate writes that cause segmentation fault.
-    const LLC_CACHE_SIZE: usize = 32 * 1024 * 1024; // 32 MiB
-    const NUM_ELEMS: usize = (LLC_CACHE_SIZE - 1) / core::mem::size_of::<usize>() + 1;
+    let num_elems = (size - 1) / core::mem::size_of::<usize>() + 1;
 
-    let mut buf: Vec<usize> = Vec::with_capacity(NUM_ELEMS);
-    buf.resize(NUM_ELEMS, Default::default());
+    let mut buf: Vec<usize> = Vec::with_capacity(num_elems);
+    buf.resize(num_elems, Default::default());
     let sum: usize = buf.iter().sum();
     consume(sum);
 }
 
-/// Avoid invoking this interface.
+/// Evicts the cache lines of `arrays` from every level of the cache hierarchy.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[allow(unused_unsafe)]
+fn flush_arrays(arrays: &[Extent]) {
+    #[cfg(target_arch = "x86")]
+    use core::arch::x86::{__cpuid, __cpuid_count, _mm_clflush, _mm_mfence};
+    #[cfg(target_arch = "x86_64")]
+    use core::arch::x86_64::{__cpuid, __cpuid_count, _mm_clflush, _mm_mfence};
+
+    // CPUID.01H:EBX[15:8] is the line size of `clflush` in quadwords, CPUID.07H:EBX[23] tells
+    // whether `clflushopt` is supported.
+    let line = match unsafe { __cpuid(1) }.ebx >> 8 & 0xff {
+        0 => 64,
+        quadwords => quadwords as usize * 8,
+    };
+    let clflushopt =
+        unsafe { __cpuid(0) }.eax >= 7 && unsafe { __cpuid_count(7, 0) }.ebx & 1 << 23 != 0;
+
+    for array in arrays {
+        let start = array.ptr as usize & !(line - 1);
+        let end = array.ptr as usize + array.len;
+        for address in (start..end).step_by(line) {
+            unsafe {
+                if clflushopt {
+                    core::arch::asm!("clflushopt byte ptr [{}]", in(reg) address);
+                } else {
+                    _mm_clflush(address as *const u8);
+                }
+            }
+        }
+    }
+    unsafe { _mm_mfence() };
+}
+
+/// Measures `f` with [`timer::Instant`].
+#[cfg(feature = "std")]
 #[inline(always)]
 pub fn benchmark<F: FnOnce()>(f: F) -> Duration {
-    flush_llc_cache();
-
-    f();
-    Duration::from_secs(0)
+    benchmark_with_timer(&[], f, &timer::Instant::new())
 }
 
-/// The target platform does not necessarily support std, so the time function should be given
-/// by the user manually as a closure.
+/// The target platform does not necessarily support std, so the timer is given by the user; see
+/// [`timer::FnTimer`] for one made of a plain function.
+///
+/// `arrays` are the arrays `task` works on, which [`CacheFlush::Clflush`] evicts from the caches
+/// beforehand.
 #[inline(always)]
-pub fn benchmark_with_timing_function<F>(task: F, time_function: &dyn Fn() -> u64) -> Duration
+pub fn benchmark_with_timer<F>(arrays: &[Extent], task: F, timer: &dyn Timer) -> Duration
 where
     F: FnOnce(),
 {
-    flush_llc_cache();
+    match cache_flush() {
+        CacheFlush::None => {}
+        CacheFlush::Sweep(size) => flush_llc_cache(size),
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        CacheFlush::Clflush => flush_arrays(arrays),
+    }
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+    let _ = arrays;
 
-    let begin = time_function();
+    timer.start();
+    let begin = timer.ticks();
     task();
-    let end = time_function();
-    Duration::from_nanos(end - begin)
+    let end = timer.ticks();
+    timer.stop();
+    timer.elapsed(begin, end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ndarray::{Contiguous, DynArray2D};
+
+    #[test]
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn cache_flush_policies() {
+        let default = cache_flush();
+        assert_eq!(CacheFlush::Sweep(LLC_CACHE_SIZE), default);
+        for policy in [
+            CacheFlush::None,
+            CacheFlush::Sweep(4096),
+            CacheFlush::Clflush,
+        ] {
+            set_cache_flush(policy);
+            assert_eq!(policy, cache_flush());
+        }
+        set_cache_flush(default);
+
+        let array = DynArray2D::<f64>::zeroed(3, 5);
+        assert_eq!(120, array.extent().len);
+        flush_arrays(&[array.extent()]);
+    }
 }
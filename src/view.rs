@@ -0,0 +1,510 @@
+//! Borrowed views into the fixed-size arrays and iterators over their elements, for blocked
+//! variants of the kernels and for verification code.
+//!
+//! Rows are padded to a multiple of 32 bytes and to their leading dimension, so the views step
+//! from row to row by a number of bytes rather than of elements. Iterators skip the padding.
+//!
+//! Unlike the indexing of the arrays, which kernels rely on and which is only checked in debug
+//! builds, the views always check that an index lies within them.
+
+use crate::ndarray::{Align32, Array1D, Array2, Array2D, Array3D};
+use core::marker::PhantomData;
+use core::mem;
+use core::ops::{Index, IndexMut, Range};
+use core::ptr::NonNull;
+use core::slice;
+
+/// `len` elements that are `stride` bytes apart, such as a column or the diagonal of an
+/// [`Array2D`].
+pub struct Strided<'a, T> {
+    ptr: NonNull<T>,
+    len: usize,
+    stride: usize,
+    array: PhantomData<&'a T>,
+}
+
+/// A mutable [`Strided`].
+pub struct StridedMut<'a, T> {
+    ptr: NonNull<T>,
+    len: usize,
+    stride: usize,
+    array: PhantomData<&'a mut T>,
+}
+
+/// `rows` rows of `cols` elements whose starts are `stride` bytes apart, a block of an
+/// [`Array2D`].
+pub struct Block<'a, T> {
+    ptr: NonNull<T>,
+    rows: usize,
+    cols: usize,
+    stride: usize,
+    array: PhantomData<&'a T>,
+}
+
+/// A mutable [`Block`], which kernels can work on like on a whole array.
+pub struct BlockMut<'a, T> {
+    ptr: NonNull<T>,
+    rows: usize,
+    cols: usize,
+    stride: usize,
+    array: PhantomData<&'a mut T>,
+}
+
+macro_rules! strided {
+    ($($view:ident),*) => {$(
+        impl<'a, T> $view<'a, T> {
+            pub fn len(&self) -> usize {
+                self.len
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.len == 0
+            }
+
+            pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+                (0..self.len).map(move |index| &self[index])
+            }
+
+            /// # Panics
+            ///
+            /// If `index` is not an element of the view.
+            #[inline(always)]
+            fn element(&self, index: usize) -> *mut T {
+                assert!(index < self.len, "element {} of a view of {}", index, self.len);
+                // SAFETY: the elements of the view lie within the array it borrows.
+                unsafe { self.ptr.as_ptr().byte_add(index * self.stride) }
+            }
+        }
+
+        impl<'a, T> Index<usize> for $view<'a, T> {
+            type Output = T;
+
+            #[inline(always)]
+            fn index(&self, index: usize) -> &Self::Output {
+                unsafe { &*self.element(index) }
+            }
+        }
+    )*};
+}
+
+strided!(Strided, StridedMut);
+
+impl<'a, T> StridedMut<'a, T> {
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> + '_ {
+        let (ptr, stride) = (self.ptr.as_ptr(), self.stride);
+        // SAFETY: the stride is at least one element, so no two elements overlap.
+        (0..self.len).map(move |index| unsafe { &mut *ptr.byte_add(index * stride) })
+    }
+}
+
+impl<'a, T> IndexMut<usize> for StridedMut<'a, T> {
+    #[inline(always)]
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        unsafe { &mut *self.element(index) }
+    }
+}
+
+macro_rules! block {
+    ($($view:ident),*) => {$(
+        impl<'a, T> $view<'a, T> {
+            /// Returns the dimensions as `(rows, columns)`.
+            pub fn dims(&self) -> (usize, usize) {
+                (self.rows, self.cols)
+            }
+
+            /// # Panics
+            ///
+            /// If `i` is not a row of the block.
+            pub fn row(&self, i: usize) -> &[T] {
+                // SAFETY: the row lies within the array the block borrows.
+                unsafe { slice::from_raw_parts(self.start(i), self.cols) }
+            }
+
+            pub fn rows(&self) -> impl Iterator<Item = &[T]> + '_ {
+                (0..self.rows).map(move |i| self.row(i))
+            }
+
+            /// Returns the elements in row-major order.
+            pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+                self.rows().flatten()
+            }
+
+            /// Returns the elements in row-major order together with their row and column in the
+            /// block.
+            pub fn indexed_iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> + '_ {
+                indexed(self.rows())
+            }
+
+            /// # Panics
+            ///
+            /// If `i` is not a row of the block.
+            #[inline(always)]
+            fn start(&self, i: usize) -> *mut T {
+                assert!(i < self.rows, "row {} of a block of {} rows", i, self.rows);
+                // SAFETY: the rows of the block lie within the array it borrows.
+                unsafe { self.ptr.as_ptr().byte_add(i * self.stride) }
+            }
+        }
+
+        impl<'a, T> Index<usize> for $view<'a, T> {
+            type Output = [T];
+
+            #[inline(always)]
+            fn index(&self, index: usize) -> &Self::Output {
+                self.row(index)
+            }
+        }
+    )*};
+}
+
+block!(Block, BlockMut);
+
+impl<'a, T> BlockMut<'a, T> {
+    /// # Panics
+    ///
+    /// If `i` is not a row of the block.
+    pub fn row_mut(&mut self, i: usize) -> &mut [T] {
+        // SAFETY: the row lies within the array the block borrows mutably.
+        unsafe { slice::from_raw_parts_mut(self.start(i), self.cols) }
+    }
+
+    pub fn rows_mut(&mut self) -> impl Iterator<Item = &mut [T]> + '_ {
+        let (ptr, cols, stride) = (self.ptr.as_ptr(), self.cols, self.stride);
+        // SAFETY: rows never overlap.
+        (0..self.rows)
+            .map(move |i| unsafe { slice::from_raw_parts_mut(ptr.byte_add(i * stride), cols) })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> + '_ {
+        self.rows_mut().flatten()
+    }
+}
+
+impl<'a, T> IndexMut<usize> for BlockMut<'a, T> {
+    #[inline(always)]
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        self.row_mut(index)
+    }
+}
+
+impl<'a, T> Array2<T> for BlockMut<'a, T> {
+    type Row = [T];
+}
+
+/// Numbers the elements of `rows`.
+fn indexed<T, R>(rows: impl Iterator<Item = R>) -> impl Iterator<Item = ((usize, usize), T)>
+where
+    R: IntoIterator<Item = T>,
+{
+    rows.enumerate()
+        .flat_map(|(i, row)| row.into_iter().enumerate().map(move |(j, x)| ((i, j), x)))
+}
+
+impl<T, const M: usize, A> Array1D<T, M, A> {
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.0.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> slice::IterMut<'_, T> {
+        self.0.iter_mut()
+    }
+
+    /// Returns the elements together with their index.
+    pub fn indexed_iter(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.0.iter().enumerate()
+    }
+}
+
+impl<T, const M: usize, const N: usize, A, const LD: usize> Array2D<T, M, N, A, LD> {
+    /// The bytes from the start of a row to the start of the next.
+    const ROW: usize = mem::size_of::<Array1D<T, LD>>();
+
+    /// Returns the `N` elements of row `i` without its padding.
+    ///
+    /// # Panics
+    ///
+    /// If `i >= M`.
+    pub fn row(&self, i: usize) -> &[T] {
+        &self.0[i].0[..N]
+    }
+
+    /// Like [`Array2D::row`], but mutable.
+    pub fn row_mut(&mut self, i: usize) -> &mut [T] {
+        &mut self.0[i].0[..N]
+    }
+
+    /// Returns the `M` elements of column `j`.
+    ///
+    /// # Panics
+    ///
+    /// If `j >= N`.
+    pub fn col(&self, j: usize) -> Strided<'_, T> {
+        assert!(j < N, "column {} of an array of {} columns", j, N);
+        Strided {
+            // SAFETY: `j` is within the first row.
+            ptr: unsafe { NonNull::from(self).cast::<T>().add(j) },
+            len: M,
+            stride: Self::ROW,
+            array: PhantomData,
+        }
+    }
+
+    /// Like [`Array2D::col`], but mutable.
+    pub fn col_mut(&mut self, j: usize) -> StridedMut<'_, T> {
+        assert!(j < N, "column {} of an array of {} columns", j, N);
+        StridedMut {
+            // SAFETY: as above.
+            ptr: unsafe { NonNull::from(self).cast::<T>().add(j) },
+            len: M,
+            stride: Self::ROW,
+            array: PhantomData,
+        }
+    }
+
+    /// Returns the elements `(i, i)` of the leading diagonal.
+    pub fn diag(&self) -> Strided<'_, T> {
+        Strided {
+            ptr: NonNull::from(self).cast(),
+            len: M.min(N),
+            stride: Self::ROW + mem::size_of::<T>(),
+            array: PhantomData,
+        }
+    }
+
+    /// Like [`Array2D::diag`], but mutable.
+    pub fn diag_mut(&mut self) -> StridedMut<'_, T> {
+        StridedMut {
+            ptr: NonNull::from(self).cast(),
+            len: M.min(N),
+            stride: Self::ROW + mem::size_of::<T>(),
+            array: PhantomData,
+        }
+    }
+
+    /// Returns the start of the block of `rows` and `cols`, after checking that it lies within
+    /// the array.
+    fn corner(base: NonNull<T>, rows: &Range<usize>, cols: &Range<usize>) -> NonNull<T> {
+        assert!(
+            rows.start <= rows.end && rows.end <= M && cols.start <= cols.end && cols.end <= N,
+            "block {:?} × {:?} of an array of {} × {}",
+            rows,
+            cols,
+            M,
+            N
+        );
+        // SAFETY: the corner is at most one past the end of the array.
+        unsafe { base.byte_add(rows.start * Self::ROW).add(cols.start) }
+    }
+
+    /// Returns the elements in `rows` and `cols`.
+    ///
+    /// # Panics
+    ///
+    /// If the block does not lie within the `M` × `N` elements.
+    pub fn block(&self, rows: Range<usize>, cols: Range<usize>) -> Block<'_, T> {
+        Block {
+            ptr: Self::corner(NonNull::from(self).cast(), &rows, &cols),
+            rows: rows.len(),
+            cols: cols.len(),
+            stride: Self::ROW,
+            array: PhantomData,
+        }
+    }
+
+    /// Like [`Array2D::block`], but mutable.
+    pub fn block_mut(&mut self, rows: Range<usize>, cols: Range<usize>) -> BlockMut<'_, T> {
+        BlockMut {
+            ptr: Self::corner(NonNull::from(self).cast(), &rows, &cols),
+            rows: rows.len(),
+            cols: cols.len(),
+            stride: Self::ROW,
+            array: PhantomData,
+        }
+    }
+
+    /// Returns the rows without their padding.
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.0.iter().map(|row| &row.0[..N])
+    }
+
+    /// Like [`Array2D::rows`], but mutable.
+    pub fn rows_mut(&mut self) -> impl Iterator<Item = &mut [T]> {
+        self.0.iter_mut().map(|row| &mut row.0[..N])
+    }
+
+    /// Returns the elements in row-major order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.rows().flatten()
+    }
+
+    /// Like [`Array2D::iter`], but mutable.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.rows_mut().flatten()
+    }
+
+    /// Returns the elements in row-major order together with their row and column.
+    pub fn indexed_iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        indexed(self.rows())
+    }
+}
+
+impl<T, const M: usize, const N: usize, const P: usize, A, const LD: usize>
+    Array3D<T, M, N, P, A, LD>
+{
+    pub fn planes(&self) -> slice::Iter<'_, Array2D<T, N, P, Align32, LD>> {
+        self.0.iter()
+    }
+
+    pub fn planes_mut(&mut self) -> slice::IterMut<'_, Array2D<T, N, P, Align32, LD>> {
+        self.0.iter_mut()
+    }
+
+    /// Returns the elements of plane `k` in `rows` and `cols`.
+    ///
+    /// # Panics
+    ///
+    /// If `k` is not below `M` or the block does not lie within the `N` × `P` elements.
+    pub fn block(&self, k: usize, rows: Range<usize>, cols: Range<usize>) -> Block<'_, T> {
+        self.0[k].block(rows, cols)
+    }
+
+    /// Like [`Array3D::block`], but mutable.
+    pub fn block_mut(
+        &mut self,
+        k: usize,
+        rows: Range<usize>,
+        cols: Range<usize>,
+    ) -> BlockMut<'_, T> {
+        self.0[k].block_mut(rows, cols)
+    }
+
+    /// Returns the rows of all planes, one plane after the other, without their padding.
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.0.iter().flat_map(Array2D::rows)
+    }
+
+    /// Like [`Array3D::rows`], but mutable.
+    pub fn rows_mut(&mut self) -> impl Iterator<Item = &mut [T]> {
+        self.0.iter_mut().flat_map(Array2D::rows_mut)
+    }
+
+    /// Returns the elements in row-major order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.0.iter().flat_map(Array2D::iter)
+    }
+
+    /// Like [`Array3D::iter`], but mutable.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.0.iter_mut().flat_map(Array2D::iter_mut)
+    }
+
+    /// Returns the elements in row-major order together with their plane, row and column.
+    pub fn indexed_iter(&self) -> impl Iterator<Item = ((usize, usize, usize), &T)> {
+        self.0
+            .iter()
+            .enumerate()
+            .flat_map(|(k, plane)| plane.indexed_iter().map(move |((i, j), x)| ((k, i, j), x)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ndarray::{Align64, ArrayAlloc};
+
+    /// A 3 × 4 array with two elements of padding after every row, holding 1 to 12.
+    fn numbered() -> alloc::boxed::Box<Array2D<usize, 3, 4, Align64, 6>> {
+        let mut a = Array2D::<usize, 3, 4, Align64, 6>::maybe_uninit_zeroed();
+        for (x, value) in a.iter_mut().zip(1..) {
+            *x = value;
+        }
+        a
+    }
+
+    #[test]
+    fn rows_and_elements() {
+        let a = numbered();
+        assert_eq!([5, 6, 7, 8], a.row(1));
+        assert!(a.rows().flatten().copied().eq(1..=12));
+        assert!(a.0.iter().all(|row| row.0[4..] == [0, 0]));
+        assert_eq!(Some(((2, 1), &10)), a.indexed_iter().nth(9));
+
+        let mut b = Array3D::<usize, 2, 3, 4>::maybe_uninit_zeroed();
+        for (x, value) in b.iter_mut().zip(1..) {
+            *x = value;
+        }
+        assert!(b
+            .indexed_iter()
+            .all(|((k, i, j), &x)| x == (k * 3 + i) * 4 + j + 1));
+        assert!(b.planes().map(|plane| plane[2][3]).eq([12, 24]));
+        assert_eq!(6, b.rows().count());
+        assert_eq!([17, 18, 19, 20], b.rows().nth(4).unwrap());
+        b.rows_mut().for_each(|row| row[0] = 0);
+        assert!(b.block(1, 1..3, 0..2).iter().copied().eq([0, 18, 0, 22]));
+        b.block_mut(0, 0..1, 1..4).iter_mut().for_each(|x| *x = 0);
+        assert!(b.iter().take(5).all(|&x| x == 0));
+    }
+
+    #[test]
+    fn columns_and_diagonals() {
+        let mut a = numbered();
+        assert!(a.col(2).iter().copied().eq([3, 7, 11]));
+        assert!(a.diag().iter().copied().eq([1, 6, 11]));
+        a.col_mut(3).iter_mut().for_each(|x| *x = 0);
+        a.diag_mut()[2] = 0;
+        assert_eq!([9, 10, 0, 0], a.row(2));
+
+        // Rows of odd-sized elements are padded to 32 bytes, which is no multiple of them.
+        let mut b = Array2D::<[u8; 3], 2, 2>::maybe_uninit_zeroed();
+        b[1][0] = [1, 2, 3];
+        assert_eq!([1, 2, 3], b.col(0)[1]);
+    }
+
+    /// Doubles every element of `a` through the indexing of the kernels.
+    fn double(rows: usize, cols: usize, a: &mut impl Array2<usize>) {
+        for i in 0..rows {
+            for j in 0..cols {
+                a[i][j] *= 2;
+            }
+        }
+    }
+
+    #[test]
+    fn blocks() {
+        let mut a = numbered();
+        let block = a.block(1..3, 1..4);
+        assert_eq!((2, 3), block.dims());
+        assert!(block.iter().copied().eq([6, 7, 8, 10, 11, 12]));
+        assert_eq!(Some(((1, 0), &10)), block.indexed_iter().nth(3));
+        assert_eq!(11, block[1][1]);
+
+        let mut block = a.block_mut(0..2, 2..4);
+        double(2, 2, &mut block);
+        block.row_mut(0)[0] += 1;
+        assert!(a
+            .iter()
+            .copied()
+            .eq([1, 2, 7, 8, 5, 6, 14, 16, 9, 10, 11, 12]));
+        assert_eq!(0, a.block(3..3, 0..4).iter().count());
+    }
+
+    #[test]
+    #[should_panic]
+    fn blocks_are_bounds_checked() {
+        numbered().block(0..2, 3..5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn block_rows_are_bounds_checked() {
+        let a = numbered();
+        let _ = a.block(0..2, 0..2)[0][2];
+    }
+
+    #[test]
+    #[should_panic]
+    fn strided_views_are_bounds_checked() {
+        let a = numbered();
+        let _ = a.col(0)[3];
+    }
+}
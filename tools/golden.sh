@@ -0,0 +1,59 @@
+#!/bin/sh
+# Reference checksums for the `check` tests of the kernels, computed from PolyBench/C itself.
+#
+#     tools/golden.sh polybench-c-4.2.1-beta.tar.gz > golden.txt
+#
+# The archive is the release from https://sourceforge.net/projects/polybench/. Every benchmark in
+# its utilities/benchmark_list is built with -DPOLYBENCH_DUMP_ARRAYS at the MINI and SMALL sizes
+# and run. For every array in the dump this prints the sum of its values and the sum of its values
+# weighted by their 1-based position in the dump, like `Checksums` in src/dump.rs.
+#
+# The sources are used unchanged except for DATA_PRINTF_MODIFIER in the headers of the kernels:
+# PolyBench/C dumps floating-point values with two decimals, which is too coarse for the checksums,
+# so they are printed with enough digits to read back every value exactly. The first line of the
+# output is the SHA-256 of the archive, to record which release the values come from.
+set -eu
+
+if [ $# -ne 1 ]; then
+    echo "usage: $0 polybench-c-4.2.1-beta.tar.gz" >&2
+    exit 2
+fi
+
+CC=${CC:-cc}
+work=$(mktemp -d)
+trap 'rm -rf "$work"' EXIT
+
+sha256sum "$1" | cut -d' ' -f1 | sed 's/^/# sha256 /'
+tar -xzf "$1" -C "$work"
+root=$(find "$work" -name benchmark_list -path '*/utilities/*' | head -n 1 | xargs dirname | xargs dirname)
+
+# %0.2lf and %0.2f become %0.17g and %0.9g, the shortest formats that round-trip a double and a
+# float.
+find "$root" -name '*.h' ! -path '*/utilities/*' -exec \
+    sed -i -e 's/"%0\.2lf "/"%0.17g "/' -e 's/"%0\.2f "/"%0.9g "/' {} +
+
+while read -r source; do
+    dir=$root/$(dirname "$source")
+    name=$(basename "$source" .c)
+    for size in MINI SMALL; do
+        # -O0 and no contraction keep the operations in the order of the C source, as in the
+        # kernels of this crate.
+        "$CC" -O0 -ffp-contract=off -I "$root/utilities" -I "$dir" \
+            -DPOLYBENCH_DUMP_ARRAYS "-D${size}_DATASET" \
+            "$root/utilities/polybench.c" "$dir/$name.c" -o "$work/bench" -lm
+        echo "$name $size"
+        "$work/bench" 2>&1 >/dev/null | awk '
+            {
+                for (f = 1; f <= NF; f++) {
+                    if ($f == "begin" && $(f + 1) == "dump:") {
+                        array = $(f + 2); f += 2; pos = 0; sum = 0; weighted = 0
+                    } else if ($f == "end" && $(f + 1) == "dump:") {
+                        printf "    (\"%s\", %.17e, %.17e),\n", array, sum, weighted
+                        array = ""; f += 2
+                    } else if (array != "") {
+                        pos++; sum += $f; weighted += $f * pos
+                    }
+                }
+            }'
+    done
+done < "$root/utilities/benchmark_list"